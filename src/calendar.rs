@@ -0,0 +1,268 @@
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use ical::IcalParser;
+use serde::{Deserialize, Deserializer, de::Error};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{LazyLock, OnceLock},
+};
+
+use crate::deadline::parse_weekday;
+
+/// Where `eow` and `end of week` fall.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeekEnd {
+    /// The end of the calendar week.
+    #[default]
+    Sunday,
+    /// The last working day of the week.
+    LastWorkday,
+}
+
+/// The working calendar, which decides the days counted by business day offsets such as
+/// `+5bd`. It is read from the `calendar` section of `config.json`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Calendar {
+    /// The days of the week that are worked.
+    #[serde(deserialize_with = "weekdays")]
+    pub workdays: Vec<Weekday>,
+    /// Days off, in addition to the days of the week that are not worked.
+    pub holidays: BTreeSet<NaiveDate>,
+    /// A file of further holidays: either an iCalendar file, whose events are days off, or a
+    /// text file with a date at the start of each line. Relative paths are relative to the
+    /// directory of `config.json`.
+    pub holiday_file: Option<PathBuf>,
+    /// Where `eow` and `end of week` fall.
+    pub end_of_week: WeekEnd,
+    /// Whether the time until a deadline also counts the working days left.
+    pub count_workdays: bool,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self {
+            workdays: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            holidays: BTreeSet::new(),
+            holiday_file: None,
+            end_of_week: WeekEnd::default(),
+            count_workdays: false,
+        }
+    }
+}
+
+static CALENDAR: OnceLock<Calendar> = OnceLock::new();
+
+impl Calendar {
+    /// The calendar in use, which is the default one until another is set.
+    pub fn current() -> &'static Self {
+        static DEFAULT: LazyLock<Calendar> = LazyLock::new(Calendar::default);
+        CALENDAR.get().unwrap_or(&DEFAULT)
+    }
+
+    /// Use this calendar for the rest of the process. Only the first call has an effect.
+    pub fn set(self) {
+        let _ = CALENDAR.set(self);
+    }
+
+    /// Check the calendar and add the holidays from its holiday file, if it has one.
+    pub fn load(&mut self, dir: &Path) -> Result<()> {
+        if self.workdays.is_empty() {
+            bail!("The calendar needs at least one workday");
+        }
+
+        let Some(file) = &self.holiday_file else {
+            return Ok(());
+        };
+        let path = dir.join(file);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read the holiday file {}", path.display()))?;
+        let holidays = if content.trim_start().starts_with("BEGIN:VCALENDAR") {
+            parse_ics(&content)
+        } else {
+            parse_text(&content)
+        }
+        .with_context(|| format!("Invalid holiday file {}", path.display()))?;
+
+        self.holidays.extend(holidays);
+        Ok(())
+    }
+
+    pub fn is_workday(&self, date: NaiveDate) -> bool {
+        self.workdays.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+
+    /// Move a date by a number of working days, backwards if `n` is negative.
+    pub fn add_workdays(&self, date: NaiveDate, n: i64) -> NaiveDate {
+        let step = Duration::days(n.signum());
+        let mut date = date;
+        for _ in 0..n.unsigned_abs() {
+            date += step;
+            while !self.is_workday(date) {
+                date += step;
+            }
+        }
+        date
+    }
+
+    /// The number of working days after `from`, up to and including `to`.
+    pub fn workdays_until(&self, from: NaiveDate, to: NaiveDate) -> i64 {
+        from.iter_days()
+            .skip(1)
+            .take_while(|date| *date <= to)
+            .filter(|date| self.is_workday(*date))
+            .count() as i64
+    }
+
+    /// The end of the week that starts on `monday`: its Sunday, or its last working day if the
+    /// calendar says so and the week has one.
+    pub fn end_of_week(&self, monday: NaiveDate) -> NaiveDate {
+        let sunday = monday + Duration::days(6);
+        match self.end_of_week {
+            WeekEnd::Sunday => sunday,
+            WeekEnd::LastWorkday => sunday
+                .iter_days()
+                .rev()
+                .take(7)
+                .find(|date| self.is_workday(*date))
+                .unwrap_or(sunday),
+        }
+    }
+}
+
+fn weekdays<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Weekday>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|day| {
+            parse_weekday(&day.to_lowercase())
+                .ok_or_else(|| D::Error::custom(format!("invalid weekday '{day}'")))
+        })
+        .collect()
+}
+
+/// The dates in a text file, one at the start of each line. Blank lines and lines starting
+/// with `#` are skipped, and anything after the date, such as the name of the holiday, is
+/// ignored.
+fn parse_text(content: &str) -> Result<Vec<NaiveDate>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let date = line.split_whitespace().next().unwrap_or_default();
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| anyhow!("Invalid date '{date}', expected YYYY-MM-DD"))
+        })
+        .collect()
+}
+
+/// The days covered by the events of an iCalendar file. An event without a `DTEND` covers its
+/// start day only, and the `DTEND` of a whole-day event is the day after it ends.
+fn parse_ics(content: &str) -> Result<Vec<NaiveDate>> {
+    let mut dates = Vec::new();
+
+    for calendar in IcalParser::new(content.as_bytes()) {
+        let calendar = calendar.map_err(|e| anyhow!("Invalid iCalendar file: {e}"))?;
+
+        for event in &calendar.events {
+            let date = |name: &str| -> Result<Option<NaiveDate>> {
+                let Some(value) = event
+                    .properties
+                    .iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(name))
+                    .and_then(|p| p.value.as_deref())
+                else {
+                    return Ok(None);
+                };
+                let day = value.get(..8).unwrap_or(value);
+                NaiveDate::parse_from_str(day, "%Y%m%d")
+                    .map(Some)
+                    .map_err(|_| anyhow!("Invalid date '{value}'"))
+            };
+
+            let Some(start) = date("DTSTART")? else {
+                continue;
+            };
+            let end = date("DTEND")?.filter(|end| *end > start);
+            dates.extend(start.iter_days().take_while(|day| match end {
+                Some(end) => *day < end,
+                None => *day == start,
+            }));
+        }
+    }
+
+    Ok(dates)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(input: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(input, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_workdays_and_holidays() {
+        let calendar = Calendar {
+            workdays: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu],
+            holidays: [date("2026-10-20")].into(),
+            ..Calendar::default()
+        };
+
+        // 2026-10-15 is a Thursday.
+        assert_eq!(
+            calendar.add_workdays(date("2026-10-15"), 1),
+            date("2026-10-19")
+        );
+        assert_eq!(
+            calendar.add_workdays(date("2026-10-15"), 2),
+            date("2026-10-21")
+        );
+        assert_eq!(
+            calendar.add_workdays(date("2026-10-21"), -2),
+            date("2026-10-15")
+        );
+        assert_eq!(
+            calendar.workdays_until(date("2026-10-15"), date("2026-10-22")),
+            3
+        );
+        assert_eq!(calendar.end_of_week(date("2026-10-12")), date("2026-10-18"));
+
+        let calendar = Calendar {
+            end_of_week: WeekEnd::LastWorkday,
+            holidays: [date("2026-10-16")].into(),
+            ..Calendar::default()
+        };
+        assert_eq!(calendar.end_of_week(date("2026-10-12")), date("2026-10-15"));
+    }
+
+    #[test]
+    fn test_holiday_files() {
+        let text = "# Public holidays\n2026-12-25 Christmas Day\n\n2026-12-28\n";
+        assert_eq!(
+            parse_text(text).unwrap(),
+            vec![date("2026-12-25"), date("2026-12-28")]
+        );
+        assert!(parse_text("25/12/2026").is_err());
+
+        let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nSUMMARY:Christmas\r\nDTSTART;VALUE=DATE:20261225\r\n\
+            DTEND;VALUE=DATE:20261227\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nSUMMARY:New Year\r\nDTSTART;VALUE=DATE:20270101\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        assert_eq!(
+            parse_ics(ics).unwrap(),
+            vec![date("2026-12-25"), date("2026-12-26"), date("2027-01-01")]
+        );
+    }
+}
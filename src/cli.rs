@@ -1,22 +1,35 @@
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::Local;
 use clap::{Parser, Subcommand};
 use dialoguer::Confirm;
-use std::path::PathBuf;
+use std::{fs, path::PathBuf};
 
 use crate::{
     db::Database,
-    display::{list_tasks, Column, ViewMode},
-    task::Task,
+    deadline::{DateFormat, Deadline, Span},
+    display::{
+        Column, GroupBy, ViewMode, agenda, board, calendar_month, list_tasks, list_trash,
+        print_tasks, time_report,
+    },
+    formats::{
+        ExportFormat, ImportFormat, ImportMode, csv, ics, json, markdown, taskwarrior, todotxt,
+    },
+    migrations,
+    recurrence::Recurrence,
+    task::{ID, Status, Task},
+    tracking::format_duration,
 };
 
 #[derive(Debug, Parser)]
 #[command(author,version,about = "A task management and productivity CLI tool",long_about = None)]
 #[command(after_help = r#"EXAMPLES:
     todo add "Fix bug" --diff 5 --deadline friday --tags work,urgent
+    todo add "Deploy" --blocked-by abc1234
     todo list --view minimal
     todo complete abc123
     todo remove def456 ghi789
-    todo remove --tags work"#)]
+    todo remove --tags work
+    todo undo"#)]
 #[command(arg_required_else_help = true)]
 pub struct Cli {
     #[command(subcommand)]
@@ -24,6 +37,9 @@ pub struct Cli {
     /// The path to the task database directory
     #[arg(short = 'p')]
     pub path: Option<PathBuf>,
+    /// How numeric dates are read and shown, overriding `date_format` in config.json
+    #[arg(long, global = true, value_name = "FORMAT")]
+    pub date_format: Option<DateFormat>,
 }
 
 const DEADLINE_HELP: &str = r"Deadline for the task
@@ -33,33 +49,78 @@ Supported formats:
     today               - Today's date
     tomorrow, tmr       - Tomorrow
     monday, mon         - Next Monday (or any weekday)
+    this friday         - Friday this week (Monday to Sunday)
+    next friday         - Friday next week
     
   Relative:
-    +5d, 5d             - 5 days from now
+    +5d, 5d, in 5 days  - 5 days from now
     +2w, 2weeks         - 2 weeks from now
-    +1m, 1month         - 1 month from now
+    +1m, in a month     - 1 calendar month from now (Jan 31 becomes Feb 28)
+    +1q, +1y            - 1 quarter or year from now
+    in 2 weekdays, +2bd - 2 working days from now, skipping weekends and holidays
     
   Special:
-    eow, endofweek      - End of current week (Sunday)
+    eow, endofweek      - End of current week (Sunday, or the last working day)
     eom, endofmonth     - Last day of current month
+    eoq, endofquarter   - Last day of current quarter
     eoy, endofyear      - December 31st
+    next month          - The start of next week, month, quarter or year
+    end of next month   - The end of this, next or last week, month, quarter or year
     
   Exact dates:
     2026-02-10          - ISO format (YYYY-MM-DD)
-    02/10/2026          - UK format (DD/MM/YYYY)
-    10-02-2026          - US format (MM-DD-YYYY)
+    10/02/2026          - Day first with --date-format uk
+    02/10/2026          - Month first with --date-format us
+                          (dates that could be read either way are refused otherwise)
+
+  Times of day (local unless a timezone is given):
+    friday 15:00        - A date and a time
+    tomorrow at 9am     - A time with am or pm
+    16:00               - A time today
+    friday 15:00 utc    - A time in UTC or at an offset like +02:00
     
+  Try an expression with `todo date <expression>`.
+
   Examples:
     --deadline today
     --deadline friday
     --deadline +5d
-    --deadline 2026-12-31";
+    --deadline 2026-12-31
+    --deadline 'tomorrow 9am'
+    --deadline 'end of next month'";
+
+const SCHEDULED_HELP: &str = r"Hide the task until this date. Deferred tasks are left out of `todo list`
+and `todo next` until the date arrives (see deadline formats in `todo list --help`)
+
+  Examples:
+    --scheduled monday
+    --scheduled +2w";
 
 const TAGS_HELP: &str = r"Tags associated with a task
   Examples:
     --tags work
     --tags work,project";
 
+const RECUR_HELP: &str = r#"Repeat the task on a schedule. Completing the task creates the next task
+in its series, with the next deadline. Defaults the deadline to the first
+occurrence if none is given.
+
+Supported rules:
+  daily                 - Every day
+  weekdays              - Every Monday to Friday
+  weekly                - Every week on the weekday of the deadline
+  weekly on mon,thu     - Every week on the given weekdays
+  every friday          - Every week on a weekday
+  monthly               - Every month on the day of the deadline
+  monthly on 15         - Every month on the given day
+  every 3d, every 2w    - A fixed interval after the previous deadline
+  5d after completion   - A fixed interval after the task is completed
+
+  Examples:
+    --recur weekly --deadline friday
+    --recur "monthly on 1"
+    --recur "2w after completion""#;
+
 fn pid_validator(s: &str) -> Result<String, String> {
     if s.len() == 7 {
         Ok(s.to_owned())
@@ -68,6 +129,16 @@ fn pid_validator(s: &str) -> Result<String, String> {
     }
 }
 
+/// The IDs given to `--blocked-by`, ignoring empty values so that `--blocked-by ""` removes
+/// every dependency.
+fn blocker_ids(ids: Vec<String>) -> Vec<ID> {
+    ids.into_iter()
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .map(ID::from)
+        .collect()
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     #[command(about = "Add a task")]
@@ -96,11 +167,54 @@ pub enum Commands {
         #[arg(short, long, value_name = "PARENT_ID")]
         #[arg(value_parser = pid_validator)]
         pid: Option<String>,
+
+        /// The IDs of tasks that must be completed before this task can be started
+        #[arg(short, long, value_delimiter = ',', value_name = "IDs")]
+        blocked_by: Option<Vec<String>>,
+
+        #[arg(short, long, value_name = "RULE")]
+        #[arg(long_help = RECUR_HELP)]
+        recur: Option<String>,
+
+        #[arg(short, long, value_name = "DATE")]
+        #[arg(long_help = SCHEDULED_HELP)]
+        scheduled: Option<String>,
     },
     #[command(alias = "done", about = "Mark a task as complete")]
     Complete { id: String },
-    #[command(alias = "undo", about = "Mark a completed task as incomplete")]
+    #[command(alias = "reopen", about = "Mark a completed task as incomplete")]
     Incomplete { id: String },
+    #[command(about = "Mark a task as in progress")]
+    Start { id: String },
+    #[command(about = "Mark a task as waiting, for example on someone else")]
+    #[command(after_help = r"EXAMPLES:
+  todo wait abc1234
+  todo wait abc1234 --until friday")]
+    Wait {
+        id: String,
+
+        /// Return the task to todo on this date (see deadline formats in `todo list --help`)
+        #[arg(long, value_name = "DEADLINE")]
+        until: Option<String>,
+    },
+    #[command(about = "Move a started or waiting task back to todo")]
+    Stop { id: String },
+    #[command(about = "Hide a task until a date")]
+    #[command(after_help = r"EXAMPLES:
+  todo defer abc1234 monday
+  todo defer abc1234 +2w
+  todo defer abc1234 --clear")]
+    Defer {
+        id: String,
+
+        /// The date the task shows up again (see deadline formats in `todo list --help`)
+        #[arg(value_name = "DATE", required_unless_present = "clear")]
+        when: Option<String>,
+
+        /// Make a deferred task visible again
+        #[arg(long, conflicts_with = "when")]
+        clear: bool,
+    },
     #[command(about = "Update a task (only specified fields are changed)")]
     Update {
         id: String,
@@ -128,22 +242,145 @@ pub enum Commands {
         /// The parent task id if this is a subtask
         #[arg(short, long, value_name = "PARENT_ID")]
         pid: Option<String>,
+
+        /// The IDs of tasks that must be completed before this task can be started. Replaces
+        /// the existing dependencies, and an empty list ("") removes them.
+        #[arg(short, long, value_delimiter = ',', value_name = "IDs")]
+        blocked_by: Option<Vec<String>>,
+
+        #[arg(short, long, value_name = "RULE", conflicts_with = "no_recur")]
+        #[arg(long_help = RECUR_HELP)]
+        recur: Option<String>,
+
+        /// Stop the task from recurring
+        #[arg(long)]
+        no_recur: bool,
+
+        #[arg(short, long, value_name = "DATE")]
+        #[arg(long_help = SCHEDULED_HELP)]
+        scheduled: Option<String>,
+    },
+    #[command(about = "Show the next task to undertake based on task status and urgency")]
+    #[command(
+        long_about = "Show the next task to undertake. Tasks in progress come first, \
+then the task with the highest urgency. Blocked, waiting and deferred tasks are skipped.
+
+Urgency is made up of the deadline, how overdue the task is, its difficulty, its age, its tags \
+and whether it blocks or is blocked by other tasks. The weight of each factor can be changed in \
+the `urgency` section of config.json."
+    )]
+    #[command(after_help = r"EXAMPLES:
+  todo next --tags work
+  todo next -n 5 --before eow
+  todo next -n 3 --skip-parents --view minimal
+  todo next --explain")]
+    Next {
+        /// The number of tasks to show, ranked from most to least urgent
+        #[arg(
+            short = 'n',
+            long = "count",
+            default_value_t = 1,
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        count: usize,
+
+        /// Show the tasks as a table in this view, which is compact for more than one task
+        #[arg(short, long)]
+        view: Option<ViewMode>,
+
+        /// Show the tasks as a table with these columns
+        #[arg(short, long, value_delimiter = ',', conflicts_with = "view")]
+        columns: Option<Vec<Column>>,
+
+        /// Only consider tasks with specific tags
+        #[arg(short, long, value_delimiter = ',', conflicts_with = "pid")]
+        tags: Option<Vec<String>>,
+
+        /// Only consider the subtasks of a parent task
+        #[arg(short, long, conflicts_with = "tags")]
+        pid: Option<String>,
+
+        /// Only consider tasks due before this date (see deadline formats in `todo list --help`)
+        #[arg(long, value_name = "DEADLINE")]
+        before: Option<String>,
+
+        /// Only consider tasks due after this date (see deadline formats in `todo list --help`)
+        #[arg(long, value_name = "DEADLINE")]
+        after: Option<String>,
+
+        /// Skip parent tasks that still have incomplete subtasks
+        #[arg(long)]
+        skip_parents: bool,
+
+        /// Also consider tasks deferred to a later date
+        #[arg(long)]
+        include_deferred: bool,
+
+        /// Show how the urgency of each task is calculated
+        #[arg(long)]
+        explain: bool,
+    },
+    #[command(about = "Plan the tasks for a day within a difficulty budget")]
+    #[command(
+        long_about = "Plan the tasks for a day. Tasks are suggested in the order of `todo next`, \
+skipping parent tasks with incomplete subtasks, until their difficulties add up to the budget. \
+Each suggestion can be accepted or rejected, and the accepted tasks are saved as the plan for \
+the day.
+
+The budget defaults to 15 points, and tasks without a difficulty count as 3 points. Both can be \
+changed in the `plan` section of config.json."
+    )]
+    #[command(after_help = r"EXAMPLES:
+  todo plan
+  todo plan tomorrow --budget 10
+  todo plan --yes
+  todo plan --show")]
+    Plan {
+        /// The day to plan (see deadline formats in `todo list --help`)
+        #[arg(default_value = "today")]
+        day: String,
+
+        /// The number of difficulty points to plan
+        #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+        budget: Option<u32>,
+
+        /// Accept every suggested task without asking
+        #[arg(short, long, conflicts_with = "show")]
+        yes: bool,
+
+        /// Show the saved plan for the day instead of making a new one
+        #[arg(short, long)]
+        show: bool,
     },
-    #[command(about = "Show the next task to undertake based on task difficulty and deadline")]
-    Next,
     #[command(about = "Show information about a task")]
     Show { id: String },
+    #[command(about = "Show the date a date expression resolves to")]
+    #[command(after_help = r"EXAMPLES:
+  todo date next friday
+  todo date end of next month
+  todo date in 2 weekdays
+  todo date +1m
+  todo date tomorrow 9am")]
+    Date {
+        /// The date expression (see deadline formats in `todo add --help`)
+        #[arg(required = true, num_args = 1.., allow_hyphen_values = true)]
+        expression: Vec<String>,
+    },
     #[command(alias = "ls", about = "List tasks")]
     #[command(after_help = r"DEADLINE FORMATS:
-  Keywords: today, tomorrow, friday
-  Relative: +5d, +2w, +1m
-  Special:  eow, eom, eoy
+  Keywords: today, tomorrow, friday, next friday
+  Relative: +5d, +2w, +1m, in 2 weekdays
+  Special:  eow, eom, eoq, eoy, next month, end of next month
   Exact:    2026-02-10
+  Times:    friday 15:00, tomorrow 9am, 16:00 utc
 
 EXAMPLES:
   todo list --before friday
   todo list --after today --before eow
-  todo list --tags work --before +7d")]
+  todo list --tags work --before +7d
+  todo list --series abc1234
+  todo list --status in-progress,waiting
+  todo list --view tree --pid abc1234")]
     List {
         #[arg(short, long, default_value = "compact")]
         view: ViewMode,
@@ -174,10 +411,100 @@ EXAMPLES:
         /// Show only completed tasks
         #[arg(long, conflicts_with = "all")]
         completed: bool,
+
+        /// Show every task, including completed tasks, in the recurring series of a task
+        #[arg(long, value_name = "ID")]
+        series: Option<String>,
+
+        /// Show only tasks with these statuses
+        #[arg(short, long, value_delimiter = ',')]
+        status: Option<Vec<Status>>,
+
+        /// Also show tasks deferred to a later date
+        #[arg(long)]
+        include_deferred: bool,
+    },
+    #[command(about = "Show tasks as cards in columns by status or tag")]
+    #[command(after_help = r"EXAMPLES:
+  todo board
+  todo board --by tags
+  todo board --pid abc1234 --all
+  todo board --before eow")]
+    Board {
+        /// What the columns are
+        #[arg(long, default_value = "status")]
+        by: GroupBy,
+
+        /// Show only tasks with specific tags
+        #[arg(short, long, value_delimiter = ',', conflicts_with = "pid")]
+        tags: Option<Vec<String>>,
+
+        /// Show only the subtasks of a parent task
+        #[arg(short, long, conflicts_with = "tags")]
+        pid: Option<String>,
+
+        /// Show tasks due before this date (see deadline formats in `todo list --help`)
+        #[arg(long, value_name = "DEADLINE")]
+        before: Option<String>,
+
+        /// Show tasks due after this date (see deadline formats in `todo list --help`)
+        #[arg(long, value_name = "DEADLINE")]
+        after: Option<String>,
+
+        /// Also show completed tasks, in a done column when showing statuses
+        #[arg(long)]
+        all: bool,
+
+        /// Also show tasks deferred to a later date
+        #[arg(long)]
+        include_deferred: bool,
+    },
+    #[command(about = "Show a month of deadlines as a calendar")]
+    #[command(after_help = r"EXAMPLES:
+  todo calendar
+  todo calendar --month 2026-11
+  todo calendar --month 'next month' --tags work")]
+    Calendar {
+        /// The month to show, as 2026-11 or a date expression such as 'next month'
+        #[arg(short, long)]
+        month: Option<String>,
+
+        /// Show only tasks with specific tags
+        #[arg(short, long, value_delimiter = ',')]
+        tags: Option<Vec<String>>,
+
+        /// Also show completed tasks
+        #[arg(long)]
+        all: bool,
+
+        /// Also show tasks deferred to a later date
+        #[arg(long)]
+        include_deferred: bool,
+    },
+    #[command(about = "List the tasks due over the coming days, by date")]
+    #[command(after_help = r"EXAMPLES:
+  todo agenda
+  todo agenda --days 14
+  todo agenda --tags work")]
+    Agenda {
+        /// The number of days to show, starting today
+        #[arg(short, long, default_value_t = 7, value_parser = clap::value_parser!(u32).range(1..))]
+        days: u32,
+
+        /// Show only tasks with specific tags
+        #[arg(short, long, value_delimiter = ',')]
+        tags: Option<Vec<String>>,
+
+        /// Also show tasks deferred to a later date
+        #[arg(long)]
+        include_deferred: bool,
     },
     #[command(about = "List all tags")]
     Tags,
-    #[command(alias = "rm", about = "Remove tasks")]
+    #[command(
+        alias = "rm",
+        about = "Remove tasks, moving them and their subtasks to the trash"
+    )]
     Remove {
         #[arg(
             value_name = "IDs",
@@ -190,14 +517,179 @@ EXAMPLES:
         #[arg(long_help = TAGS_HELP)]
         tags: Option<Vec<String>>,
     },
+    #[command(about = "Show the history of changes to a task, or to all tasks")]
+    #[command(after_help = r"EXAMPLES:
+  todo log abc1234
+  todo log --after mon
+  todo log --after 2026-01-01 --before 2026-01-31")]
+    Log {
+        /// The task to show the history of. Shows the activity of all tasks if omitted.
+        id: Option<String>,
+
+        /// Show changes made on or after this date. Weekdays refer to the most recent one.
+        #[arg(long, value_name = "DATE")]
+        after: Option<String>,
+
+        /// Show changes made on or before this date (see deadline formats in `todo list --help`)
+        #[arg(long, value_name = "DATE")]
+        before: Option<String>,
+    },
+    #[command(about = "Undo the last operations that changed tasks")]
+    #[command(
+        long_about = "Undo the last operations that changed tasks. Time tracking (`todo track`) \
+and daily plans (`todo plan`) are not recorded in the journal, so they are not undone."
+    )]
+    Undo {
+        /// The number of operations to undo
+        #[arg(
+            short = 'n',
+            long = "count",
+            default_value_t = 1,
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+            conflicts_with = "list"
+        )]
+        n: usize,
+
+        /// Show the journal of operations that can be undone and redone
+        #[arg(short, long)]
+        list: bool,
+    },
+    #[command(about = "Redo the last undone operations")]
+    Redo {
+        /// The number of operations to redo
+        #[arg(
+            short = 'n',
+            long = "count",
+            default_value_t = 1,
+            value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        n: usize,
+    },
+    #[command(about = "Restore a removed task, along with the subtasks removed with it")]
+    Restore { id: String },
+    #[command(about = "Manage removed tasks")]
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommands,
+    },
+    #[command(about = "Track the time spent on tasks")]
+    #[command(after_help = r"EXAMPLES:
+  todo track start abc1234
+  todo track stop
+  todo track report --after mon --before eow --tags work")]
+    Track {
+        #[command(subcommand)]
+        command: TrackCommands,
+    },
+    #[command(about = "Export tasks")]
+    #[command(after_help = r"EXAMPLES:
+  todo export > tasks.json
+  todo export --format json --output tasks.json
+  todo export --format csv --columns id,task,deadline,tags
+  todo export --format todotxt --lossless --output todo.txt
+  todo export --format ics --output tasks.ics
+  todo export --format markdown >> report.md")]
+    Export {
+        #[arg(short, long, default_value = "json")]
+        format: ExportFormat,
+
+        /// The columns to include in a CSV export. Defaults to all columns.
+        #[arg(short, long, value_delimiter = ',')]
+        columns: Option<Vec<Column>>,
+
+        /// Keep task and parent IDs in a todo.txt export so that importing it again updates
+        /// the tasks instead of duplicating them
+        #[arg(long)]
+        lossless: bool,
+
+        /// The file to write the export to. Writes to stdout if omitted.
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Import tasks from a file")]
+    #[command(after_help = r#"EXAMPLES:
+  todo import tasks.json
+  todo import tasks.json --mode replace
+  todo import tasks.csv --map "Summary=task,Due Date=deadline"
+  todo import meeting-notes.md
+  todo import --from taskwarrior tasks.json"#)]
+    Import {
+        file: PathBuf,
+
+        /// The format of the file. Guessed from the file extension if omitted.
+        #[arg(short, long, visible_alias = "from")]
+        format: Option<ImportFormat>,
+
+        /// Map CSV headers onto task fields as HEADER=FIELD. Headers named after task fields
+        /// are mapped automatically.
+        #[arg(long, value_delimiter = ',', value_name = "HEADER=FIELD")]
+        map: Vec<String>,
+
+        /// How to combine the imported tasks with the existing tasks
+        #[arg(short, long, default_value = "merge")]
+        mode: ImportMode,
+    },
     #[command(about = "Clear all tasks")]
     Clear {
         #[arg(short, long)]
         force: bool,
     },
+    #[command(about = "Manage the task database")]
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrashCommands {
+    #[command(alias = "ls", about = "List removed tasks")]
+    List,
+    #[command(about = "Permanently delete removed tasks")]
+    Empty {
+        /// Only delete tasks removed at least this long ago (e.g. 30d, 2w, 1m)
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrackCommands {
+    #[command(about = "Start a timer for a task")]
+    Start { id: String },
+    #[command(about = "Stop the running timer")]
+    Stop,
+    #[command(about = "Show the time tracked per task and per tag")]
+    Report {
+        /// Only count time tracked on or after this date. Weekdays refer to the most recent one.
+        #[arg(long, value_name = "DATE")]
+        after: Option<String>,
+
+        /// Only count time tracked on or before this date
+        #[arg(long, value_name = "DATE")]
+        before: Option<String>,
+
+        /// Only count time tracked against tasks with these tags
+        #[arg(short, long, value_delimiter = ',')]
+        tags: Option<Vec<String>>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommands {
+    #[command(about = "Apply pending schema migrations to the task database")]
+    Migrate {
+        /// Report the pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 impl Commands {
+    #[allow(clippy::too_many_arguments)]
     pub fn add(
         mut db: Database,
         title: Option<String>,
@@ -206,21 +698,46 @@ impl Commands {
         deadline: Option<String>,
         tags: Option<Vec<String>>,
         pid: Option<String>,
+        blocked_by: Option<Vec<String>>,
+        recur: Option<String>,
+        scheduled: Option<String>,
     ) -> Result<()> {
-        let task: Task;
+        let recurrence = recur.map(|r| Recurrence::parse(&r)).transpose()?;
+        let scheduled = scheduled.map(|s| Deadline::parse(&s)).transpose()?;
+
+        let mut task: Task;
         if let Some(t) = title {
             task = Task::new(t, description, difficulty, deadline, tags, pid)?;
         } else {
             task = Task::interactive()?;
         }
+
+        if let Some(recurrence) = recurrence {
+            if task.deadline.is_none() {
+                task.deadline = Some(recurrence.first(Local::now().date_naive()).into());
+            }
+            task.recurrence = Some(recurrence);
+        }
+        task.blocked_by = blocked_by.map(blocker_ids);
+        if scheduled.is_some() {
+            task.scheduled = scheduled;
+        }
+
         let id = db.add(task)?;
         println!("Added task with ID {id}");
         Ok(())
     }
 
     pub fn complete(mut db: Database, id: String) -> Result<()> {
-        let id = db.completed(id)?;
+        let spawned = db.completed(id.clone())?;
         println!("Task with ID {id} marked as complete");
+        for task in spawned {
+            println!(
+                "Next occurrence {} due {}",
+                task.id.short(),
+                task.deadline.map(|d| d.to_string()).unwrap_or_default()
+            );
+        }
         Ok(())
     }
 
@@ -230,6 +747,52 @@ impl Commands {
         Ok(())
     }
 
+    pub fn start(mut db: Database, id: String) -> Result<()> {
+        let id = db.set_status(id, Status::InProgress, None)?;
+        println!("Task with ID {id} marked as in progress");
+
+        if db.get_task(&id)?.blocked {
+            eprintln!("Warning: task {id} is blocked by incomplete tasks");
+        }
+        Ok(())
+    }
+
+    pub fn wait(mut db: Database, id: String, until: Option<String>) -> Result<()> {
+        let until = until.map(|s| Deadline::parse(&s)).transpose()?;
+        let message = match &until {
+            Some(until) => format!("waiting until {until}"),
+            None => "waiting".to_string(),
+        };
+
+        let id = db.set_status(id, Status::Waiting, until)?;
+        println!("Task with ID {id} marked as {message}");
+        Ok(())
+    }
+
+    pub fn stop(mut db: Database, id: String) -> Result<()> {
+        let id = db.set_status(id, Status::Todo, None)?;
+        println!("Task with ID {id} moved back to todo");
+        Ok(())
+    }
+
+    pub fn defer(mut db: Database, id: String, when: Option<String>, clear: bool) -> Result<()> {
+        let until = match (when, clear) {
+            (_, true) => None,
+            (Some(when), false) => Some(Deadline::parse(&when)?),
+            (None, false) => unreachable!("clap requires a date unless --clear is given"),
+        };
+
+        let message = match &until {
+            Some(until) => format!("deferred until {until}"),
+            None => "no longer deferred".to_string(),
+        };
+
+        let id = db.defer(id, until)?;
+        println!("Task with ID {id} {message}");
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn update(
         mut db: Database,
         id: String,
@@ -239,6 +802,10 @@ impl Commands {
         deadline: Option<String>,
         tags: Option<Vec<String>>,
         pid: Option<String>,
+        blocked_by: Option<Vec<String>>,
+        recur: Option<String>,
+        no_recur: bool,
+        scheduled: Option<String>,
     ) -> Result<()> {
         let mut task_title = "".to_string();
 
@@ -246,16 +813,156 @@ impl Commands {
             task_title = t
         }
 
-        let task = Task::new(task_title, description, difficulty, deadline, tags, pid)?;
+        let mut task = Task::new(task_title, description, difficulty, deadline, tags, pid)?;
+        task.recurrence = recur.map(|r| Recurrence::parse(&r)).transpose()?;
+        task.blocked_by = blocked_by.map(blocker_ids);
+        task.scheduled = scheduled.map(|s| Deadline::parse(&s)).transpose()?;
 
-        let id = db.update(id, task)?;
+        let id = db.update(id, task, no_recur)?;
         println!("Updated task with ID {id}");
         Ok(())
     }
 
-    pub fn next(db: Database) -> Result<()> {
-        let task = db.next()?;
+    #[allow(clippy::too_many_arguments)]
+    pub fn next(
+        db: Database,
+        count: usize,
+        view: Option<ViewMode>,
+        columns: Option<Vec<Column>>,
+        tags: Option<Vec<String>>,
+        pid: Option<String>,
+        before: Option<String>,
+        after: Option<String>,
+        skip_parents: bool,
+        include_deferred: bool,
+        explain: bool,
+    ) -> Result<()> {
+        let before = before.map(|s| Deadline::parse(&s)).transpose()?;
+        let after = after.map(|s| Deadline::parse(&s)).transpose()?;
+
+        let mut tasks = db.next(
+            tags,
+            pid,
+            before,
+            after,
+            skip_parents,
+            include_deferred,
+            count,
+        )?;
+
+        if count > 1 || view.is_some() || columns.is_some() {
+            let explanations: Vec<String> = if explain {
+                tasks
+                    .iter()
+                    .map(|task| {
+                        format!("{}  {}\n{}", task.id.short(), task.title, db.urgency(task))
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            print_tasks(tasks, &view.unwrap_or(ViewMode::Compact), columns);
+            for explanation in explanations {
+                println!("\n{explanation}");
+            }
+            return Ok(());
+        }
+
+        let task = tasks.remove(0);
         println!("{}", task);
+
+        if explain {
+            println!("{}", db.urgency(&task));
+        }
+        Ok(())
+    }
+
+    pub fn plan(
+        mut db: Database,
+        day: String,
+        budget: Option<u32>,
+        yes: bool,
+        show: bool,
+    ) -> Result<()> {
+        let date = Deadline::parse(&day)?.date();
+        let config = db.config.plan.clone();
+        let budget = budget.unwrap_or(config.budget);
+
+        if show {
+            let tasks = db.plan(date)?;
+            if tasks.is_empty() {
+                println!("Nothing planned for {date}");
+                return Ok(());
+            }
+
+            let points: u32 = tasks.iter().map(|t| config.cost(t)).sum();
+            print_tasks(tasks, &ViewMode::Compact, None);
+            println!("Planned {points} of {budget} points for {date}");
+            return Ok(());
+        }
+
+        let due: u32 = db
+            .get_tasks(
+                None,
+                None,
+                Some(Deadline::from(date)),
+                None,
+                false,
+                false,
+                None,
+                None,
+                true,
+            )?
+            .iter()
+            .map(|t| config.cost(t))
+            .sum();
+        if due > budget {
+            eprintln!(
+                "Warning: the tasks due by {date} add up to {due} points, more than the budget of {budget}"
+            );
+        }
+
+        let mut planned = Vec::new();
+        let mut used = 0;
+        // Tasks deferred to the planned day or earlier can be planned for it.
+        let tasks = db
+            .ranked(None, None, None, None, true, true)?
+            .into_iter()
+            .filter(|t| t.scheduled.as_ref().is_none_or(|s| s.date() <= date));
+        for task in tasks {
+            let cost = config.cost(&task);
+            if used + cost > budget {
+                continue;
+            }
+
+            let accept = yes
+                || Confirm::new()
+                    .with_prompt(format!(
+                        "Plan \"{}\" ({cost} points, {used} of {budget} used)?",
+                        task.title
+                    ))
+                    .default(true)
+                    .interact()?;
+            if accept {
+                used += cost;
+                planned.push(task);
+            }
+
+            if used == budget {
+                break;
+            }
+        }
+
+        db.save_plan(date, &planned)?;
+
+        if planned.is_empty() {
+            println!("Nothing planned for {date}");
+            return Ok(());
+        }
+
+        print_tasks(planned, &ViewMode::Compact, None);
+        println!("Planned {used} of {budget} points for {date}");
         Ok(())
     }
 
@@ -265,6 +972,21 @@ impl Commands {
         Ok(())
     }
 
+    pub fn date(expression: Vec<String>) -> Result<()> {
+        let date = Deadline::parse(&expression.join(" "))?;
+        println!(
+            "{} ({}, {})",
+            date,
+            date.date().format("%A"),
+            date.days_until()
+        );
+        if date.time().is_some() {
+            println!("  UTC: {}", date.rfc3339());
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn list(
         db: Database,
         view: ViewMode,
@@ -275,9 +997,23 @@ impl Commands {
         after: Option<String>,
         all: bool,
         completed: bool,
+        series: Option<String>,
+        status: Option<Vec<Status>>,
+        include_deferred: bool,
     ) -> Result<()> {
         list_tasks(
-            &db, &view, columns, tags, pid, before, after, all, completed,
+            &db,
+            &view,
+            columns,
+            tags,
+            pid,
+            before,
+            after,
+            all,
+            completed,
+            series,
+            status,
+            include_deferred,
         )?;
         Ok(())
     }
@@ -296,18 +1032,222 @@ impl Commands {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn board(
+        db: Database,
+        by: GroupBy,
+        tags: Option<Vec<String>>,
+        pid: Option<String>,
+        before: Option<String>,
+        after: Option<String>,
+        all: bool,
+        include_deferred: bool,
+    ) -> Result<()> {
+        board(&db, by, tags, pid, before, after, all, include_deferred)
+    }
+
+    pub fn calendar(
+        db: Database,
+        month: Option<String>,
+        tags: Option<Vec<String>>,
+        all: bool,
+        include_deferred: bool,
+    ) -> Result<()> {
+        calendar_month(&db, month, tags, all, include_deferred)
+    }
+
+    pub fn agenda(
+        db: Database,
+        days: u32,
+        tags: Option<Vec<String>>,
+        include_deferred: bool,
+    ) -> Result<()> {
+        agenda(&db, days, tags, include_deferred)
+    }
+
     pub fn tags(db: Database) -> Result<()> {
         let tags = db.tags()?;
         println!("{}", tags.join("  "));
         Ok(())
     }
 
+    pub fn log(
+        db: Database,
+        id: Option<String>,
+        after: Option<String>,
+        before: Option<String>,
+    ) -> Result<()> {
+        let after = after.map(|s| Deadline::parse_since(&s)).transpose()?;
+        let before = before.map(|s| Deadline::parse(&s)).transpose()?;
+
+        let events = db.history(id.as_deref(), after, before)?;
+
+        if events.is_empty() {
+            match id {
+                Some(id) => bail!("No history found for a task matching '{id}'"),
+                None => println!("No history found"),
+            }
+        }
+
+        for event in events {
+            println!("{event}");
+        }
+        Ok(())
+    }
+
+    pub fn undo(mut db: Database, n: usize, list: bool) -> Result<()> {
+        if list {
+            let journal = db.journal()?;
+            if journal.is_empty() {
+                println!("No operations recorded");
+            }
+            for entry in journal {
+                println!("{entry}");
+            }
+            return Ok(());
+        }
+
+        for entry in db.undo(n)? {
+            println!("Undid: {}", entry.operation);
+        }
+        Ok(())
+    }
+
+    pub fn redo(mut db: Database, n: usize) -> Result<()> {
+        for entry in db.redo(n)? {
+            println!("Redid: {}", entry.operation);
+        }
+        Ok(())
+    }
+
+    pub fn restore(mut db: Database, id: String) -> Result<()> {
+        let n = db.restore(id)?;
+        println!("Restored {} task(s)", n);
+        Ok(())
+    }
+
+    pub fn export(
+        db: Database,
+        format: ExportFormat,
+        columns: Option<Vec<Column>>,
+        lossless: bool,
+        output: Option<PathBuf>,
+    ) -> Result<()> {
+        if columns.is_some() && !matches!(format, ExportFormat::Csv) {
+            bail!("--columns is only supported by CSV exports");
+        }
+        if lossless && !matches!(format, ExportFormat::TodoTxt) {
+            bail!("--lossless is only supported by todo.txt exports");
+        }
+
+        // Only the JSON format can represent removed tasks.
+        let mut tasks = db.all_tasks()?;
+        if !matches!(format, ExportFormat::Json) {
+            tasks.retain(|t| t.deleted.is_none());
+        }
+
+        let exported = match format {
+            ExportFormat::Json => json::export(&tasks)?,
+            ExportFormat::Csv => {
+                csv::export(&tasks, columns.as_deref().unwrap_or(csv::DEFAULT_COLUMNS))?
+            }
+            ExportFormat::TodoTxt => todotxt::export(&tasks, lossless),
+            ExportFormat::Ics => ics::export(&tasks),
+            ExportFormat::Markdown => markdown::export(&tasks),
+        };
+
+        match output {
+            Some(path) => {
+                fs::write(&path, exported)
+                    .with_context(|| format!("Unable to write to {}", path.display()))?;
+                println!("Exported {} task(s) to {}", tasks.len(), path.display());
+            }
+            None => println!("{exported}"),
+        }
+        Ok(())
+    }
+
+    pub fn import(
+        mut db: Database,
+        file: PathBuf,
+        format: Option<ImportFormat>,
+        map: Vec<String>,
+        mode: ImportMode,
+    ) -> Result<()> {
+        let format = format
+            .or_else(|| ImportFormat::from_extension(&file))
+            .context(
+                "Unable to determine the format of the file to import. Use --format to specify it.",
+            )?;
+
+        let input = fs::read_to_string(&file)
+            .with_context(|| format!("Unable to read {}", file.display()))?;
+
+        if !map.is_empty() && !matches!(format, ImportFormat::Csv) {
+            bail!("--map is only supported by CSV imports");
+        }
+
+        let tasks = match format {
+            ImportFormat::Json => json::import(&input)?,
+            ImportFormat::TodoTxt => todotxt::import(&input, &db.all_ids()?)?,
+            ImportFormat::Ics => ics::import(&input)?,
+            ImportFormat::Markdown => markdown::import(&input)?,
+            ImportFormat::Taskwarrior => {
+                let imported = taskwarrior::import(&input)?;
+
+                for (attribute, count) in &imported.unsupported {
+                    eprintln!(
+                        "Unable to import the Taskwarrior attribute '{attribute}' of {count} task(s)"
+                    );
+                }
+                if imported.skipped_recurring > 0 {
+                    eprintln!(
+                        "Skipped {} recurring task template(s). Their pending instances were imported.",
+                        imported.skipped_recurring
+                    );
+                }
+
+                imported.tasks
+            }
+            ImportFormat::Csv => {
+                let mapping = map
+                    .iter()
+                    .map(|m| csv::parse_mapping(m))
+                    .collect::<Result<Vec<_>>>()?;
+                let imported = csv::import(&input, &mapping, &db.all_ids()?)?;
+
+                for header in &imported.ignored {
+                    eprintln!(
+                        "Ignoring column '{header}', which does not map onto a stored task field"
+                    );
+                }
+                for error in &imported.errors {
+                    eprintln!("Skipping {error}");
+                }
+                if !imported.errors.is_empty() {
+                    eprintln!("Skipped {} invalid row(s)", imported.errors.len());
+                }
+
+                imported.tasks
+            }
+        };
+
+        let summary = db.import_tasks(tasks, mode, &format!("import {}", file.display()))?;
+        println!(
+            "Imported {} task(s) ({} added, {} updated)",
+            summary.added + summary.updated,
+            summary.added,
+            summary.updated
+        );
+        Ok(())
+    }
+
     pub fn clear(db: Database, force: bool) -> Result<()> {
         let mut confirm = true;
 
         if !force {
             confirm = Confirm::new()
-                .with_prompt("Are you sure you want to clear ALL tasks? This cannot be undone.")
+                .with_prompt("Are you sure you want to clear ALL tasks?")
                 .default(false)
                 .interact()?;
         }
@@ -318,3 +1258,103 @@ impl Commands {
         Ok(())
     }
 }
+
+impl TrashCommands {
+    pub fn list(db: Database) -> Result<()> {
+        list_trash(&db)
+    }
+
+    pub fn empty(mut db: Database, older_than: Option<String>, force: bool) -> Result<()> {
+        let today = Local::now().date_naive();
+        let days = older_than
+            .map(|age| {
+                Span::parse(&age)
+                    .filter(|span| span.is_positive())
+                    .and_then(|span| span.before(today))
+                    .map(|date| (today - date).num_days())
+                    .ok_or_else(|| {
+                        anyhow!("Invalid age '{age}'. Use a number of days, weeks or months, e.g. 30d, 2w or 1m")
+                    })
+            })
+            .transpose()?;
+
+        let mut confirm = true;
+
+        if !force {
+            confirm = Confirm::new()
+                .with_prompt("Are you sure you want to permanently delete the removed tasks?")
+                .default(false)
+                .interact()?;
+        }
+
+        if confirm {
+            let n = db.empty_trash(days)?;
+            println!("Permanently deleted {} task(s)", n);
+        }
+        Ok(())
+    }
+}
+
+impl TrackCommands {
+    pub fn start(mut db: Database, id: String) -> Result<()> {
+        let entry = db.start_timer(&id)?;
+        println!(
+            "Started timer for {} \"{}\"",
+            entry.task_id.short(),
+            entry.title
+        );
+        Ok(())
+    }
+
+    pub fn stop(mut db: Database) -> Result<()> {
+        let entry = db.stop_timer()?;
+        println!(
+            "Stopped timer for {} \"{}\" after {}",
+            entry.task_id.short(),
+            entry.title,
+            format_duration(entry.duration())
+        );
+        Ok(())
+    }
+
+    pub fn report(
+        db: Database,
+        after: Option<String>,
+        before: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> Result<()> {
+        time_report(&db, after, before, tags)
+    }
+}
+
+impl DbCommands {
+    pub fn migrate(mut db: Database, dry_run: bool) -> Result<()> {
+        let current = migrations::current_version(&db.conn)?;
+        let pending = migrations::pending(&db.conn)?;
+
+        if pending.is_empty() {
+            println!("Task database is up to date (schema version {current})");
+            return Ok(());
+        }
+
+        if dry_run {
+            println!(
+                "Task database is at schema version {current}, the latest is {}",
+                migrations::latest_version()
+            );
+            println!("Pending migrations:");
+            for migration in pending {
+                println!("  v{}  {}", migration.version, migration.description);
+            }
+            return Ok(());
+        }
+
+        for migration in db.migrate()? {
+            println!(
+                "Applied migration v{}: {}",
+                migration.version, migration.description
+            );
+        }
+        Ok(())
+    }
+}
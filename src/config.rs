@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{calendar::Calendar, deadline::DateFormat, plan::PlanConfig, urgency::Coefficients};
+
+/// User settings, read from `config.json`. Every setting is optional and falls back to its
+/// default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The weights of the factors that make up the urgency of a task.
+    pub urgency: Coefficients,
+    /// The budget used by `todo plan`.
+    pub plan: PlanConfig,
+    /// How numeric dates are read and shown: `iso`, `uk` or `us`.
+    pub date_format: DateFormat,
+    /// The working days and holidays counted by business day offsets.
+    pub calendar: Calendar,
+}
+
+impl Config {
+    /// The path of the config file. It lives in the task database directory if one is given,
+    /// and in the platform's config directory otherwise.
+    pub fn path(dir: Option<&Path>) -> Result<PathBuf> {
+        let dir = match dir {
+            Some(dir) => dir.to_path_buf(),
+            None => ProjectDirs::from("com", "Todo", "todo")
+                .context("Could not determine the config directory")?
+                .config_dir()
+                .to_path_buf(),
+        };
+        Ok(dir.join("config.json"))
+    }
+
+    /// Load the config file, using the defaults if there is none.
+    pub fn load(dir: Option<&Path>) -> Result<Self> {
+        let path = Self::path(dir)?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read the config file {}", path.display()))?;
+        let mut config: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        config
+            .calendar
+            .load(path.parent().unwrap_or(Path::new(".")))?;
+        Ok(config)
+    }
+}
@@ -1,15 +1,100 @@
 use anyhow::{Context, Result, bail};
+use chrono::{Local, NaiveDate};
 use directories::ProjectDirs;
 use rusqlite::{Connection, params};
 use std::{fs, path::PathBuf, time::SystemTime};
 
 use crate::{
+    config::Config,
     deadline::Deadline,
-    task::{ID, Task},
+    formats::{ImportMode, ImportSummary},
+    migrations::{self, Migration},
+    recurrence::Recurrence,
+    task::{ID, Status, Task},
+    urgency::Urgency,
 };
 
+const TASK_COLUMNS: &str = "id, title, description, difficulty, deadline, parent_id, created, \
+    completed, deleted, recurrence, series, status, waiting_until, scheduled";
+
+/// Whether the task in `tasks` blocks a task that is incomplete and has not been removed.
+const BLOCKING: &str = "EXISTS (SELECT 1 FROM depends_on d JOIN tasks b ON b.id = d.task_id
+    WHERE d.blocker_id = tasks.id AND b.completed IS NULL AND b.deleted IS NULL)";
+
+/// Whether the task in `tasks` is blocked by a task that is incomplete and has not been
+/// removed.
+const BLOCKED: &str = "EXISTS (SELECT 1 FROM depends_on d JOIN tasks b ON b.id = d.blocker_id
+    WHERE d.task_id = tasks.id AND b.completed IS NULL AND b.deleted IS NULL)";
+
+/// The local date and time of the date in a column, matching [`Deadline::sortable`]. Dates
+/// with a time of day are stored as RFC 3339 UTC times, which end in `Z`.
+fn local_key(column: &str) -> String {
+    format!(
+        "CASE WHEN {column} LIKE '%Z'
+            THEN strftime('%Y-%m-%dT%H:%M', {column}, 'localtime')
+            ELSE {column}
+        END"
+    )
+}
+
+/// Whether the date in a column has been reached, matching [`Deadline::is_reached`].
+fn reached(column: &str) -> String {
+    format!(
+        "{} <= strftime('%Y-%m-%dT%H:%M', 'now', 'localtime')",
+        local_key(column)
+    )
+}
+
+/// The local date of the date in a column.
+fn local_date(column: &str) -> String {
+    format!("substr({}, 1, 10)", local_key(column))
+}
+
+/// The current [`Status`] of the tasks in the table with the given alias, matching
+/// [`Task::current_status`].
+fn current_status(alias: &str) -> String {
+    format!(
+        "CASE
+            WHEN {alias}.completed IS NOT NULL THEN 'done'
+            WHEN {alias}.status = 'waiting' AND {} THEN 'todo'
+            ELSE {alias}.status
+        END",
+        reached(&format!("{alias}.waiting_until"))
+    )
+}
+
+/// [`TASK_COLUMNS`] qualified with a table alias, for queries that join other tables.
+fn task_columns(alias: &str) -> String {
+    TASK_COLUMNS
+        .split(", ")
+        .map(|c| format!("{alias}.{c}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The recurrence rule of a task as it is stored, with a monthly rule fixed to the day of the
+/// task's deadline.
+fn anchored_recurrence(task: &Task) -> Option<Recurrence> {
+    let recurrence = task.recurrence.clone()?;
+    Some(match &task.deadline {
+        Some(deadline) => recurrence.anchored(deadline.date()),
+        None => recurrence,
+    })
+}
+
+/// The current time as a unix timestamp.
+pub(crate) fn timestamp_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+        .cast_signed()
+}
+
 pub struct Database {
     pub conn: Connection,
+    pub config: Config,
+    path: PathBuf,
 }
 
 impl Database {
@@ -27,60 +112,90 @@ impl Database {
         Ok(db_dir.join("tasks.db"))
     }
 
-    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+    /// Open the task database without applying any pending schema migrations.
+    pub fn open(path: Option<PathBuf>) -> Result<Self> {
+        let config = Config::load(path.as_deref())?;
         let db_path = Self::get_path(path)?;
         let conn = Connection::open(&db_path).context("Could not open the task database")?;
 
-        let mut db = Database { conn };
-        db.initialize_schema()?;
+        conn.execute("PRAGMA foreign_keys = ON", [])?;
 
-        Ok(db)
+        Ok(Database {
+            conn,
+            config,
+            path: db_path,
+        })
     }
 
-    fn initialize_schema(&mut self) -> Result<()> {
-        self.conn.execute_batch(
-            "
-            CREATE TABLE IF NOT EXISTS tasks (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                description TEXT,
-                difficulty INTEGER,
-                deadline INTEGER, 
-                parent_id TEXT,
-                created INTEGER NOT NULL,
-                completed INTEGER,
-                FOREIGN KEY (parent_id) REFERENCES tasks(id) ON DELETE CASCADE
-            );
-            
-            CREATE TABLE IF NOT EXISTS tags (
-                id INTEGER PRIMARY KEY,
-                name TEXT UNIQUE NOT NULL
-            );
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let mut db = Self::open(path)?;
+        let existing = db.has_data()?;
 
-            CREATE TABLE IF NOT EXISTS task_tags (
-                task_id TEXT NOT NULL,
-                tag_id INTEGER NOT NULL,
-                PRIMARY KEY (task_id, tag_id),
-                FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
-                FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        for migration in db.migrate()? {
+            if !existing {
+                continue;
+            }
+            eprintln!(
+                "Migrated task database to schema version {}: {}",
+                migration.version, migration.description
             );
+        }
+
+        Ok(db)
+    }
 
-            CREATE INDEX IF NOT EXISTS idx_tasks_parent ON tasks(parent_id);
-            CREATE INDEX IF NOT EXISTS idx_task_tags_task ON task_tags(task_id);
-            CREATE INDEX IF NOT EXISTS idx_task_tags_tag ON task_tags(tag_id);
-            ",
+    /// Bring the database schema up to date, backing up the database file first if it
+    /// already holds data.
+    pub fn migrate(&mut self) -> Result<Vec<&'static Migration>> {
+        if migrations::pending(&self.conn)?.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if self.has_data()? {
+            let backup = self.backup()?;
+            eprintln!("Backed up task database to {}", backup.display());
+        }
+
+        migrations::migrate(&mut self.conn)
+    }
+
+    fn has_data(&self) -> Result<bool> {
+        let tables: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+            [],
+            |row| row.get(0),
         )?;
+        Ok(tables > 0)
+    }
 
-        self.conn.execute("PRAGMA foreign_keys = ON", [])?;
+    /// Write a copy of the database alongside the original, named after its current schema
+    /// version.
+    pub fn backup(&self) -> Result<PathBuf> {
+        let version = migrations::current_version(&self.conn)?;
+        let timestamp = Local::now().format("%Y%m%d%H%M%S");
+        let backup = self
+            .path
+            .with_file_name(format!("tasks.v{version}.{timestamp}.db.bak"));
 
-        Ok(())
+        self.conn
+            .execute("VACUUM INTO ?1", [backup.to_string_lossy().into_owned()])
+            .context("Unable to back up the task database")?;
+
+        Ok(backup)
     }
 
     pub fn clear(&self) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let ids = self.all_ids()?;
+        let entry = self.journal_begin("clear", &ids)?;
+
         self.conn.execute("DELETE FROM tasks", [])?;
         self.conn.execute("DELETE FROM tags", [])?;
         self.conn.execute("DELETE FROM task_tags", [])?;
 
+        self.journal_end(entry, &ids)?;
+        tx.commit()?;
+
         self.conn.execute("VACUUM", [])?;
 
         Ok(())
@@ -103,13 +218,20 @@ impl Database {
                 .cast_signed()
         });
 
-        let pid: Option<ID> = if let Some(parent_id) = task.pid {
+        let tx = self.conn.unchecked_transaction()?;
+        let ids = vec![task.id.to_string()];
+        let entry =
+            self.journal_begin(&format!("add {} \"{}\"", task.id.short(), task.title), &ids)?;
+
+        let pid: Option<ID> = if let Some(parent_id) = &task.pid {
             let p = parent_id.short();
             let pattern = format!("{p}%");
             self.conn
-                .query_row("SELECT id FROM tasks WHERE id LIKE ?1", [&pattern], |row| {
-                    row.get(0)
-                })
+                .query_row(
+                    "SELECT id FROM tasks WHERE id LIKE ?1 AND deleted IS NULL",
+                    [&pattern],
+                    |row| row.get(0),
+                )
                 .context("Unable to find parent ID")?
         } else {
             None
@@ -124,8 +246,13 @@ impl Database {
                 deadline,
                 parent_id,
                 created,
-                completed
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                completed,
+                recurrence,
+                series,
+                status,
+                waiting_until,
+                scheduled
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
             params![
                 task.id,
                 task.title,
@@ -135,6 +262,11 @@ impl Database {
                 pid,
                 created,
                 completed,
+                anchored_recurrence(&task),
+                task.series,
+                task.status,
+                task.waiting_until,
+                task.scheduled,
             ],
         )?;
 
@@ -158,10 +290,24 @@ impl Database {
             }
         }
 
+        if let Some(blocked_by) = &task.blocked_by {
+            let blockers = blocked_by
+                .iter()
+                .map(|b| self.resolve_id(&b.to_string()).map(ID::from))
+                .collect::<Result<Vec<_>>>()?;
+            self.update_task_dependencies(&task.id, &blockers)?;
+        }
+
+        self.journal_end(entry, &ids)?;
+        tx.commit()?;
+
         Ok(task.id.short())
     }
 
-    pub fn completed(&mut self, id: String) -> Result<String> {
+    /// Mark the tasks matching `id` as complete. Completing a recurring task creates the next
+    /// task in its series, unless the series already has an incomplete task. Returns the
+    /// created tasks.
+    pub fn completed(&mut self, id: String) -> Result<Vec<Task>> {
         #[expect(clippy::missing_panics_doc, reason = "infallible")]
         let timestamp = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
@@ -169,46 +315,178 @@ impl Database {
             .as_secs()
             .cast_signed();
 
-        let pattern = format!("{id}%");
+        let ids = self.matching_ids(&id)?;
+        if ids.is_empty() {
+            bail!("No task found matching '{id}'");
+        }
 
-        let n = self.conn.execute(
-            "UPDATE tasks SET completed = ?1 WHERE id LIKE ?2",
+        let mut recurring = Vec::new();
+        for id in &ids {
+            let task = self.get_task(id)?;
+            if task.completed.is_none() && task.recurrence.is_some() {
+                recurring.push(task);
+            }
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let entry = self.journal_begin(&format!("complete {id}"), &ids)?;
+
+        let pattern = format!("{id}%");
+        self.conn.execute(
+            "UPDATE tasks SET completed = ?1 WHERE id LIKE ?2 AND deleted IS NULL",
             params![timestamp, &pattern],
         )?;
 
-        if n == 0 {
-            bail!("No task found matching '{id}'");
+        let today = Local::now().date_naive();
+        let mut spawned = Vec::new();
+        for task in recurring {
+            if let Some(next) = self.next_occurrence(task, today)? {
+                self.upsert_task(&next)?;
+                spawned.push(next);
+            }
         }
 
-        Ok(id)
+        let mut changed = ids;
+        changed.extend(spawned.iter().map(|t| t.id.to_string()));
+        self.journal_end(entry, &changed)?;
+        tx.commit()?;
+
+        Ok(spawned)
     }
 
-    pub fn incomplete(&mut self, id: String) -> Result<String> {
-        let pattern = format!("{id}%");
+    /// The task that follows a completed recurring task in its series, or `None` if the series
+    /// still has an incomplete task.
+    fn next_occurrence(&self, task: Task, today: NaiveDate) -> Result<Option<Task>> {
+        let Some(recurrence) = task.recurrence else {
+            return Ok(None);
+        };
+        let series = task.series.unwrap_or(task.id);
 
-        let count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM tasks WHERE id LIKE ?1",
-            params![&pattern],
+        let pending: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM tasks
+             WHERE COALESCE(series, id) = ?1 AND completed IS NULL AND deleted IS NULL",
+            [&series],
             |row| row.get(0),
         )?;
-        if count == 0 {
+        if pending > 0 {
+            return Ok(None);
+        }
+
+        let previous = task.deadline.as_ref().map(Deadline::date);
+        let recurrence = match previous {
+            Some(previous) => recurrence.anchored(previous),
+            None => recurrence,
+        };
+        let deadline = recurrence.next(previous, today);
+        let mut next = Task::new(task.title, task.desc, None, None, task.tags, None)?;
+        next.difficulty = task.difficulty;
+        next.deadline = Some(match &task.deadline {
+            Some(previous) => previous.on(deadline),
+            None => Deadline::from(deadline),
+        });
+        next.pid = task.pid;
+        next.recurrence = Some(recurrence);
+        next.series = Some(series);
+
+        Ok(Some(next))
+    }
+
+    pub fn incomplete(&mut self, id: String) -> Result<String> {
+        let ids = self.matching_ids(&id)?;
+        if ids.is_empty() {
             bail!("No task found matching '{id}'");
         }
 
+        let tx = self.conn.unchecked_transaction()?;
+        let entry = self.journal_begin(&format!("incomplete {id}"), &ids)?;
+
+        let pattern = format!("{id}%");
         let n = self.conn.execute(
-            "UPDATE tasks SET completed = NULL WHERE id LIKE ?1 AND completed IS NOT NULL",
+            "UPDATE tasks SET completed = NULL
+             WHERE id LIKE ?1 AND completed IS NOT NULL AND deleted IS NULL",
             params![&pattern],
         )?;
         if n == 0 {
             bail!("Task '{id}' is not completed");
         }
+
+        self.journal_end(entry, &ids)?;
+        tx.commit()?;
+
+        Ok(id)
+    }
+
+    /// Set the workflow status of the incomplete tasks matching `id`. `until` is the date a
+    /// waiting task becomes workable again.
+    pub fn set_status(
+        &mut self,
+        id: String,
+        status: Status,
+        until: Option<Deadline>,
+    ) -> Result<String> {
+        if status == Status::Done {
+            bail!("Use `todo complete {id}` to mark a task as done");
+        }
+
+        let ids = self.matching_ids(&id)?;
+        if ids.is_empty() {
+            bail!("No task found matching '{id}'");
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let entry = self.journal_begin(&format!("{} {id}", status.verb()), &ids)?;
+
+        let pattern = format!("{id}%");
+        let n = self.conn.execute(
+            "UPDATE tasks SET status = ?1, waiting_until = ?2
+             WHERE id LIKE ?3 AND completed IS NULL AND deleted IS NULL",
+            params![status, until, &pattern],
+        )?;
+        if n == 0 {
+            bail!("Task '{id}' is completed. Reopen it with `todo incomplete {id}` first");
+        }
+
+        self.journal_end(entry, &ids)?;
+        tx.commit()?;
+
+        Ok(id)
+    }
+
+    /// Defer the incomplete tasks matching `id` until a date, hiding them until then, or make
+    /// them visible again if `until` is `None`.
+    pub fn defer(&mut self, id: String, until: Option<Deadline>) -> Result<String> {
+        let ids = self.matching_ids(&id)?;
+        if ids.is_empty() {
+            bail!("No task found matching '{id}'");
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let entry = self.journal_begin(&format!("defer {id}"), &ids)?;
+
+        let pattern = format!("{id}%");
+        let n = self.conn.execute(
+            "UPDATE tasks SET scheduled = ?1
+             WHERE id LIKE ?2 AND completed IS NULL AND deleted IS NULL",
+            params![until, &pattern],
+        )?;
+        if n == 0 {
+            bail!("Task '{id}' is completed. Reopen it with `todo incomplete {id}` first");
+        }
+
+        self.journal_end(entry, &ids)?;
+        tx.commit()?;
+
         Ok(id)
     }
 
     pub fn tags(&self) -> Result<Vec<String>> {
         let mut stmt = self.conn.prepare(
-            "SELECT tags.name 
-                FROM tags",
+            "SELECT DISTINCT tags.name
+                FROM tags
+                JOIN task_tags ON tags.id = task_tags.tag_id
+                JOIN tasks ON task_tags.task_id = tasks.id
+                WHERE tasks.deleted IS NULL
+                ORDER BY tags.name",
         )?;
 
         let tags = stmt
@@ -219,7 +497,14 @@ impl Database {
         Ok(tags)
     }
 
-    pub fn update(&mut self, id: String, mut updates: Task) -> Result<String> {
+    /// Update the fields of a task that are set in `updates`, removing its recurrence rule if
+    /// `clear_recurrence` is set.
+    pub fn update(
+        &mut self,
+        id: String,
+        mut updates: Task,
+        clear_recurrence: bool,
+    ) -> Result<String> {
         let existing = self.get_task(&id)?;
 
         if updates.title.is_empty() {
@@ -237,16 +522,33 @@ impl Database {
         if updates.pid.is_none() {
             updates.pid = existing.pid;
         }
+        if updates.scheduled.is_none() {
+            updates.scheduled = existing.scheduled;
+        }
+        if updates.recurrence.is_none() && !clear_recurrence {
+            updates.recurrence = existing.recurrence;
+        }
+        if let Some(recurrence) = &updates.recurrence
+            && updates.deadline.is_none()
+        {
+            updates.deadline = Some(recurrence.first(Local::now().date_naive()).into());
+        }
         updates.id = existing.id.clone();
         updates.created = existing.created;
 
+        let tx = self.conn.unchecked_transaction()?;
+        let ids = vec![updates.id.to_string()];
+        let entry = self.journal_begin(&format!("update {id}"), &ids)?;
+
         self.conn.execute(
             "UPDATE tasks SET 
             title = ?2,
             description = ?3,
             difficulty = ?4,
             deadline = ?5,
-            parent_id = ?6
+            parent_id = ?6,
+            recurrence = ?7,
+            scheduled = ?8
          WHERE id = ?1",
             params![
                 updates.id,
@@ -254,7 +556,9 @@ impl Database {
                 updates.desc,
                 updates.difficulty,
                 updates.deadline,
-                updates.pid.map(|p| p.to_string()),
+                updates.pid.as_ref().map(|p| p.to_string()),
+                anchored_recurrence(&updates),
+                updates.scheduled,
             ],
         )?;
 
@@ -262,10 +566,21 @@ impl Database {
             self.update_task_tags(&updates.id, &new_tags)?;
         }
 
+        if let Some(blocked_by) = &updates.blocked_by {
+            let blockers = blocked_by
+                .iter()
+                .map(|b| self.resolve_id(&b.to_string()).map(ID::from))
+                .collect::<Result<Vec<_>>>()?;
+            self.update_task_dependencies(&updates.id, &blockers)?;
+        }
+
+        self.journal_end(entry, &ids)?;
+        tx.commit()?;
+
         Ok(id)
     }
 
-    fn update_task_tags(&mut self, task_id: &ID, tags: &[String]) -> Result<()> {
+    fn update_task_tags(&self, task_id: &ID, tags: &[String]) -> Result<()> {
         self.conn
             .execute("DELETE FROM task_tags WHERE task_id = ?1", [task_id])?;
 
@@ -288,23 +603,79 @@ impl Database {
         Ok(())
     }
 
-    pub fn next(&self) -> Result<Task> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, title, description, difficulty, deadline, parent_id, created, completed
-         FROM tasks
-         WHERE completed IS NULL
-         ORDER BY 
-            CASE WHEN deadline IS NULL THEN 1 ELSE 0 END,  
-            deadline ASC,
-            difficulty DESC
-         LIMIT 1",
+    /// The `n` most urgent tasks matching the filters that can be worked on. See
+    /// [`Database::ranked`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn next(
+        &self,
+        tags: Option<Vec<String>>,
+        pid: Option<String>,
+        before: Option<Deadline>,
+        after: Option<Deadline>,
+        skip_parents: bool,
+        include_deferred: bool,
+        n: usize,
+    ) -> Result<Vec<Task>> {
+        let mut tasks = self.ranked(tags, pid, before, after, skip_parents, include_deferred)?;
+        tasks.truncate(n);
+
+        if tasks.is_empty() {
+            bail!("No incomplete tasks found");
+        }
+
+        Ok(tasks)
+    }
+
+    /// The tasks matching the filters that can be worked on, from most to least urgent, with
+    /// tasks that are in progress first. Blocked and waiting tasks are skipped, as are tasks
+    /// with incomplete subtasks if `skip_parents` is set and deferred tasks unless
+    /// `include_deferred` is set.
+    pub fn ranked(
+        &self,
+        tags: Option<Vec<String>>,
+        pid: Option<String>,
+        before: Option<Deadline>,
+        after: Option<Deadline>,
+        skip_parents: bool,
+        include_deferred: bool,
+    ) -> Result<Vec<Task>> {
+        let mut tasks = self.get_tasks(
+            tags,
+            pid,
+            before,
+            after,
+            false,
+            false,
+            None,
+            Some(vec![Status::Todo, Status::InProgress]),
+            include_deferred,
         )?;
 
-        let mut task = stmt.query_row([], |row| Task::try_from(row))?;
-        let tags = self.get_tags(&task.id.clone().into())?;
-        task.tags = Some(tags);
+        tasks.retain(|t| !t.blocked);
+        if skip_parents {
+            let parents = self.incomplete_parents()?;
+            tasks.retain(|t| !parents.contains(&t.id.to_string()));
+        }
 
-        Ok(task)
+        tasks.sort_by(|a, b| {
+            (b.current_status() == Status::InProgress)
+                .cmp(&(a.current_status() == Status::InProgress))
+                .then(b.urgency.total_cmp(&a.urgency))
+        });
+
+        Ok(tasks)
+    }
+
+    /// The full IDs of the tasks that have incomplete subtasks.
+    fn incomplete_parents(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT parent_id FROM tasks
+             WHERE parent_id IS NOT NULL AND completed IS NULL AND deleted IS NULL",
+        )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
     }
 
     pub fn remove_ids(&mut self, ids: Vec<String>) -> Result<usize> {
@@ -319,45 +690,388 @@ impl Database {
             }
         }
 
-        let mut n = 0;
-        for id in valid_ids {
-            let pattern = format!("{id}%");
-            n += self
-                .conn
-                .execute("DELETE FROM tasks WHERE id LIKE ?1", [&pattern])?;
+        let mut roots = Vec::new();
+        for id in &valid_ids {
+            roots.extend(self.matching_ids(id)?);
         }
 
-        Ok(n)
+        self.trash(&format!("remove {}", valid_ids.join(" ")), &roots)
     }
 
     pub fn remove_tags(&self, tags: Vec<String>) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id
+             FROM tasks t
+             JOIN task_tags tt ON t.id = tt.task_id
+             JOIN tags tg ON tt.tag_id = tg.id
+             WHERE tg.name = ?1 AND t.deleted IS NULL",
+        )?;
+
+        let mut roots = Vec::new();
+        for tag in &tags {
+            roots.extend(
+                stmt.query_map([tag], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?,
+            );
+        }
+
+        self.trash(&format!("remove --tags {}", tags.join(",")), &roots)
+    }
+
+    /// Move tasks, along with all of their subtasks, to the trash. Returns the number of the
+    /// given tasks that were trashed.
+    fn trash(&self, operation: &str, roots: &[String]) -> Result<usize> {
+        let mut removed = Vec::new();
+        for root in roots {
+            for id in self.subtree_ids(root)? {
+                if !removed.contains(&id) {
+                    removed.push(id);
+                }
+            }
+        }
+
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let entry = self.journal_begin(operation, &removed)?;
+        // Each removal gets its own timestamp, so that restoring a task can tell the subtasks
+        // removed with it from those removed before it.
+        let latest: Option<i64> =
+            self.conn
+                .query_row("SELECT MAX(deleted) FROM tasks", [], |row| row.get(0))?;
+        let timestamp = latest.map_or(timestamp_now(), |latest| timestamp_now().max(latest + 1));
+
         let mut n = 0;
+        for id in &removed {
+            let trashed = self.conn.execute(
+                "UPDATE tasks SET deleted = ?1 WHERE id = ?2 AND deleted IS NULL",
+                params![timestamp, id],
+            )?;
+            if roots.contains(id) {
+                n += trashed;
+            }
+        }
+
+        self.journal_end(entry, &removed)?;
+        tx.commit()?;
+
+        Ok(n)
+    }
+
+    /// The tasks in the trash, most recently removed first.
+    pub fn trashed(&self) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TASK_COLUMNS} FROM tasks WHERE deleted IS NOT NULL ORDER BY deleted DESC"
+        ))?;
+
+        let mut tasks = stmt
+            .query_map([], |row| Task::try_from(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for task in &mut tasks {
+            self.load_relations(task)?;
+        }
+
+        Ok(tasks)
+    }
 
-        for tag in tags {
-            let mut stmt = self.conn.prepare(
-                "SELECT t.id
-                 FROM tasks t
-                 JOIN task_tags tt ON t.id = tt.task_id
-                 JOIN tags tg ON tt.tag_id = tg.id
-                 WHERE tg.name = ?1",
+    /// Restore a task from the trash, together with the subtasks that were removed with it and
+    /// any of its parents that are also in the trash. Returns the number of restored tasks.
+    pub fn restore(&mut self, id: String) -> Result<usize> {
+        let pattern = format!("{id}%");
+        let (full_id, deleted): (String, i64) = self
+            .conn
+            .query_row(
+                "SELECT id, deleted FROM tasks WHERE id LIKE ?1 AND deleted IS NOT NULL",
+                [&pattern],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .context(format!("No removed task found matching ID '{id}'"))?;
+
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE ancestors(id, parent_id) AS (
+                SELECT id, parent_id FROM tasks WHERE id = ?1
+                UNION
+                SELECT t.id, t.parent_id FROM tasks t JOIN ancestors a ON t.id = a.parent_id
+            )
+            SELECT id FROM ancestors",
+        )?;
+        let mut restored = stmt
+            .query_map([&full_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        // Subtasks removed separately, before their parent, stay in the trash.
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT id FROM tasks WHERE id = ?1
+                UNION
+                SELECT t.id FROM tasks t JOIN subtree s ON t.parent_id = s.id
+                WHERE t.deleted = ?2
+            )
+            SELECT id FROM subtree",
+        )?;
+        restored.extend(
+            stmt.query_map(params![full_id, deleted], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?,
+        );
+        restored.sort();
+        restored.dedup();
+
+        let tx = self.conn.unchecked_transaction()?;
+        let entry = self.journal_begin(&format!("restore {id}"), &restored)?;
+
+        let mut n = 0;
+        for id in &restored {
+            n += self.conn.execute(
+                "UPDATE tasks SET deleted = NULL WHERE id = ?1 AND deleted IS NOT NULL",
+                [id],
             )?;
+        }
 
-            let ids = stmt
-                .query_map([tag], |row| row.get(0))?
-                .collect::<rusqlite::Result<Vec<String>>>()?;
+        self.journal_end(entry, &restored)?;
+        tx.commit()?;
 
-            for id in ids {
-                let pattern = format!("{id}%");
-                n += self
-                    .conn
-                    .execute("DELETE FROM tasks WHERE id LIKE ?1", [&pattern])?;
+        Ok(n)
+    }
+
+    /// Permanently delete the tasks that were moved to the trash at least `days` days ago, or
+    /// every task in the trash if no age is given. Returns the number of deleted tasks.
+    pub fn empty_trash(&mut self, days: Option<i64>) -> Result<usize> {
+        let cutoff = days.map_or(i64::MAX, |days| timestamp_now() - days * 24 * 60 * 60);
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM tasks WHERE deleted IS NOT NULL AND deleted <= ?1")?;
+        let roots = stmt
+            .query_map([cutoff], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        let mut deleted = Vec::new();
+        for root in &roots {
+            for id in self.subtree_ids(root)? {
+                if !deleted.contains(&id) {
+                    deleted.push(id);
+                }
             }
         }
 
-        Ok(n)
+        if deleted.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let entry = self.journal_begin("trash empty", &deleted)?;
+
+        for id in &deleted {
+            self.conn.execute("DELETE FROM tasks WHERE id = ?1", [id])?;
+        }
+
+        self.journal_end(entry, &deleted)?;
+        tx.commit()?;
+
+        Ok(deleted.len())
+    }
+
+    /// Every task in the database, including completed and removed tasks, in the order they
+    /// were created.
+    pub fn all_tasks(&self) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {TASK_COLUMNS} FROM tasks ORDER BY created ASC, rowid ASC"
+        ))?;
+
+        let mut tasks = stmt
+            .query_map([], |row| Task::try_from(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for task in &mut tasks {
+            self.load_relations(task)?;
+        }
+
+        Ok(tasks)
     }
 
-    fn get_tags(&self, id: &String) -> Result<Vec<String>> {
+    /// Import tasks, keeping their IDs. Tasks with the same ID as an existing task replace it.
+    /// With [`ImportMode::Replace`], all existing tasks are removed first.
+    pub fn import_tasks(
+        &mut self,
+        tasks: Vec<Task>,
+        mode: ImportMode,
+        operation: &str,
+    ) -> Result<ImportSummary> {
+        let existing = self.all_ids()?;
+
+        for task in &tasks {
+            if let Some(pid) = &task.pid {
+                let pid = pid.to_string();
+                if !tasks.iter().any(|t| t.id.to_string() == pid)
+                    && (matches!(mode, ImportMode::Replace) || !existing.contains(&pid))
+                {
+                    bail!(
+                        "Task '{}' has parent ID '{pid}', which is not in the import or the database",
+                        task.title
+                    );
+                }
+            }
+
+            for blocker in task.blocked_by.as_deref().unwrap_or_default() {
+                let blocker = blocker.to_string();
+                if !tasks.iter().any(|t| t.id.to_string() == blocker)
+                    && (matches!(mode, ImportMode::Replace) || !existing.contains(&blocker))
+                {
+                    bail!(
+                        "Task '{}' is blocked by ID '{blocker}', which is not in the import or the database",
+                        task.title
+                    );
+                }
+            }
+        }
+
+        // Only the imported tasks are written, unless replacing removes every existing task.
+        let mut ids = match mode {
+            ImportMode::Replace => existing.clone(),
+            ImportMode::Merge => Vec::new(),
+        };
+        for task in &tasks {
+            let id = task.id.to_string();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let entry = self.journal_begin(operation, &ids)?;
+
+        // Parents may appear after their subtasks in the imported tasks.
+        self.conn.execute("PRAGMA defer_foreign_keys = ON", [])?;
+
+        if matches!(mode, ImportMode::Replace) {
+            self.conn.execute("DELETE FROM tasks", [])?;
+        }
+
+        let mut summary = ImportSummary::default();
+        for task in &tasks {
+            let id = task.id.to_string();
+            if matches!(mode, ImportMode::Merge) && existing.contains(&id) {
+                summary.updated += 1;
+            } else {
+                summary.added += 1;
+            }
+            self.upsert_task(task)?;
+        }
+
+        self.journal_end(entry, &ids)?;
+        tx.commit()?;
+
+        Ok(summary)
+    }
+
+    /// Insert a task with its ID, parent ID and timestamps as they are, replacing any existing
+    /// task with the same ID.
+    fn upsert_task(&self, task: &Task) -> Result<()> {
+        let to_secs = |t: SystemTime| {
+            t.duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs().cast_signed())
+                .unwrap_or_default()
+        };
+
+        self.conn.execute(
+            "INSERT INTO tasks (
+                id,
+                title,
+                description,
+                difficulty,
+                deadline,
+                parent_id,
+                created,
+                completed,
+                deleted,
+                recurrence,
+                series,
+                status,
+                waiting_until,
+                scheduled
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+            ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title,
+                description = excluded.description,
+                difficulty = excluded.difficulty,
+                deadline = excluded.deadline,
+                parent_id = excluded.parent_id,
+                created = excluded.created,
+                completed = excluded.completed,
+                deleted = excluded.deleted,
+                recurrence = excluded.recurrence,
+                series = excluded.series,
+                status = excluded.status,
+                waiting_until = excluded.waiting_until,
+                scheduled = excluded.scheduled",
+            params![
+                task.id,
+                task.title,
+                task.desc,
+                task.difficulty,
+                task.deadline,
+                task.pid,
+                to_secs(task.created),
+                task.completed.map(to_secs),
+                task.deleted.map(to_secs),
+                anchored_recurrence(task),
+                task.series,
+                task.status,
+                task.waiting_until,
+                task.scheduled,
+            ],
+        )?;
+
+        self.update_task_tags(&task.id, task.tags.as_deref().unwrap_or_default())?;
+
+        if let Some(blocked_by) = &task.blocked_by {
+            self.update_task_dependencies(&task.id, blocked_by)?;
+        }
+
+        Ok(())
+    }
+
+    /// The full IDs of all tasks matching a (possibly partial) ID.
+    fn matching_ids(&self, id: &str) -> Result<Vec<String>> {
+        let pattern = format!("{id}%");
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id FROM tasks WHERE id LIKE ?1 AND deleted IS NULL")?;
+        let ids = stmt
+            .query_map([&pattern], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+
+    /// The full IDs of every task, including completed and removed tasks.
+    pub fn all_ids(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT id FROM tasks")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+
+    /// The full ID of a task along with the IDs of all of its descendant subtasks.
+    fn subtree_ids(&self, id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "WITH RECURSIVE subtree(id) AS (
+                SELECT id FROM tasks WHERE id = ?1
+                UNION
+                SELECT t.id FROM tasks t JOIN subtree s ON t.parent_id = s.id
+            )
+            SELECT id FROM subtree",
+        )?;
+        let ids = stmt
+            .query_map([id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+
+    pub(crate) fn get_tags(&self, id: &String) -> Result<Vec<String>> {
         let mut stmt = self.conn.prepare(
             "SELECT tags.name 
                 FROM tags
@@ -374,21 +1088,97 @@ impl Database {
         Ok(tags)
     }
 
+    /// The full IDs of the tasks a task is blocked by.
+    fn get_blockers(&self, id: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT blocker_id FROM depends_on WHERE task_id = ?1 ORDER BY blocker_id")?;
+        let ids = stmt
+            .query_map([id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+
+    /// Fill in the tags, dependencies, tracked time and urgency of a task read from the tasks
+    /// table.
+    fn load_relations(&self, task: &mut Task) -> Result<()> {
+        let id = task.id.to_string();
+        task.tags = Some(self.get_tags(&id)?);
+        task.tracked = self.tracked_time(&id)?;
+        task.blocked_by = Some(self.get_blockers(&id)?.into_iter().map(ID::from).collect());
+        (task.blocked, task.blocking) = self.conn.query_row(
+            &format!("SELECT {BLOCKED}, {BLOCKING} FROM tasks WHERE id = ?1"),
+            [&id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        task.urgency = self.urgency(task).total();
+        Ok(())
+    }
+
+    /// The urgency of a task, weighted by the coefficients in the config.
+    pub fn urgency(&self, task: &Task) -> Urgency {
+        Urgency::of(task, &self.config.urgency, Local::now().date_naive())
+    }
+
+    /// The full ID of the only task matching a (possibly partial) ID.
+    pub(crate) fn resolve_id(&self, id: &str) -> Result<String> {
+        match self.matching_ids(id)?.as_slice() {
+            [full] => Ok(full.clone()),
+            [] => bail!("No task found matching ID '{id}'"),
+            _ => bail!("ID '{id}' matches more than one task"),
+        }
+    }
+
+    /// Replace the tasks a task is blocked by, refusing dependencies that would form a cycle.
+    fn update_task_dependencies(&self, task_id: &ID, blockers: &[ID]) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM depends_on WHERE task_id = ?1", [task_id])?;
+
+        for blocker in blockers {
+            let cycle: bool = self.conn.query_row(
+                "WITH RECURSIVE blockers(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT d.blocker_id FROM depends_on d JOIN blockers b ON d.task_id = b.id
+                )
+                SELECT EXISTS (SELECT 1 FROM blockers WHERE id = ?2)",
+                (blocker, task_id),
+                |row| row.get(0),
+            )?;
+            if cycle {
+                bail!(
+                    "Task {} cannot be blocked by {}, as that would create a dependency cycle",
+                    task_id.short(),
+                    blocker.short()
+                );
+            }
+
+            self.conn.execute(
+                "INSERT OR IGNORE INTO depends_on (task_id, blocker_id) VALUES (?1, ?2)",
+                (task_id, blocker),
+            )?;
+        }
+
+        Ok(())
+    }
+
     pub fn get_task(&self, id: &str) -> Result<Task> {
         let pattern = format!("{id}%");
-        let mut task =
-            self.conn
-                .query_row("SELECT id, title, description, difficulty, deadline, parent_id, created, completed FROM tasks WHERE id LIKE ?1", [&pattern], |row| {
-                    Task::try_from(row)
-                })
-        .context(format!("No task found matching ID '{id}'"))?;
+        let mut task = self
+            .conn
+            .query_row(
+                &format!("SELECT {TASK_COLUMNS} FROM tasks WHERE id LIKE ?1 AND deleted IS NULL"),
+                [&pattern],
+                |row| Task::try_from(row),
+            )
+            .context(format!("No task found matching ID '{id}'"))?;
 
-        let tags = self.get_tags(&task.id.clone().into())?;
-        task.tags = Some(tags);
+        self.load_relations(&mut task)?;
 
         Ok(task)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn get_tasks(
         &self,
         tags: Option<Vec<String>>,
@@ -397,15 +1187,14 @@ impl Database {
         after: Option<Deadline>,
         all: bool,
         only_completed: bool,
+        series: Option<String>,
+        statuses: Option<Vec<Status>>,
+        include_deferred: bool,
     ) -> Result<Vec<Task>> {
-        let mut query = String::from(
-            "SELECT DISTINCT t.id, t.title, t.description, t.difficulty, t.deadline, 
-                t.parent_id, t.created, t.completed
-         FROM tasks t",
-        );
+        let mut query = format!("SELECT DISTINCT {} FROM tasks t", task_columns("t"));
 
         let mut joins = Vec::new();
-        let mut conditions = Vec::new();
+        let mut conditions = vec!["t.deleted IS NULL".to_string()];
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
         let mut group_by_clause = String::new();
 
@@ -423,9 +1212,8 @@ impl Database {
             }
 
             group_by_clause = format!(
-                " GROUP BY t.id, t.title, t.description, t.difficulty, t.deadline, 
-                          t.parent_id, t.created, t.completed
-                 HAVING COUNT(DISTINCT tg.name) = {}",
+                " GROUP BY {} HAVING COUNT(DISTINCT tg.name) = {}",
+                task_columns("t"),
                 tag_list.len()
             );
         }
@@ -438,19 +1226,47 @@ impl Database {
 
         if let Some(deadline) = &before {
             conditions.push("t.deadline IS NOT NULL".to_string());
-            conditions.push("t.deadline <= ?".to_string());
-            params.push(Box::new(deadline.to_string()));
+            conditions.push(format!("{} <= ?", local_date("t.deadline")));
+            params.push(Box::new(deadline.date().to_string()));
         }
 
         if let Some(deadline) = &after {
             conditions.push("t.deadline IS NOT NULL".to_string());
-            conditions.push("t.deadline >= ?".to_string());
-            params.push(Box::new(deadline.to_string()));
+            conditions.push(format!("{} >= ?", local_date("t.deadline")));
+            params.push(Box::new(deadline.date().to_string()));
+        }
+
+        // A series includes its completed tasks, which record when it has been done.
+        if let Some(id) = &series {
+            conditions.push(
+                "COALESCE(t.series, t.id) IN
+                    (SELECT COALESCE(series, id) FROM tasks WHERE id LIKE ?)"
+                    .to_string(),
+            );
+            params.push(Box::new(format!("{id}%")));
         }
 
+        if let Some(statuses) = &statuses
+            && !statuses.is_empty()
+        {
+            let placeholders = statuses.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+            conditions.push(format!("{} IN ({placeholders})", current_status("t")));
+            for status in statuses {
+                params.push(Box::new(*status));
+            }
+        }
+
+        if !include_deferred {
+            conditions.push(format!(
+                "(t.scheduled IS NULL OR {} OR t.completed IS NOT NULL)",
+                reached("t.scheduled")
+            ));
+        }
+
+        // Filtering by status decides whether completed tasks are shown.
         if only_completed {
             conditions.push("t.completed IS NOT NULL".to_string());
-        } else if !all {
+        } else if !all && series.is_none() && statuses.is_none() {
             conditions.push("t.completed IS NULL".to_string());
         }
 
@@ -468,12 +1284,13 @@ impl Database {
             query.push_str(&group_by_clause);
         }
 
-        query.push_str(
-            " ORDER BY 
+        query.push_str(&format!(
+            " ORDER BY
             CASE WHEN t.deadline IS NULL THEN 1 ELSE 0 END,
-            t.deadline ASC,
+            {} ASC,
             t.difficulty DESC",
-        );
+            local_key("t.deadline")
+        ));
 
         let mut stmt = self.conn.prepare(&query)?;
         let mut tasks = stmt
@@ -484,9 +1301,9 @@ impl Database {
             .collect::<Result<Vec<_>, _>>()?;
 
         for task in &mut tasks {
-            let tags = self.get_tags(&task.id.clone().into())?;
-            task.tags = Some(tags);
+            self.load_relations(task)?;
         }
+        tasks.sort_by(|a, b| b.urgency.total_cmp(&a.urgency));
 
         Ok(tasks)
     }
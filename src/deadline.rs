@@ -1,82 +1,603 @@
-use anyhow::Result;
-use chrono::{Datelike, Duration, Local, NaiveDate};
+use anyhow::{Result, anyhow, bail};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Months, NaiveDate, NaiveTime, SecondsFormat,
+    TimeZone, Utc,
+};
+use clap::ValueEnum;
 use colored::Colorize;
 use rusqlite::ToSql;
-use std::fmt::Display;
+use serde::Deserialize;
+use std::{fmt::Display, sync::OnceLock};
 
+use crate::calendar::Calendar;
+
+/// How numeric dates are read and shown. Dates written year first (2026-02-10) are read the
+/// same way in every format.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DateFormat {
+    /// Show dates as 2026-02-10. Day-first and month-first dates are only read if they cannot
+    /// be read the other way round.
+    #[default]
+    Iso,
+    /// Show and read dates day first, as 10/02/2026
+    Uk,
+    /// Show and read dates month first, as 02/10/2026
+    Us,
+}
+
+static DATE_FORMAT: OnceLock<DateFormat> = OnceLock::new();
+
+impl DateFormat {
+    /// The date format in use, which is [`DateFormat::Iso`] until another is set.
+    pub fn current() -> Self {
+        DATE_FORMAT.get().copied().unwrap_or_default()
+    }
+
+    /// Use this date format for the rest of the process. Only the first call has an effect.
+    pub fn set(self) {
+        let _ = DATE_FORMAT.set(self);
+    }
+
+    /// The `strftime` pattern dates are shown with.
+    pub fn pattern(self) -> &'static str {
+        match self {
+            DateFormat::Iso => "%Y-%m-%d",
+            DateFormat::Uk => "%d/%m/%Y",
+            DateFormat::Us => "%m/%d/%Y",
+        }
+    }
+}
+
+/// A date, optionally with a time of day. Deadlines with a time are stored in UTC, and are
+/// compared and shown in the local timezone.
 #[derive(Default, Debug)]
 pub struct Deadline {
+    /// The local date of the deadline.
     date: NaiveDate,
+    time: Option<DateTime<Utc>>,
 }
 
 impl Deadline {
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    /// The moment the deadline falls due, if it has a time of day.
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        self.time
+    }
+
+    /// Whether the deadline has been reached: its day has come, or its time has passed.
+    pub fn is_reached(&self) -> bool {
+        self.sortable() <= Deadline::from(Utc::now()).sortable()
+    }
+
+    /// The deadline moved to another date, keeping its local time of day.
+    pub fn on(&self, date: NaiveDate) -> Self {
+        match self.time {
+            Some(time) => Self::at(date, time.with_timezone(&Local).time(), None)
+                .unwrap_or_else(|_| date.into()),
+            None => date.into(),
+        }
+    }
+
+    /// The deadline with its date written year first, as 2026-02-10 or 2026-02-10 15:00, which
+    /// is read back the same way in every [`DateFormat`].
+    pub fn iso(&self) -> String {
+        self.format(DateFormat::Iso)
+    }
+
+    fn format(&self, format: DateFormat) -> String {
+        match self.time {
+            Some(time) => time
+                .with_timezone(&Local)
+                .format(&format!("{} %H:%M", format.pattern()))
+                .to_string(),
+            None => self.date.format(format.pattern()).to_string(),
+        }
+    }
+
+    /// The deadline as it is sorted and compared: the local date, followed by the local time
+    /// of day if it has one, as 2026-02-10 or 2026-02-10T15:00. A date sorts before the times
+    /// on that day, so a deadline without a time is reached when its day starts.
+    pub fn sortable(&self) -> String {
+        match self.time {
+            Some(time) => time
+                .with_timezone(&Local)
+                .format("%Y-%m-%dT%H:%M")
+                .to_string(),
+            None => self.date.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    /// The deadline as it is stored and exported: the date if there is no time of day, and an
+    /// RFC 3339 UTC time otherwise, which is the same moment in every timezone.
+    pub fn rfc3339(&self) -> String {
+        match self.time {
+            Some(time) => time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            None => self.date.format("%Y-%m-%d").to_string(),
+        }
+    }
+
     pub fn days_until(&self) -> String {
-        let days_until = (self.date - Local::now().date_naive()).num_days();
+        // Deadlines with a time count down in hours once they are less than a day away.
+        if let Some(time) = self.time {
+            let remaining = time - Utc::now();
+            if remaining.num_hours().abs() < 24 {
+                let res = match (remaining.num_hours(), remaining.num_minutes()) {
+                    (0, minutes) if minutes >= 0 => format!("in {minutes} minutes"),
+                    (0, minutes) => format!("{} minutes ago", -minutes),
+                    (hours, _) if hours > 0 => format!("in {hours} hours"),
+                    (hours, _) => format!("{} hours ago", -hours),
+                };
+                return res.red().to_string();
+            }
+        }
+
+        let today = Local::now().date_naive();
+        let days_until = (self.date - today).num_days();
         if days_until < 0 {
             format!("{} days ago", -days_until).red().to_string()
         } else {
             let mut res = format!("in {days_until} days").to_string();
             if res == "in 0 days" {
                 res = res.red().to_string();
+            } else if Calendar::current().count_workdays {
+                let workdays = Calendar::current().workdays_until(today, self.date);
+                res = format!("{res}, {workdays} working days");
             }
             res
         }
     }
 
+    /// Parse a date, optionally followed by a time of day and a timezone, such as `friday`,
+    /// `tomorrow 9am`, `friday at 15:00` or `2026-02-10 09:00 utc`. Times without a timezone
+    /// are local, and a time on its own is today.
     pub fn parse(input: &str) -> Result<Self> {
-        let input = input.trim().to_lowercase();
-        let today = Local::now().date_naive();
+        let input = input.trim();
+        if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+            return Ok(time.with_timezone(&Utc).into());
+        }
+
+        let input = input.to_lowercase();
+        let mut words: Vec<&str> = input.split_whitespace().collect();
 
-        let deadline = match input.as_str() {
-            "today" => today,
-            "tomorrow" | "tmr" => today + Duration::days(1),
-
-            "monday" | "mon" => next_weekday(today, chrono::Weekday::Mon),
-            "tuesday" | "tue" => next_weekday(today, chrono::Weekday::Tue),
-            "wednesday" | "wed" => next_weekday(today, chrono::Weekday::Wed),
-            "thursday" | "thu" => next_weekday(today, chrono::Weekday::Thu),
-            "friday" | "fri" => next_weekday(today, chrono::Weekday::Fri),
-            "saturday" | "sat" => next_weekday(today, chrono::Weekday::Sat),
-            "sunday" | "sun" => next_weekday(today, chrono::Weekday::Sun),
-
-            "week" | "1week" | "1w" => today + Duration::weeks(1),
-            "2weeks" | "2w" => today + Duration::weeks(2),
-            "month" | "1month" | "1m" => today + Duration::days(30),
-            "3months" | "3m" => today + Duration::days(90),
-
-            "eow" | "endofweek" => end_of_week(today),
-            "eom" | "endofmonth" => end_of_month(today),
-            "eoy" | "endofyear" => end_of_year(today),
-
-            _ => {
-                let rel_duration = parse_relative_duration(&input);
-                match rel_duration {
-                    Some(days) => today + Duration::days(days),
-                    None => NaiveDate::parse_from_str(&input, "%Y-%m-%d")
-                        .or_else(|_| NaiveDate::parse_from_str(&input, "%d/%m/%Y"))
-                        .or_else(|_| NaiveDate::parse_from_str(&input, "%m-%d-%Y"))
-                        .map_err(|_| {
-                            anyhow::anyhow!(
-                                "Invalid deadline format. Use: today, tomorrow, monday, +5d, 2026-02-10, etc. See `todo add --help` for more information."
-                                )
-                        })?,
-                }
+        let mut offset = None;
+        if let [.., time, zone] = words.as_slice()
+            && parse_time_of_day(time).is_some()
+        {
+            offset = parse_offset(zone);
+            if offset.is_some() {
+                words.pop();
             }
+        }
+
+        let time = words.last().and_then(|word| parse_time_of_day(word));
+        if time.is_some() {
+            words.pop();
+            if words.last() == Some(&"at") {
+                words.pop();
+            }
+        }
+
+        let date = match (words.is_empty(), time) {
+            (true, Some(_)) => Local::now().date_naive(),
+            _ => parse_date(&words.join(" "))?,
         };
-        Ok(Self { date: deadline })
+
+        match time {
+            Some(time) => Self::at(date, time, offset),
+            None => Ok(date.into()),
+        }
+    }
+
+    /// Parse the start of a date range that looks back from today, in which a weekday refers
+    /// to its most recent occurrence (today included) rather than the next one.
+    pub fn parse_since(input: &str) -> Result<Self> {
+        match parse_weekday(&input.trim().to_lowercase()) {
+            Some(weekday) => Ok(previous_weekday(Local::now().date_naive(), weekday).into()),
+            None => Self::parse(input),
+        }
+    }
+
+    /// A deadline at a time of day on a date, in the given timezone or the local one.
+    fn at(date: NaiveDate, time: NaiveTime, offset: Option<FixedOffset>) -> Result<Self> {
+        let local = date.and_time(time);
+        let time = match offset {
+            Some(offset) => offset.from_local_datetime(&local).single(),
+            None => Local
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|t| t.fixed_offset()),
+        }
+        .ok_or_else(|| anyhow!("{local} does not exist in the local timezone"))?;
+
+        Ok(time.with_timezone(&Utc).into())
+    }
+}
+
+impl From<NaiveDate> for Deadline {
+    fn from(date: NaiveDate) -> Self {
+        Self { date, time: None }
+    }
+}
+
+impl From<DateTime<Utc>> for Deadline {
+    fn from(time: DateTime<Utc>) -> Self {
+        Self {
+            date: time.with_timezone(&Local).date_naive(),
+            time: Some(time),
+        }
     }
 }
 
 impl Display for Deadline {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.date.format("%Y-%m-%d"))
+        write!(f, "{}", self.format(DateFormat::current()))
     }
 }
 
 impl ToSql for Deadline {
     fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
-        Ok(self.date.to_string().into())
+        Ok(self.rfc3339().into())
+    }
+}
+
+/// Parse a date expression on its own, such as `friday`, `+5d` or `end of next month`, from
+/// lowercase input.
+fn parse_date(input: &str) -> Result<NaiveDate> {
+    if let Some(date) = parse_expression(input, Local::now().date_naive(), Calendar::current()) {
+        return Ok(date);
+    }
+    if let Some(date) = parse_numeric(input, DateFormat::current())? {
+        return Ok(date);
+    }
+
+    bail!(
+        "Invalid deadline format '{input}'. Use: today, friday, next friday, +5d, +1m, in 2 weekdays, end of next month, eoq, 2026-02-10, friday 15:00, etc. See `todo add --help` for more information."
+    )
+}
+
+/// Parse a date written day first or month first, such as `10/02/2026`, `10-02-2026` or
+/// `10.02.2026`. The date format decides which way round it is read, and dates that could
+/// be read either way are refused rather than guessed in the ISO format.
+fn parse_numeric(input: &str, format: DateFormat) -> Result<Option<NaiveDate>> {
+    let parts: Vec<&str> = input.split(['/', '-', '.']).collect();
+    let [first, second, year] = parts.as_slice() else {
+        return Ok(None);
+    };
+    if first.len() > 2 || second.len() > 2 || year.len() != 4 {
+        return Ok(None);
+    }
+    let (Ok(first), Ok(second), Ok(year)) = (first.parse(), second.parse(), year.parse()) else {
+        return Ok(None);
+    };
+
+    let day_first = NaiveDate::from_ymd_opt(year, second, first);
+    let month_first = NaiveDate::from_ymd_opt(year, first, second);
+
+    let date = match (format, day_first, month_first) {
+        (DateFormat::Uk, date, _) => date,
+        (DateFormat::Us, _, date) => date,
+        (DateFormat::Iso, Some(day), Some(month)) if day != month => bail!(
+            "Ambiguous date '{input}', which could be {} or {}. Write it as YYYY-MM-DD, or choose how dates are read with `--date-format uk` or `--date-format us`",
+            day.format("%-d %B %Y"),
+            month.format("%-d %B %Y")
+        ),
+        (DateFormat::Iso, day, month) => day.or(month),
+    };
+
+    match date {
+        Some(date) => Ok(Some(date)),
+        None if format == DateFormat::Iso => Ok(None),
+        None => bail!(
+            "Invalid date '{input}'. Dates are read as {}",
+            match format {
+                DateFormat::Uk => "DD/MM/YYYY",
+                _ => "MM/DD/YYYY",
+            }
+        ),
+    }
+}
+
+/// A span of the calendar that dates can be counted in or rounded to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Period {
+    Day,
+    BusinessDay,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+/// A number of days, working days, weeks, months, quarters or years, such as `3d` or `1m`.
+/// Months, quarters and years follow the calendar, and working days the working calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    n: i64,
+    period: Period,
+}
+
+impl Span {
+    /// Parse a span written as an offset from today, such as `3d`, `2 weeks` or `a month`,
+    /// which is negative if it looks back, as in `3 days ago`.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        span(&input.split_whitespace().collect::<Vec<_>>())
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.n > 0
+    }
+
+    /// The date this span after `date`, if it is in range.
+    pub fn after(self, date: NaiveDate) -> Option<NaiveDate> {
+        add_periods(date, self.period, self.n, Calendar::current())
+    }
+
+    /// The date this span before `date`, if it is in range.
+    pub fn before(self, date: NaiveDate) -> Option<NaiveDate> {
+        add_periods(date, self.period, -self.n, Calendar::current())
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = match self.period {
+            Period::Day => "d",
+            Period::BusinessDay => "bd",
+            Period::Week => "w",
+            Period::Month => "m",
+            Period::Quarter => "q",
+            Period::Year => "y",
+        };
+        write!(f, "{}{unit}", self.n)
+    }
+}
+
+/// Resolve a date expression relative to `today`. Expressions are made up of:
+///
+/// * keywords: `today`, `tomorrow`, `yesterday`, `eow`, `eom`, `eoq`, `eoy`
+/// * weekdays: `friday` (the next one), `this friday`, `next friday`, `last friday`
+/// * offsets: `+5d`, `2w`, `in 3 months`, `a year`, `in 2 weekdays`, `3bd`, `2 days ago`
+/// * period boundaries: `next month`, `end of next month`, `start of this quarter`,
+///   `last day of the year`
+/// * dates written year first: `2026-02-10`
+///
+/// Months, quarters and years follow the calendar, ending on the last day of a shorter month.
+/// Business days and the end of the week follow the working calendar.
+fn parse_expression(input: &str, today: NaiveDate, calendar: &Calendar) -> Option<NaiveDate> {
+    let words: Vec<&str> = input
+        .split_whitespace()
+        .filter(|word| *word != "the")
+        .collect();
+
+    keyword(&words, today, calendar)
+        .or_else(|| weekday(&words, today))
+        .or_else(|| boundary(&words, today, calendar))
+        .or_else(|| offset(&words, today, calendar))
+        .or_else(|| exact(input))
+}
+
+fn keyword(words: &[&str], today: NaiveDate, calendar: &Calendar) -> Option<NaiveDate> {
+    match words {
+        ["today"] => Some(today),
+        ["tomorrow" | "tmr"] => Some(today + Duration::days(1)),
+        ["yesterday"] => Some(today - Duration::days(1)),
+        ["eow" | "endofweek"] => Some(calendar.end_of_week(start_of(today, Period::Week))),
+        ["eom" | "endofmonth"] => Some(end_of(today, Period::Month)),
+        ["eoq" | "endofquarter"] => Some(end_of(today, Period::Quarter)),
+        ["eoy" | "endofyear"] => Some(end_of(today, Period::Year)),
+        _ => None,
+    }
+}
+
+/// A weekday on its own is the next one after today. `this` and `next` pick the day in this
+/// week or next week (Monday to Sunday), and `last` the most recent one before today.
+fn weekday(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    let (qualifier, day) = match words {
+        [day] => (None, day),
+        [qualifier @ ("this" | "next" | "last"), day] => (Some(*qualifier), day),
+        _ => return None,
+    };
+    let weekday = parse_weekday(day)?;
+    let this_week =
+        start_of(today, Period::Week) + Duration::days(i64::from(weekday.num_days_from_monday()));
+
+    Some(match qualifier {
+        None => next_weekday(today, weekday),
+        Some("this") => this_week,
+        Some("next") => this_week + Duration::weeks(1),
+        _ => previous_weekday(today - Duration::days(1), weekday),
+    })
+}
+
+/// The start or end of a week, month, quarter or year. A qualified period on its own, such as
+/// `next month`, is its start.
+fn boundary(words: &[&str], today: NaiveDate, calendar: &Calendar) -> Option<NaiveDate> {
+    let (end, rest) = match words {
+        ["end", "of", rest @ ..] | ["last", "day", "of", rest @ ..] => (true, rest),
+        ["start" | "beginning", "of", rest @ ..] | ["first", "day", "of", rest @ ..] => {
+            (false, rest)
+        }
+        ["this" | "next" | "last", _] => (false, words),
+        _ => return None,
+    };
+
+    let (shift, period) = match rest {
+        [period] | ["this", period] => (0, period),
+        ["next", period] => (1, period),
+        ["last" | "previous", period] => (-1, period),
+        _ => return None,
+    };
+    let period =
+        parse_period(period).filter(|p| !matches!(p, Period::Day | Period::BusinessDay))?;
+
+    let date = add_periods(start_of(today, period), period, shift, calendar)?;
+    Some(match (end, period) {
+        (true, Period::Week) => calendar.end_of_week(date),
+        (true, _) => end_of(date, period),
+        (false, _) => start_of(date, period),
+    })
+}
+
+/// A number of periods from today, such as `+5d`, `in 2 weeks`, `a month` or `3 days ago`.
+fn offset(words: &[&str], today: NaiveDate, calendar: &Calendar) -> Option<NaiveDate> {
+    let span = span(words)?;
+    add_periods(today, span.period, span.n, calendar)
+}
+
+/// A number of periods, such as `+5d`, `in 2 weeks`, `a month` or `3 days ago`. The number
+/// defaults to 1 and may be written together with the period.
+fn span(words: &[&str]) -> Option<Span> {
+    let (words, ago) = match words {
+        [rest @ .., "ago"] => (rest, true),
+        _ => (words, false),
+    };
+    let words = words.strip_prefix(&["in"]).unwrap_or(words);
+
+    let joined = match words {
+        ["a" | "an", rest @ ..] => format!("1{}", rest.concat()),
+        _ => words.concat(),
+    };
+    let (sign, rest) = match joined.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, joined.strip_prefix('+').unwrap_or(&joined)),
+    };
+
+    let (number, unit) = rest.split_at(rest.chars().take_while(char::is_ascii_digit).count());
+    let n = match number {
+        "" => 1,
+        number => number.parse::<i64>().ok()?,
+    };
+
+    Some(Span {
+        n: if ago { -n } else { n } * sign,
+        period: parse_period(unit)?,
+    })
+}
+
+fn exact(input: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(input, "%Y-%m-%d").ok()
+}
+
+fn parse_period(unit: &str) -> Option<Period> {
+    match unit {
+        "d" | "day" | "days" => Some(Period::Day),
+        "bd" | "weekday" | "weekdays" | "businessday" | "businessdays" | "workday" | "workdays" => {
+            Some(Period::BusinessDay)
+        }
+        "w" | "wk" | "week" | "weeks" => Some(Period::Week),
+        "m" | "mo" | "month" | "months" => Some(Period::Month),
+        "q" | "quarter" | "quarters" => Some(Period::Quarter),
+        "y" | "yr" | "year" | "years" => Some(Period::Year),
+        _ => None,
+    }
+}
+
+/// Move a date by a number of periods, backwards if `n` is negative.
+fn add_periods(date: NaiveDate, period: Period, n: i64, calendar: &Calendar) -> Option<NaiveDate> {
+    let months = |months: i64| {
+        let m = Months::new(u32::try_from(months.unsigned_abs()).ok()?);
+        if months < 0 {
+            date.checked_sub_months(m)
+        } else {
+            date.checked_add_months(m)
+        }
+    };
+
+    match period {
+        Period::Day => date.checked_add_signed(Duration::try_days(n)?),
+        Period::BusinessDay => Some(calendar.add_workdays(date, n)),
+        Period::Week => date.checked_add_signed(Duration::try_weeks(n)?),
+        Period::Month => months(n),
+        Period::Quarter => months(n.checked_mul(3)?),
+        Period::Year => months(n.checked_mul(12)?),
+    }
+}
+
+/// The first day of the period containing a date. Weeks start on Monday.
+fn start_of(date: NaiveDate, period: Period) -> NaiveDate {
+    let first_month = |month: u32| NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap();
+    match period {
+        Period::Day | Period::BusinessDay => date,
+        Period::Week => date - Duration::days(i64::from(date.weekday().num_days_from_monday())),
+        Period::Month => first_month(date.month()),
+        Period::Quarter => first_month(date.month0() / 3 * 3 + 1),
+        Period::Year => first_month(1),
+    }
+}
+
+/// The last day of the period containing a date. Weeks end on Sunday.
+fn end_of(date: NaiveDate, period: Period) -> NaiveDate {
+    match period {
+        Period::Day | Period::BusinessDay => date,
+        Period::Week => start_of(date, period) + Duration::days(6),
+        Period::Month => date
+            .with_day(days_in_month(date.year(), date.month()))
+            .unwrap(),
+        Period::Quarter | Period::Year => {
+            let months = if period == Period::Quarter { 3 } else { 12 };
+            start_of(date, period) + Months::new(months) - Duration::days(1)
+        }
+    }
+}
+
+/// Parse a time of day, either on the 24 hour clock (`15:00`) or with `am` or `pm` (`9am`,
+/// `9:30pm`), from lowercase input.
+fn parse_time_of_day(input: &str) -> Option<NaiveTime> {
+    let (clock, pm) = match (input.strip_suffix("am"), input.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, false),
+        (_, Some(clock)) => (clock, true),
+        _ => {
+            return NaiveTime::parse_from_str(input, "%H:%M")
+                .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M:%S"))
+                .ok();
+        }
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None => (clock.parse::<u32>().ok()?, 0),
+    };
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+
+    NaiveTime::from_hms_opt(hour % 12 + if pm { 12 } else { 0 }, minute, 0)
+}
+
+/// Parse a timezone, either `utc` or an offset from UTC such as `+02:00`, `+0200` or `-5`,
+/// from lowercase input.
+fn parse_offset(input: &str) -> Option<FixedOffset> {
+    if matches!(input, "utc" | "gmt" | "z") {
+        return FixedOffset::east_opt(0);
+    }
+
+    let (sign, digits) = match input.split_at_checked(1)? {
+        ("+", digits) => (1, digits.replace(':', "")),
+        ("-", digits) => (-1, digits.replace(':', "")),
+        _ => return None,
+    };
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    if hours > 14 || minutes > 59 {
+        return None;
     }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
 }
 
 fn next_weekday(from: NaiveDate, target: chrono::Weekday) -> NaiveDate {
@@ -92,25 +613,30 @@ fn next_weekday(from: NaiveDate, target: chrono::Weekday) -> NaiveDate {
     }
 }
 
-fn end_of_week(date: NaiveDate) -> NaiveDate {
-    let days_until_sunday = i64::from(6 - date.weekday().num_days_from_monday());
-    date + Duration::days(days_until_sunday)
-}
+fn previous_weekday(from: NaiveDate, target: chrono::Weekday) -> NaiveDate {
+    let days_behind = (i64::from(from.weekday().num_days_from_monday())
+        - i64::from(target.num_days_from_monday())
+        + 7)
+        % 7;
 
-fn end_of_month(date: NaiveDate) -> NaiveDate {
-    NaiveDate::from_ymd_opt(
-        date.year(),
-        date.month(),
-        days_in_month(date.year(), date.month()),
-    )
-    .unwrap()
+    from - Duration::days(days_behind)
 }
 
-fn end_of_year(date: NaiveDate) -> NaiveDate {
-    NaiveDate::from_ymd_opt(date.year(), 12, 31).unwrap()
+/// Parse the full or abbreviated name of a weekday, in lowercase.
+pub(crate) fn parse_weekday(input: &str) -> Option<chrono::Weekday> {
+    match input {
+        "monday" | "mon" => Some(chrono::Weekday::Mon),
+        "tuesday" | "tue" => Some(chrono::Weekday::Tue),
+        "wednesday" | "wed" => Some(chrono::Weekday::Wed),
+        "thursday" | "thu" => Some(chrono::Weekday::Thu),
+        "friday" | "fri" => Some(chrono::Weekday::Fri),
+        "saturday" | "sat" => Some(chrono::Weekday::Sat),
+        "sunday" | "sun" => Some(chrono::Weekday::Sun),
+        _ => None,
+    }
 }
 
-fn days_in_month(year: i32, month: u32) -> u32 {
+pub(crate) fn days_in_month(year: i32, month: u32) -> u32 {
     NaiveDate::from_ymd_opt(year, month + 1, 1)
         .unwrap_or(NaiveDate::from_ymd_opt(year + 1, 1, 1).unwrap())
         .pred_opt()
@@ -118,27 +644,179 @@ fn days_in_month(year: i32, month: u32) -> u32 {
         .day()
 }
 
-fn parse_relative_duration(input: &str) -> Option<i64> {
-    let cleaned = input.replace("in ", "").replace('+', "").trim().to_string();
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::calendar::WeekEnd;
+
+    fn date(input: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(input, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_calendar_months() {
+        let resolve = |input, today| parse_expression(input, date(today), &Calendar::default());
+
+        assert_eq!(resolve("+1m", "2026-01-31"), Some(date("2026-02-28")));
+        assert_eq!(resolve("1 month", "2028-01-31"), Some(date("2028-02-29")));
+        assert_eq!(resolve("3months", "2026-11-30"), Some(date("2027-02-28")));
+        assert_eq!(resolve("in a year", "2028-02-29"), Some(date("2029-02-28")));
+        assert_eq!(resolve("+2w", "2026-01-31"), Some(date("2026-02-14")));
+        assert_eq!(
+            resolve("3 days ago", "2026-03-01"),
+            Some(date("2026-02-26"))
+        );
+        assert_eq!(resolve("-1m", "2026-03-31"), Some(date("2026-02-28")));
+    }
+
+    #[test]
+    fn test_qualified_weekdays() {
+        // 2026-10-14 is a Wednesday.
+        let resolve = |input| parse_expression(input, date("2026-10-14"), &Calendar::default());
+
+        assert_eq!(resolve("friday"), Some(date("2026-10-16")));
+        assert_eq!(resolve("wed"), Some(date("2026-10-21")));
+        assert_eq!(resolve("this monday"), Some(date("2026-10-12")));
+        assert_eq!(resolve("next friday"), Some(date("2026-10-23")));
+        assert_eq!(resolve("last wednesday"), Some(date("2026-10-07")));
+        assert_eq!(resolve("next week"), Some(date("2026-10-19")));
+    }
+
+    #[test]
+    fn test_business_days() {
+        // 2026-10-16 is a Friday.
+        let resolve = |input| parse_expression(input, date("2026-10-16"), &Calendar::default());
+
+        assert_eq!(resolve("in 2 weekdays"), Some(date("2026-10-20")));
+        assert_eq!(resolve("+1bd"), Some(date("2026-10-19")));
+        assert_eq!(resolve("5 business days"), Some(date("2026-10-23")));
+        assert_eq!(resolve("2 workdays ago"), Some(date("2026-10-14")));
+
+        let calendar = Calendar {
+            holidays: [date("2026-10-19")].into(),
+            end_of_week: WeekEnd::LastWorkday,
+            ..Calendar::default()
+        };
+        let resolve = |input| parse_expression(input, date("2026-10-16"), &calendar);
+
+        assert_eq!(resolve("+1bd"), Some(date("2026-10-20")));
+        assert_eq!(resolve("eow"), Some(date("2026-10-16")));
+        assert_eq!(resolve("end of next week"), Some(date("2026-10-23")));
+    }
+
+    #[test]
+    fn test_period_boundaries() {
+        let resolve = |input| parse_expression(input, date("2026-11-15"), &Calendar::default());
 
-    if let Some(num_str) = cleaned
-        .strip_suffix('d')
-        .or_else(|| cleaned.strip_suffix("days"))
-    {
-        return num_str.trim().parse::<i64>().ok();
+        assert_eq!(resolve("end of next month"), Some(date("2026-12-31")));
+        assert_eq!(resolve("last day of quarter"), Some(date("2026-12-31")));
+        assert_eq!(resolve("end of the quarter"), Some(date("2026-12-31")));
+        assert_eq!(resolve("start of next quarter"), Some(date("2027-01-01")));
+        assert_eq!(resolve("first day of last month"), Some(date("2026-10-01")));
+        assert_eq!(resolve("eoq"), Some(date("2026-12-31")));
+        assert_eq!(resolve("eom"), Some(date("2026-11-30")));
+        assert_eq!(resolve("eow"), Some(date("2026-11-15")));
+        assert_eq!(resolve("next year"), Some(date("2027-01-01")));
+        assert_eq!(resolve("end of day"), None);
+        assert_eq!(resolve("someday"), None);
     }
-    if let Some(num_str) = cleaned
-        .strip_suffix('w')
-        .or_else(|| cleaned.strip_suffix("weeks"))
-    {
-        return num_str.trim().parse::<i64>().ok().map(|n| n * 7);
+
+    #[test]
+    fn test_numeric_dates() {
+        let read = |input, format| parse_numeric(input, format).unwrap();
+
+        assert_eq!(read("10/02/2026", DateFormat::Uk), Some(date("2026-02-10")));
+        assert_eq!(read("10-02-2026", DateFormat::Uk), Some(date("2026-02-10")));
+        assert_eq!(read("02/10/2026", DateFormat::Us), Some(date("2026-02-10")));
+        assert_eq!(read("02.10.2026", DateFormat::Us), Some(date("2026-02-10")));
+        assert_eq!(
+            read("23/01/2026", DateFormat::Iso),
+            Some(date("2026-01-23"))
+        );
+        assert_eq!(
+            read("01-23-2026", DateFormat::Iso),
+            Some(date("2026-01-23"))
+        );
+        assert_eq!(
+            read("05/05/2026", DateFormat::Iso),
+            Some(date("2026-05-05"))
+        );
+        assert_eq!(read("2026-02-10", DateFormat::Uk), None);
+
+        let ambiguous = parse_numeric("02/10/2026", DateFormat::Iso).unwrap_err();
+        assert!(
+            ambiguous
+                .to_string()
+                .contains("2 October 2026 or 10 February 2026")
+        );
+        assert!(parse_numeric("23/01/2026", DateFormat::Us).is_err());
     }
-    if let Some(num_str) = cleaned
-        .strip_suffix('m')
-        .or_else(|| cleaned.strip_suffix("months"))
-    {
-        return num_str.trim().parse::<i64>().ok().map(|n| n * 30);
+
+    #[test]
+    fn test_time_of_day() {
+        let tomorrow = Local::now().date_naive() + Duration::days(1);
+        let deadline = Deadline::parse("tomorrow 9am").unwrap();
+        let local = deadline.time().unwrap().with_timezone(&Local);
+
+        assert_eq!(deadline.date(), tomorrow);
+        assert_eq!(local.time(), NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        assert_eq!(
+            Deadline::parse("friday at 15:00").unwrap().time(),
+            Deadline::parse("Friday 15:00").unwrap().time()
+        );
+        assert!(Deadline::parse("+5d").unwrap().time().is_none());
+        assert!(Deadline::parse("3m").unwrap().time().is_none());
+        assert!(Deadline::parse("friday 25:00").is_err());
     }
 
-    None
+    #[test]
+    fn test_timezone() {
+        let utc = Deadline::parse("2026-02-10 09:00 utc").unwrap();
+        let offset = Deadline::parse("2026-02-10 09:00 +02:00").unwrap();
+
+        assert_eq!(utc.rfc3339(), "2026-02-10T09:00:00Z");
+        assert_eq!(offset.rfc3339(), "2026-02-10T07:00:00Z");
+        assert_eq!(
+            Deadline::parse(&offset.rfc3339()).unwrap().time(),
+            offset.time()
+        );
+        assert_eq!(
+            Deadline::parse("2026-02-10").unwrap().sortable(),
+            "2026-02-10"
+        );
+    }
+
+    #[test]
+    fn test_sortable() {
+        let evening = Deadline::parse("2026-10-20 22:00").unwrap();
+        let day = Deadline::parse("2026-10-21").unwrap();
+        let morning = Deadline::parse("2026-10-21 00:00").unwrap();
+
+        assert_eq!(evening.sortable(), "2026-10-20T22:00");
+        assert!(evening.sortable() < day.sortable());
+        assert!(day.sortable() < morning.sortable());
+    }
+
+    #[test]
+    fn test_parse_time_of_day() {
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0);
+
+        assert_eq!(parse_time_of_day("12am"), time(0, 0));
+        assert_eq!(parse_time_of_day("12pm"), time(12, 0));
+        assert_eq!(parse_time_of_day("9:30pm"), time(21, 30));
+        assert_eq!(parse_time_of_day("09:00"), time(9, 0));
+        assert_eq!(parse_time_of_day("13pm"), None);
+        assert_eq!(parse_time_of_day("9"), None);
+    }
+
+    #[test]
+    fn test_hours_until() {
+        let soon = Deadline::from(Utc::now() + Duration::minutes(150));
+        let overdue = Deadline::from(Utc::now() - Duration::minutes(150));
+
+        assert!(soon.days_until().contains("in 2 hours"));
+        assert!(overdue.days_until().contains("2 hours ago"));
+        assert!(!soon.is_reached());
+        assert!(overdue.is_reached());
+    }
 }
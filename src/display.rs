@@ -1,9 +1,22 @@
-use crate::{db::Database, deadline::Deadline};
-use anyhow::Result;
+use crate::{
+    db::Database,
+    deadline::{DateFormat, Deadline},
+    task::{Status, Task},
+    tracking::format_duration,
+};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate};
 use clap::ValueEnum;
+use colored::Colorize;
+use console::{Term, measure_text_width, truncate_str};
+use std::collections::{BTreeMap, HashMap};
 use tabled::{
-    settings::{formatting::AlignmentStrategy, location::ByColumnName, Remove, Style},
     Table,
+    builder::Builder,
+    settings::{
+        Modify, Remove, Style, Width, formatting::AlignmentStrategy, location::ByColumnName,
+        object::Segment,
+    },
 };
 
 #[derive(ValueEnum, Debug, Clone)]
@@ -11,6 +24,7 @@ pub enum ViewMode {
     Minimal,
     Compact,
     Full,
+    Tree,
 }
 
 impl ViewMode {
@@ -18,48 +32,78 @@ impl ViewMode {
         match self {
             ViewMode::Minimal => {
                 table
+                    .with(Remove::column(ByColumnName::new("Status")))
                     .with(Remove::column(ByColumnName::new("Description")))
                     .with(Remove::column(ByColumnName::new("Difficulty")))
                     .with(Remove::column(ByColumnName::new("Deadline")))
+                    .with(Remove::column(ByColumnName::new("Scheduled")))
+                    .with(Remove::column(ByColumnName::new("Urgency")))
                     .with(Remove::column(ByColumnName::new("Tags")))
                     .with(Remove::column(ByColumnName::new("Parent")))
+                    .with(Remove::column(ByColumnName::new("Blocked")))
+                    .with(Remove::column(ByColumnName::new("Recurrence")))
+                    .with(Remove::column(ByColumnName::new("Tracked")))
                     .with(Remove::column(ByColumnName::new("Created")))
                     .with(Remove::column(ByColumnName::new("Complete")));
             }
             ViewMode::Compact => {
                 table
                     .with(Remove::column(ByColumnName::new("Description")))
+                    .with(Remove::column(ByColumnName::new("Scheduled")))
+                    .with(Remove::column(ByColumnName::new("Recurrence")))
+                    .with(Remove::column(ByColumnName::new("Tracked")))
                     .with(Remove::column(ByColumnName::new("Created")))
                     .with(Remove::column(ByColumnName::new("Complete")));
             }
-            ViewMode::Full => (),
+            ViewMode::Full | ViewMode::Tree => (),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum)]
+/// What the columns of `todo board` are.
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum GroupBy {
+    /// A column for each status
+    Status,
+    /// A column for each tag
+    Tags,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum Column {
     Id,
     Task,
+    Status,
     Description,
     Difficulty,
     Deadline,
+    Scheduled,
+    Urgency,
     Tags,
     Parent,
+    Blocked,
+    Recurrence,
+    Tracked,
     Created,
     Complete,
 }
 
 impl Column {
-    fn as_str(self) -> &'static str {
+    pub(crate) fn as_str(self) -> &'static str {
         match self {
             Column::Id => "ID",
             Column::Task => "Task",
+            Column::Status => "Status",
             Column::Description => "Description",
             Column::Difficulty => "Difficulty",
             Column::Deadline => "Deadline",
+            Column::Scheduled => "Scheduled",
+            Column::Urgency => "Urgency",
             Column::Tags => "Tags",
             Column::Parent => "Parent",
+            Column::Blocked => "Blocked",
+            Column::Recurrence => "Recurrence",
+            Column::Tracked => "Tracked",
             Column::Created => "Created",
             Column::Complete => "Complete",
         }
@@ -69,17 +113,24 @@ impl Column {
         vec![
             "ID".to_string(),
             "Task".to_string(),
+            "Status".to_string(),
             "Description".to_string(),
             "Difficulty".to_string(),
             "Deadline".to_string(),
+            "Scheduled".to_string(),
+            "Urgency".to_string(),
             "Tags".to_string(),
             "Parent".to_string(),
+            "Blocked".to_string(),
+            "Recurrence".to_string(),
+            "Tracked".to_string(),
             "Created".to_string(),
             "Complete".to_string(),
         ]
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn list_tasks(
     db: &Database,
     view: &ViewMode,
@@ -90,11 +141,39 @@ pub fn list_tasks(
     after: Option<String>,
     include_completed: bool,
     completed: bool,
+    series: Option<String>,
+    statuses: Option<Vec<Status>>,
+    include_deferred: bool,
 ) -> Result<()> {
     let before_date = before.map(|s| Deadline::parse(&s)).transpose()?;
 
     let after_date = after.map(|s| Deadline::parse(&s)).transpose()?;
 
+    // The tree needs completed subtasks to show progress, and starts from the parent rather
+    // than listing its subtasks.
+    if let ViewMode::Tree = view {
+        let root = pid.map(|pid| db.resolve_id(&pid)).transpose()?;
+        let tasks = db.get_tasks(
+            tags,
+            None,
+            before_date,
+            after_date,
+            true,
+            completed,
+            series,
+            statuses,
+            include_deferred,
+        )?;
+        let lines = tree(&tasks, root.as_deref(), include_completed || completed);
+
+        if lines.is_empty() {
+            println!("No tasks found");
+        } else {
+            println!("{}", lines.join("\n"));
+        }
+        return Ok(());
+    }
+
     let tasks = db.get_tasks(
         tags,
         pid,
@@ -102,6 +181,9 @@ pub fn list_tasks(
         after_date,
         include_completed,
         completed,
+        series,
+        statuses,
+        include_deferred,
     )?;
 
     if tasks.is_empty() {
@@ -109,6 +191,18 @@ pub fn list_tasks(
         return Ok(());
     }
 
+    print_tasks(tasks, view, columns);
+
+    Ok(())
+}
+
+/// Print tasks as a table, with either the columns of a view or the given columns.
+pub fn print_tasks(tasks: Vec<Task>, view: &ViewMode, columns: Option<Vec<Column>>) {
+    if let (ViewMode::Tree, None) = (view, &columns) {
+        println!("{}", tree(&tasks, None, true).join("\n"));
+        return;
+    }
+
     let mut table = Table::new(tasks);
     table.with(Style::modern()).with(AlignmentStrategy::PerLine);
 
@@ -128,6 +222,522 @@ pub fn list_tasks(
     }
 
     println!("{}", table);
+}
+
+/// Draw tasks as a tree of parents and subtasks, one line per task. Tasks whose parent is not
+/// among them are at the top level, or only the subtasks of `root` are drawn if it is given.
+///
+/// Parents show how many of their subtasks (at any depth) are complete. Unless `expand` is set,
+/// the subtasks of completed tasks are collapsed into their parent's line and completed tasks
+/// at the top level are left out.
+fn tree(tasks: &[Task], root: Option<&str>, expand: bool) -> Vec<String> {
+    let ids: Vec<String> = tasks.iter().map(|t| t.id.to_string()).collect();
+    let mut children: HashMap<Option<String>, Vec<&Task>> = HashMap::new();
+    for task in tasks {
+        let parent = task
+            .pid
+            .as_ref()
+            .map(ToString::to_string)
+            .filter(|pid| ids.contains(pid));
+        children.entry(parent).or_default().push(task);
+    }
+
+    let top = children
+        .get(&root.map(str::to_string))
+        .cloned()
+        .unwrap_or_default();
+    let top: Vec<&Task> = top
+        .into_iter()
+        .filter(|task| expand || root.is_some() || task.completed.is_none())
+        .collect();
+
+    let mut lines = Vec::new();
+    for task in top {
+        branch(task, &children, "", None, expand, &mut lines);
+    }
+    lines
+}
+
+/// Draw a task and its subtasks. `last` is whether the task is the last of its siblings, and
+/// is `None` at the top level.
+fn branch(
+    task: &Task,
+    children: &HashMap<Option<String>, Vec<&Task>>,
+    prefix: &str,
+    last: Option<bool>,
+    expand: bool,
+    lines: &mut Vec<String>,
+) {
+    let (connector, indent) = match last {
+        None => ("", ""),
+        Some(false) => ("├── ", "│   "),
+        Some(true) => ("└── ", "    "),
+    };
+    let mark = if task.completed.is_some() {
+        "✓".green().to_string()
+    } else {
+        "○".to_string()
+    };
+    let subtasks = children
+        .get(&Some(task.id.to_string()))
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let mut line = format!("{prefix}{connector}{mark} {}", task.title);
+    if !subtasks.is_empty() {
+        let (done, total) = progress(task, children);
+        let progress = format!("{done}/{total}");
+        line.push_str(&format!(
+            " [{}]",
+            if done == total {
+                progress.green().to_string()
+            } else {
+                progress
+            }
+        ));
+    }
+    line.push_str(&format!(" {}", task.id.short().dimmed()));
+    lines.push(line);
+
+    if task.completed.is_some() && !expand {
+        return;
+    }
+
+    let prefix = format!("{prefix}{indent}");
+    for (i, subtask) in subtasks.iter().enumerate() {
+        let last = i + 1 == subtasks.len();
+        branch(subtask, children, &prefix, Some(last), expand, lines);
+    }
+}
+
+/// The number of completed subtasks of a task, at any depth, and the number of subtasks.
+fn progress(task: &Task, children: &HashMap<Option<String>, Vec<&Task>>) -> (usize, usize) {
+    let Some(subtasks) = children.get(&Some(task.id.to_string())) else {
+        return (0, 0);
+    };
+
+    subtasks.iter().fold((0, 0), |(done, total), subtask| {
+        let (sub_done, sub_total) = progress(subtask, children);
+        (
+            done + sub_done + usize::from(subtask.completed.is_some()),
+            total + sub_total + 1,
+        )
+    })
+}
+
+/// Lay tasks out as cards in columns, by status or by tag, sized to fit the terminal.
+#[allow(clippy::too_many_arguments)]
+pub fn board(
+    db: &Database,
+    by: GroupBy,
+    tags: Option<Vec<String>>,
+    pid: Option<String>,
+    before: Option<String>,
+    after: Option<String>,
+    include_completed: bool,
+    include_deferred: bool,
+) -> Result<()> {
+    let before = before.map(|s| Deadline::parse(&s)).transpose()?;
+    let after = after.map(|s| Deadline::parse(&s)).transpose()?;
+
+    let tasks = db.get_tasks(
+        tags,
+        pid,
+        before,
+        after,
+        include_completed,
+        false,
+        None,
+        None,
+        include_deferred,
+    )?;
+
+    if tasks.is_empty() {
+        println!("No tasks found");
+        return Ok(());
+    }
+
+    let columns: Vec<(String, Vec<&Task>)> = match by {
+        GroupBy::Status => {
+            let mut statuses = vec![Status::Todo, Status::InProgress, Status::Waiting];
+            if include_completed {
+                statuses.push(Status::Done);
+            }
+            statuses
+                .into_iter()
+                .map(|status| {
+                    let cards = tasks
+                        .iter()
+                        .filter(|t| t.current_status() == status)
+                        .collect();
+                    (status.colour(), cards)
+                })
+                .collect()
+        }
+        GroupBy::Tags => {
+            let mut by_tag: BTreeMap<&str, Vec<&Task>> = BTreeMap::new();
+            let mut untagged = Vec::new();
+            for task in &tasks {
+                match task.tags.as_deref() {
+                    Some(tags) if !tags.is_empty() => {
+                        for tag in tags {
+                            by_tag.entry(tag).or_default().push(task);
+                        }
+                    }
+                    _ => untagged.push(task),
+                }
+            }
+
+            let mut columns: Vec<(String, Vec<&Task>)> = by_tag
+                .into_iter()
+                .map(|(tag, cards)| (tag.to_string(), cards))
+                .collect();
+            if !untagged.is_empty() {
+                columns.push(("untagged".to_string(), untagged));
+            }
+            columns
+        }
+    };
+
+    let column_width = column_width(columns.len(), 12);
+
+    let mut builder = Builder::default();
+    builder.push_record(columns.iter().map(|(name, cards)| {
+        wrap(&format!("{name} ({})", cards.len()), column_width, " ").join("\n")
+    }));
+    builder.push_record(columns.iter().map(|(_, cards)| {
+        cards
+            .iter()
+            .map(|task| card(task, column_width))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }));
+
+    let mut table = builder.build();
+    table
+        .with(Style::modern())
+        .with(AlignmentStrategy::PerLine)
+        .with(Modify::new(Segment::all()).with(Width::increase(column_width)));
+    println!("{}", table);
+
+    Ok(())
+}
+
+/// Draw a month as a grid of weeks, with the number of tasks due on each day and as many of
+/// their titles as fit. The month is the current one unless given.
+pub fn calendar_month(
+    db: &Database,
+    month: Option<String>,
+    tags: Option<Vec<String>>,
+    include_completed: bool,
+    include_deferred: bool,
+) -> Result<()> {
+    const TITLES: usize = 3;
+
+    let today = Local::now().date_naive();
+    let first = match month {
+        Some(month) => parse_month(&month)?,
+        None => today.with_day(1).unwrap(),
+    };
+    let last = first + Months::new(1) - Duration::days(1);
+
+    let tasks = db.get_tasks(
+        tags,
+        None,
+        Some(last.into()),
+        Some(first.into()),
+        include_completed,
+        false,
+        None,
+        None,
+        include_deferred,
+    )?;
+    let mut by_day: BTreeMap<NaiveDate, Vec<&Task>> = BTreeMap::new();
+    for task in &tasks {
+        if let Some(deadline) = &task.deadline {
+            by_day.entry(deadline.date()).or_default().push(task);
+        }
+    }
+
+    let width = column_width(7, 8);
+    let day = |date: NaiveDate| {
+        let due = by_day.get(&date).map(Vec::as_slice).unwrap_or_default();
+        let number = match due.len() {
+            0 => date.day().to_string(),
+            n => format!("{} ({n})", date.day()),
+        };
+
+        let mut lines = vec![if date == today {
+            number.reversed().to_string()
+        } else {
+            number.bold().to_string()
+        }];
+        for task in due.iter().take(TITLES) {
+            let title = match task.completed {
+                Some(_) => format!("✓ {}", task.title),
+                None => task.title.clone(),
+            };
+            lines.push(truncate_str(&title, width, "…").into_owned());
+        }
+        if due.len() > TITLES {
+            lines.push(format!("+{} more", due.len() - TITLES).dimmed().to_string());
+        }
+        lines.join("\n")
+    };
+
+    let mut builder = Builder::default();
+    builder.push_record(["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]);
+    let mut monday = first - Duration::days(i64::from(first.weekday().num_days_from_monday()));
+    while monday <= last {
+        builder.push_record(monday.iter_days().take(7).map(|date| {
+            if date.month() == first.month() {
+                day(date)
+            } else {
+                String::new()
+            }
+        }));
+        monday += Duration::weeks(1);
+    }
+
+    let mut table = builder.build();
+    table
+        .with(Style::modern())
+        .with(AlignmentStrategy::PerLine)
+        .with(Modify::new(Segment::all()).with(Width::increase(width)));
+    println!("{}", first.format("%B %Y").to_string().bold());
+    println!("{}", table);
+
+    Ok(())
+}
+
+/// Parse a month written as `2026-11`, or any date expression such as `next month`.
+fn parse_month(input: &str) -> Result<NaiveDate> {
+    let date = NaiveDate::parse_from_str(&format!("{}-01", input.trim()), "%Y-%m-%d")
+        .or_else(|_| Deadline::parse(input).map(|d| d.date()))
+        .map_err(|_| {
+            anyhow!("Invalid month '{input}'. Use: 2026-11, next month, last month, etc.")
+        })?;
+    Ok(date.with_day(1).unwrap())
+}
+
+/// List the incomplete tasks due over a number of days from today, grouped by date, after the
+/// overdue tasks.
+pub fn agenda(
+    db: &Database,
+    days: u32,
+    tags: Option<Vec<String>>,
+    include_deferred: bool,
+) -> Result<()> {
+    let today = Local::now().date_naive();
+    let until = today + Duration::days(i64::from(days) - 1);
+
+    let tasks = db.get_tasks(
+        tags,
+        None,
+        Some(until.into()),
+        None,
+        false,
+        false,
+        None,
+        None,
+        include_deferred,
+    )?;
+    if tasks.is_empty() {
+        println!("Nothing due in the next {days} days");
+        return Ok(());
+    }
+
+    // Overdue tasks share one group, which is keyed on the day before today.
+    let mut days: BTreeMap<NaiveDate, Vec<(&Task, &Deadline)>> = BTreeMap::new();
+    for task in &tasks {
+        if let Some(deadline) = &task.deadline {
+            let day = deadline.date().max(today - Duration::days(1));
+            days.entry(day).or_default().push((task, deadline));
+        }
+    }
+
+    let date_format = format!("%A {}", DateFormat::current().pattern());
+    let mut groups = Vec::new();
+    for (date, mut entries) in days {
+        // Tasks are ranked by urgency, which is kept for tasks due at the same time.
+        entries.sort_by_key(|(_, deadline)| {
+            let time = deadline.time().map(|t| t.with_timezone(&Local).time());
+            (deadline.date(), time)
+        });
+
+        let heading = match (date - today).num_days() {
+            ..0 => "Overdue".red().bold().to_string(),
+            0 => format!("{} (today)", date.format(&date_format))
+                .bold()
+                .to_string(),
+            1 => format!("{} (tomorrow)", date.format(&date_format))
+                .bold()
+                .to_string(),
+            _ => date.format(&date_format).to_string().bold().to_string(),
+        };
+
+        let lines: Vec<String> = entries
+            .into_iter()
+            .map(|(task, deadline)| {
+                let mut line = format!("  {}  ", task.id.short().dimmed());
+                if let Some(time) = deadline.time() {
+                    line.push_str(&format!("{} ", time.with_timezone(&Local).format("%H:%M")));
+                }
+                line.push_str(&task.title);
+                if deadline.date() < today {
+                    line.push_str(&format!(" ({})", deadline.days_until()));
+                }
+                line
+            })
+            .collect();
+        groups.push((heading, lines));
+    }
+
+    let groups: Vec<String> = groups
+        .into_iter()
+        .map(|(heading, lines)| format!("{heading}\n{}", lines.join("\n")))
+        .collect();
+    println!("{}", groups.join("\n\n"));
+
+    Ok(())
+}
+
+/// The width of each of a number of columns sharing the width of the terminal, less their
+/// borders and padding, but no narrower than `min`.
+fn column_width(columns: usize, min: usize) -> usize {
+    let width = Term::stdout()
+        .size_checked()
+        .map(|(_, w)| usize::from(w))
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(80);
+
+    (width.saturating_sub(columns + 1) / columns)
+        .saturating_sub(2)
+        .max(min)
+}
+
+/// A task as a card on the board: its title, followed by its short ID, difficulty and how long
+/// until its deadline, wrapped to fit the width of a column.
+fn card(task: &Task, width: usize) -> String {
+    let mut details = vec![task.id.short().dimmed().to_string()];
+    if let Some(difficulty) = task.difficulty {
+        details.push(difficulty.colour());
+    }
+    if let Some(deadline) = &task.deadline {
+        details.push(deadline.days_until());
+    }
+
+    let mut lines: Vec<String> = wrap(&task.title, width, " ")
+        .iter()
+        .map(|line| line.bold().to_string())
+        .collect();
+    lines.extend(wrap(&details.join(" · "), width, " · "));
+    lines.join("\n")
+}
+
+/// Wrap text at a separator so that no line is wider than `width`, ignoring colours. Parts
+/// that are too wide on their own are cut.
+fn wrap(text: &str, width: usize, separator: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for part in text.split(separator) {
+        let joined = format!("{line}{separator}{part}");
+        if line.is_empty() {
+            line = part.to_string();
+        } else if measure_text_width(&joined) <= width {
+            line = joined;
+        } else {
+            lines.push(std::mem::replace(&mut line, part.to_string()));
+        }
+        if measure_text_width(&line) > width {
+            lines.push(truncate_str(&line, width, "").into_owned());
+            line.clear();
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+pub fn list_trash(db: &Database) -> Result<()> {
+    let tasks = db.trashed()?;
+
+    if tasks.is_empty() {
+        println!("Trash is empty");
+        return Ok(());
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["ID", "Task", "Tags", "Parent", "Removed"]);
+
+    for task in tasks {
+        let removed = task
+            .deleted
+            .map(|t| {
+                let t: DateTime<Local> = t.into();
+                t.format(DateFormat::current().pattern()).to_string()
+            })
+            .unwrap_or_default();
+
+        builder.push_record([
+            task.id.short(),
+            task.title,
+            task.tags.map(|t| t.join(", ")).unwrap_or_default(),
+            task.pid.map(|p| p.short()).unwrap_or_default(),
+            removed,
+        ]);
+    }
+
+    let mut table = builder.build();
+    table.with(Style::modern()).with(AlignmentStrategy::PerLine);
+    println!("{}", table);
+
+    Ok(())
+}
+
+pub fn time_report(
+    db: &Database,
+    after: Option<String>,
+    before: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<()> {
+    let after = after.map(|s| Deadline::parse_since(&s)).transpose()?;
+    let before = before.map(|s| Deadline::parse(&s)).transpose()?;
+
+    let report = db.time_report(after, before, tags)?;
+
+    if report.tasks.is_empty() {
+        println!("No time tracked");
+        return Ok(());
+    }
+
+    let mut builder = Builder::default();
+    builder.push_record(["ID", "Task", "Time"]);
+    for (id, title, duration) in &report.tasks {
+        builder.push_record([id.short(), title.clone(), format_duration(*duration)]);
+    }
+    builder.push_record([
+        "".to_string(),
+        "Total".to_string(),
+        format_duration(report.total),
+    ]);
+
+    let mut table = builder.build();
+    table.with(Style::modern()).with(AlignmentStrategy::PerLine);
+    println!("{}", table);
+
+    if !report.tags.is_empty() {
+        let mut builder = Builder::default();
+        builder.push_record(["Tag", "Time"]);
+        for (tag, duration) in &report.tags {
+            builder.push_record([tag.clone(), format_duration(*duration)]);
+        }
+
+        let mut table = builder.build();
+        table.with(Style::modern()).with(AlignmentStrategy::PerLine);
+        println!("{}", table);
+    }
 
     Ok(())
 }
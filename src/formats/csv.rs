@@ -0,0 +1,452 @@
+//! The CSV interchange format, for moving tasks to and from spreadsheets.
+//!
+//! Columns are named after the [`Column`]s of the task table. When importing, spreadsheet
+//! headers are matched onto task fields by name, or by an explicit mapping given with
+//! `todo import --map`.
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+use std::{fmt::Display, time::SystemTime};
+
+use crate::{
+    deadline::Deadline,
+    display::Column,
+    formats::{is_full_id, resolve_id},
+    recurrence::Recurrence,
+    task::{Status, Task},
+};
+
+/// The columns exported when none are requested.
+pub const DEFAULT_COLUMNS: &[Column] = &[
+    Column::Id,
+    Column::Task,
+    Column::Status,
+    Column::Description,
+    Column::Difficulty,
+    Column::Deadline,
+    Column::Scheduled,
+    Column::Tags,
+    Column::Parent,
+    Column::Recurrence,
+    Column::Created,
+    Column::Complete,
+];
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A row of an imported CSV file that could not be turned into a task.
+#[derive(Debug)]
+pub struct RowError {
+    /// The line number of the row in the file, counting the header as line 1.
+    pub row: usize,
+    pub message: String,
+}
+
+impl Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Row {}: {}", self.row, self.message)
+    }
+}
+
+/// The outcome of reading a CSV file of tasks.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub tasks: Vec<Task>,
+    /// The rows that were skipped because they failed validation.
+    pub errors: Vec<RowError>,
+    /// The headers that did not map onto a task field, or that map onto one that is worked
+    /// out rather than stored, such as the urgency.
+    pub ignored: Vec<String>,
+}
+
+impl Column {
+    /// The task field a spreadsheet header refers to, matching column names and a few common
+    /// alternatives regardless of case.
+    pub fn from_header(header: &str) -> Option<Self> {
+        let header = header.trim().to_lowercase();
+        match header.as_str() {
+            "title" | "name" | "summary" => Some(Column::Task),
+            "desc" | "notes" => Some(Column::Description),
+            "due" | "due date" => Some(Column::Deadline),
+            "tag" => Some(Column::Tags),
+            "pid" | "parent id" => Some(Column::Parent),
+            "completed" | "done" => Some(Column::Complete),
+            _ => Column::from_str(&header, true).ok(),
+        }
+    }
+}
+
+/// Parse a `HEADER=FIELD` mapping of a spreadsheet header onto a task field.
+pub fn parse_mapping(mapping: &str) -> Result<(String, Column)> {
+    let (header, field) = mapping.split_once('=').ok_or_else(|| {
+        anyhow!("Invalid column mapping '{mapping}'. Use HEADER=FIELD, e.g. 'Due Date=deadline'")
+    })?;
+
+    let column = Column::from_str(field.trim(), true).map_err(|_| {
+        anyhow!(
+            "Unknown task field '{}' in column mapping '{mapping}'",
+            field.trim()
+        )
+    })?;
+
+    Ok((header.trim().to_string(), column))
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format(TIME_FORMAT)
+        .to_string()
+}
+
+fn parse_time(time: &str) -> Result<SystemTime> {
+    let time = time.trim();
+    let naive = NaiveDateTime::parse_from_str(time, TIME_FORMAT)
+        .or_else(|_| {
+            NaiveDate::parse_from_str(time, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default())
+        })
+        .map_err(|_| {
+            anyhow!("Invalid timestamp '{time}'. Use YYYY-MM-DD or YYYY-MM-DD HH:MM:SS")
+        })?;
+
+    naive
+        .and_local_timezone(Local)
+        .earliest()
+        .map(SystemTime::from)
+        .ok_or_else(|| anyhow!("Invalid local time '{time}'"))
+}
+
+fn field(task: &Task, column: Column) -> String {
+    match column {
+        Column::Id => task.id.to_string(),
+        Column::Task => task.title.clone(),
+        Column::Status => task.current_status().to_string(),
+        Column::Description => task.desc.clone().unwrap_or_default(),
+        Column::Difficulty => task
+            .difficulty
+            .map(|d| u8::from(d).to_string())
+            .unwrap_or_default(),
+        Column::Deadline => task
+            .deadline
+            .as_ref()
+            .map(Deadline::iso)
+            .unwrap_or_default(),
+        Column::Scheduled => task
+            .scheduled
+            .as_ref()
+            .map(Deadline::iso)
+            .unwrap_or_default(),
+        Column::Urgency => format!("{:.2}", task.urgency),
+        Column::Tags => task.tags.as_deref().unwrap_or_default().join(","),
+        Column::Parent => task.pid.as_ref().map(|p| p.to_string()).unwrap_or_default(),
+        Column::Blocked => task
+            .blocked_by
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        Column::Recurrence => task
+            .recurrence
+            .as_ref()
+            .map(|r| r.to_string())
+            .unwrap_or_default(),
+        Column::Tracked => task.tracked.as_secs().to_string(),
+        Column::Created => format_time(task.created),
+        Column::Complete => task.completed.map(format_time).unwrap_or_default(),
+    }
+}
+
+/// Write the given columns of the tasks as CSV, with a header row of column names.
+pub fn export(tasks: &[Task], columns: &[Column]) -> Result<String> {
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(columns.iter().map(|c| c.as_str()))?;
+    for task in tasks {
+        writer.write_record(columns.iter().map(|c| field(task, *c)))?;
+    }
+
+    let output = String::from_utf8(writer.into_inner()?)?;
+    Ok(output.trim_end().to_string())
+}
+
+struct Row {
+    line: usize,
+    task: Task,
+    parent: Option<String>,
+    blockers: Vec<String>,
+}
+
+/// Build a row from a CSV record, using `columns` to find each field. Its parent and blockers
+/// are resolved once every row has been read.
+fn parse_row(
+    line: usize,
+    record: &::csv::StringRecord,
+    columns: &[Option<Column>],
+    known_ids: &[String],
+) -> Result<Row> {
+    let value = |column: Column| {
+        columns
+            .iter()
+            .position(|c| *c == Some(column))
+            .and_then(|i| record.get(i))
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+
+    let title = value(Column::Task).context("Missing task title")?;
+
+    let difficulty = value(Column::Difficulty)
+        .map(|d| {
+            d.parse::<u8>().map_err(|_| {
+                anyhow!("Invalid difficulty '{d}'. The value should be between 0 and 10.")
+            })
+        })
+        .transpose()?;
+
+    let tags = value(Column::Tags).map(|tags| {
+        tags.split([',', ';'])
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect()
+    });
+
+    let mut task = Task::new(
+        title.to_string(),
+        value(Column::Description).map(String::from),
+        difficulty,
+        value(Column::Deadline).map(String::from),
+        tags,
+        None,
+    )?;
+
+    if let Some(id) = value(Column::Id) {
+        let id = id.to_lowercase();
+        if is_full_id(&id) {
+            task.id = id.into();
+        } else {
+            task.id = resolve_id(&id, known_ids)?.into();
+        }
+    }
+
+    task.recurrence = value(Column::Recurrence)
+        .map(Recurrence::parse)
+        .transpose()?;
+    task.scheduled = value(Column::Scheduled).map(Deadline::parse).transpose()?;
+
+    if let Some(created) = value(Column::Created) {
+        task.created = parse_time(created)?;
+    }
+
+    if let Some(completed) = value(Column::Complete) {
+        task.completed = Some(parse_time(completed)?);
+    }
+
+    if let Some(status) = value(Column::Status) {
+        match Status::from_str(status, true).map_err(|_| {
+            anyhow!("Invalid status '{status}'. Use todo, in-progress, waiting or done")
+        })? {
+            Status::Done => {
+                task.completed.get_or_insert_with(SystemTime::now);
+            }
+            status => task.status = status,
+        }
+    }
+
+    let blockers = value(Column::Blocked)
+        .map(|ids| {
+            ids.split([',', ';'])
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_lowercase)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(Row {
+        line,
+        task,
+        parent: value(Column::Parent).map(str::to_lowercase),
+        blockers,
+    })
+}
+
+/// Read tasks from CSV with a header row. Headers are mapped onto task fields by `mapping`
+/// first and then by name. Rows that fail validation are reported rather than aborting the
+/// import.
+///
+/// IDs, parent IDs and the IDs of blocking tasks may be abbreviated, and are resolved against
+/// the IDs of the other imported tasks and `known_ids`.
+pub fn import(input: &str, mapping: &[(String, Column)], known_ids: &[String]) -> Result<Imported> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(input.as_bytes());
+
+    let mut imported = Imported::default();
+
+    let columns: Vec<Option<Column>> = reader
+        .headers()
+        .context("Invalid CSV task export")?
+        .iter()
+        .map(|header| {
+            let column = mapping
+                .iter()
+                .find(|(h, _)| h.eq_ignore_ascii_case(header.trim()))
+                .map(|(_, c)| *c)
+                .or_else(|| Column::from_header(header))
+                .filter(|c| !matches!(c, Column::Urgency | Column::Tracked));
+            if column.is_none() {
+                imported.ignored.push(header.to_string());
+            }
+            column
+        })
+        .collect();
+
+    if !columns.contains(&Some(Column::Task)) {
+        bail!("The CSV file has no task title column. Use --map to say which column holds it.");
+    }
+
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let line = i + 2;
+        let parsed = record
+            .map_err(anyhow::Error::from)
+            .and_then(|record| parse_row(line, &record, &columns, known_ids));
+
+        match parsed {
+            Ok(row) => rows.push(row),
+            Err(e) => imported.errors.push(RowError {
+                row: line,
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    // Rejecting a row can leave the subtasks and blocked tasks that refer to it dangling, so
+    // keep resolving parents and blockers until every remaining row is valid.
+    loop {
+        let mut ids: Vec<String> = rows.iter().map(|r| r.task.id.to_string()).collect();
+        ids.extend(known_ids.iter().cloned());
+
+        let before = rows.len();
+        let mut valid = Vec::new();
+        for mut row in rows {
+            let pid = row
+                .parent
+                .as_deref()
+                .map(|p| resolve_id(p, &ids))
+                .transpose()
+                .map_err(|e| format!("Invalid parent: {e}"));
+            let blockers = row
+                .blockers
+                .iter()
+                .map(|b| resolve_id(b, &ids))
+                .collect::<Result<Vec<_>>>()
+                .map_err(|e| format!("Invalid blocker: {e}"));
+
+            match (pid, blockers) {
+                (Ok(pid), Ok(blockers)) => {
+                    row.task.pid = pid.map(Into::into);
+                    if !blockers.is_empty() {
+                        row.task.blocked_by = Some(blockers.into_iter().map(Into::into).collect());
+                    }
+                    valid.push(row);
+                }
+                (Err(message), _) | (_, Err(message)) => imported.errors.push(RowError {
+                    row: row.line,
+                    message,
+                }),
+            }
+        }
+        rows = valid;
+
+        if rows.len() == before {
+            break;
+        }
+    }
+
+    imported.errors.sort_by_key(|e| e.row);
+    imported.tasks = rows.into_iter().map(|r| r.task).collect();
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let parent = Task::new(
+            "parent, with comma".to_string(),
+            Some("desc".to_string()),
+            Some(4),
+            Some("2026-01-23".to_string()),
+            Some(vec!["work".to_string(), "urgent".to_string()]),
+            None,
+        )
+        .unwrap();
+        let mut child = Task::new("child".to_string(), None, None, None, None, None).unwrap();
+        child.pid = Some(parent.id.clone());
+        child.blocked_by = Some(vec![parent.id.clone()]);
+
+        let columns = [DEFAULT_COLUMNS, &[Column::Blocked, Column::Urgency]].concat();
+        let output = export(&[parent, child], &columns).unwrap();
+        let imported = import(&output, &[], &[]).unwrap();
+
+        assert!(imported.errors.is_empty());
+        assert_eq!(imported.tasks.len(), 2);
+        assert_eq!(imported.tasks[0].title, "parent, with comma");
+        assert_eq!(
+            imported.tasks[0].tags,
+            Some(vec!["work".to_string(), "urgent".to_string()])
+        );
+        assert_eq!(
+            imported.tasks[1].pid.as_ref().map(ToString::to_string),
+            Some(imported.tasks[0].id.to_string())
+        );
+        assert_eq!(
+            imported.tasks[1]
+                .blocked_by
+                .as_deref()
+                .map(|ids| ids.iter().map(ToString::to_string).collect::<Vec<_>>()),
+            Some(vec![imported.tasks[0].id.to_string()])
+        );
+        assert_eq!(imported.ignored, vec!["Urgency".to_string()]);
+    }
+
+    #[test]
+    fn test_invalid_rows_are_reported() {
+        let input = "Title,Difficulty,Due Date,Owner\n\
+                     good,3,2026-01-23,me\n\
+                     too hard,11,,me\n\
+                     bad date,,someday,me\n\
+                     ,2,,me\n";
+
+        let imported = import(input, &[], &[]).unwrap();
+
+        assert_eq!(imported.tasks.len(), 1);
+        assert_eq!(imported.ignored, vec!["Owner".to_string()]);
+        let rows: Vec<usize> = imported.errors.iter().map(|e| e.row).collect();
+        assert_eq!(rows, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn test_mapping() {
+        let input = "Thing,When\nreport,2026-01-23\n";
+        let mapping = vec![
+            parse_mapping("Thing=task").unwrap(),
+            parse_mapping("when = deadline").unwrap(),
+        ];
+
+        let imported = import(input, &mapping, &[]).unwrap();
+
+        assert_eq!(imported.tasks[0].title, "report");
+        assert!(imported.tasks[0].deadline.is_some());
+        assert!(parse_mapping("Thing").is_err());
+        assert!(parse_mapping("Thing=colour").is_err());
+    }
+}
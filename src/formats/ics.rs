@@ -0,0 +1,383 @@
+//! The iCalendar format ([RFC 5545](https://datatracker.ietf.org/doc/html/rfc5545)), with
+//! each task as a `VTODO` component.
+//!
+//! Tasks are exported with their ID as the `UID`, so that calendar apps update rather than
+//! duplicate them when a calendar is imported again. Tasks imported from calendar apps are
+//! given an ID derived from their `UID` for the same reason.
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, Utc};
+use ical::{IcalParser, parser::ical::component::IcalTodo};
+use std::time::SystemTime;
+
+use crate::{
+    deadline::Deadline,
+    formats::is_full_id,
+    task::{ID, Status, Task},
+};
+
+const UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// The longest a content line may be before it is folded, in octets.
+const LINE_LENGTH: usize = 75;
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).format(UTC_FORMAT).to_string()
+}
+
+/// Parse a `DATE` or `DATE-TIME` value. Times without a `Z` suffix are taken to be local.
+fn parse_time(value: &str) -> Result<SystemTime> {
+    let invalid = || anyhow!("Invalid date '{value}'");
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        return Ok(time.and_utc().into());
+    }
+
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y%m%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default())
+        })
+        .map_err(|_| invalid())?;
+
+    time.and_local_timezone(Local)
+        .earliest()
+        .map(SystemTime::from)
+        .ok_or_else(invalid)
+}
+
+/// Parse a `DUE` or `DTSTART` value, keeping the time of day of a `DATE-TIME`.
+fn parse_deadline(value: &str) -> Result<Deadline> {
+    let time = DateTime::<Utc>::from(parse_time(value)?);
+    if value.contains('T') {
+        Ok(time.into())
+    } else {
+        Ok(DateTime::<Local>::from(time).date_naive().into())
+    }
+}
+
+/// Format a deadline as a `DATE`, or as a UTC `DATE-TIME` if it has a time of day, with the
+/// name of the property it is for.
+fn format_deadline(name: &str, deadline: &Deadline) -> String {
+    match deadline.time() {
+        Some(time) => format!("{name}:{}", time.format(UTC_FORMAT)),
+        None => format!("{name};VALUE=DATE:{}", deadline.date().format("%Y%m%d")),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Split a list value on the commas that are not escaped.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            ',' if !escaped => items.push(String::new()),
+            _ => {
+                escaped = c == '\\' && !escaped;
+                if let Some(item) = items.last_mut() {
+                    item.push(c);
+                }
+            }
+        }
+    }
+    items
+        .iter()
+        .map(|item| unescape(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Fold a content line so that no line is longer than [`LINE_LENGTH`] octets.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+fn export_task(task: &Task, stamp: &str) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}", task.id),
+        format!("DTSTAMP:{stamp}"),
+        format!("CREATED:{}", format_time(task.created)),
+        format!("SUMMARY:{}", escape(&task.title)),
+    ];
+
+    if let Some(desc) = &task.desc {
+        lines.push(format!("DESCRIPTION:{}", escape(desc)));
+    }
+
+    if let Some(scheduled) = &task.scheduled {
+        lines.push(format_deadline("DTSTART", scheduled));
+    }
+
+    if let Some(deadline) = &task.deadline {
+        lines.push(format_deadline("DUE", deadline));
+    }
+
+    if let Some(tags) = task.tags.as_ref().filter(|t| !t.is_empty()) {
+        let tags: Vec<String> = tags.iter().map(|t| escape(t)).collect();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+
+    if let Some(pid) = &task.pid {
+        lines.push(format!("RELATED-TO;RELTYPE=PARENT:{pid}"));
+    }
+
+    if let Some(d) = task.difficulty {
+        lines.push(format!("X-TODO-DIFFICULTY:{}", u8::from(d)));
+    }
+
+    match task.completed {
+        Some(completed) => {
+            lines.push("STATUS:COMPLETED".to_string());
+            lines.push(format!("COMPLETED:{}", format_time(completed)));
+        }
+        None if task.status == Status::InProgress => lines.push("STATUS:IN-PROCESS".to_string()),
+        None => lines.push("STATUS:NEEDS-ACTION".to_string()),
+    }
+
+    lines.push("END:VTODO".to_string());
+    lines
+}
+
+/// Write the tasks as a calendar of `VTODO` components.
+pub fn export(tasks: &[Task]) -> String {
+    let stamp = format_time(SystemTime::now());
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//todo-cli//todo {}//EN", env!("CARGO_PKG_VERSION")),
+    ];
+    for task in tasks {
+        lines.extend(export_task(task, &stamp));
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+/// The ID of a task from a calendar `UID`, which is kept as it is if it is already a task ID.
+fn task_id(uid: &str) -> ID {
+    let uid = uid.trim();
+    if is_full_id(uid) {
+        uid.to_lowercase().into()
+    } else {
+        ID::from_external(uid)
+    }
+}
+
+fn import_task(todo: &IcalTodo) -> Result<Task> {
+    let mut summary = None;
+    let mut desc = None;
+    let mut due = None;
+    let mut start = None;
+    let mut tags = Vec::new();
+    let mut uid = None;
+    let mut parent = None;
+    let mut difficulty = None;
+    let mut created = None;
+    let mut completed = None;
+    let mut status = String::new();
+
+    for property in &todo.properties {
+        let Some(value) = property.value.as_deref() else {
+            continue;
+        };
+        let param = |name: &str| {
+            property
+                .params
+                .as_ref()?
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .and_then(|(_, values)| values.first())
+                .map(String::as_str)
+        };
+
+        match property.name.to_uppercase().as_str() {
+            "SUMMARY" => summary = Some(unescape(value)),
+            "DESCRIPTION" => desc = Some(unescape(value)),
+            "UID" => uid = Some(value.to_string()),
+            "DUE" => due = Some(parse_deadline(value)?),
+            "DTSTART" => start = Some(parse_deadline(value)?),
+            "CATEGORIES" => tags.extend(split_list(value)),
+            "RELATED-TO" if param("RELTYPE").is_none_or(|r| r.eq_ignore_ascii_case("PARENT")) => {
+                parent = Some(task_id(value));
+            }
+            "X-TODO-DIFFICULTY" => {
+                difficulty = Some(
+                    value
+                        .trim()
+                        .parse::<u8>()
+                        .map_err(|_| anyhow!("Invalid difficulty '{value}'"))?,
+                );
+            }
+            "CREATED" => created = Some(parse_time(value)?),
+            "DTSTAMP" if created.is_none() => created = Some(parse_time(value)?),
+            "COMPLETED" => completed = Some(parse_time(value)?),
+            "STATUS" => status = value.trim().to_uppercase(),
+            _ => (),
+        }
+    }
+
+    let summary = summary
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| anyhow!("Missing SUMMARY"))?;
+
+    tags.sort();
+    tags.dedup();
+
+    let mut task = Task::new(
+        summary,
+        desc,
+        difficulty,
+        None,
+        (!tags.is_empty()).then_some(tags),
+        None,
+    )?;
+
+    if let Some(uid) = uid {
+        task.id = task_id(&uid);
+    }
+    task.pid = parent;
+    task.deadline = due;
+    task.scheduled = start;
+    if let Some(created) = created {
+        task.created = created;
+    }
+    if completed.is_some() || status == "COMPLETED" {
+        task.completed = Some(completed.unwrap_or_else(SystemTime::now));
+    }
+    if status == "IN-PROCESS" {
+        task.status = Status::InProgress;
+    }
+
+    Ok(task)
+}
+
+/// Read the `VTODO` components of one or more calendars as tasks. Other components, such as
+/// events, are ignored.
+pub fn import(input: &str) -> Result<Vec<Task>> {
+    let mut tasks = Vec::new();
+
+    for calendar in IcalParser::new(input.as_bytes()) {
+        let calendar = calendar.map_err(|e| anyhow!("Invalid iCalendar file: {e}"))?;
+
+        for todo in &calendar.todos {
+            let task = import_task(todo).map_err(|e| anyhow!("VTODO {}: {e}", tasks.len() + 1))?;
+            tasks.push(task);
+        }
+    }
+
+    if tasks.is_empty() {
+        bail!("The iCalendar file contains no VTODO components");
+    }
+
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let parent = Task::new(
+            "Plan; trip, \\ soon".to_string(),
+            Some("line one\nline two".to_string()),
+            Some(6),
+            Some("2026-05-01".to_string()),
+            Some(vec!["travel".to_string(), "a,b".to_string()]),
+            None,
+        )
+        .unwrap();
+        let parent_id = parent.id.to_string();
+        let mut child = Task::new("x".repeat(200), None, None, None, None, None).unwrap();
+        child.pid = Some(parent.id.clone());
+        child.completed = Some(SystemTime::now());
+
+        let output = export(&[parent, child]);
+        assert!(output.lines().all(|line| line.len() <= LINE_LENGTH + 1));
+
+        let tasks = import(&output).unwrap();
+        assert_eq!(tasks[0].id.to_string(), parent_id);
+        assert_eq!(tasks[0].title, "Plan; trip, \\ soon");
+        assert_eq!(tasks[0].desc.as_deref(), Some("line one\nline two"));
+        assert_eq!(tasks[0].difficulty.map(u8::from), Some(6));
+        assert_eq!(
+            tasks[0].deadline.as_ref().map(ToString::to_string),
+            Some("2026-05-01".to_string())
+        );
+        assert_eq!(
+            tasks[0].tags,
+            Some(vec!["a,b".to_string(), "travel".to_string()])
+        );
+        assert_eq!(tasks[1].title, "x".repeat(200));
+        assert_eq!(
+            tasks[1].pid.as_ref().map(ToString::to_string),
+            Some(parent_id)
+        );
+        assert!(tasks[1].completed.is_some());
+    }
+
+    #[test]
+    fn test_external_uid_is_stable() {
+        let input = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+                     BEGIN:VTODO\r\nUID:abc@example.com\r\nSUMMARY:Call\r\n\
+                     DUE:20260301T170000Z\r\nEND:VTODO\r\n\
+                     END:VCALENDAR\r\n";
+
+        let first = import(input).unwrap();
+        let second = import(input).unwrap();
+
+        assert_eq!(first[0].id.to_string(), second[0].id.to_string());
+        assert_eq!(
+            first[0].deadline.as_ref().and_then(Deadline::time),
+            Some(
+                DateTime::parse_from_rfc3339("2026-03-01T17:00:00Z")
+                    .unwrap()
+                    .to_utc()
+            )
+        );
+    }
+
+    #[test]
+    fn test_missing_summary() {
+        let input = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:1\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        assert!(import(input).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! The JSON interchange format. See `docs/export.schema.json` for the schema of version
+//! [`VERSION`] of the format.
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+use crate::{
+    deadline::Deadline,
+    formats::is_full_id,
+    recurrence::Recurrence,
+    task::{Difficulty, Status, Task},
+};
+
+/// The version of the JSON format written by [`export`]. Bump this whenever a field is added,
+/// removed or changes meaning.
+pub const VERSION: u32 = 6;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Document {
+    version: u32,
+    exported: String,
+    tasks: Vec<ExportedTask>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedTask {
+    id: String,
+    title: String,
+    description: Option<String>,
+    difficulty: Option<u8>,
+    deadline: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    parent: Option<String>,
+    created: String,
+    completed: Option<String>,
+    deleted: Option<String>,
+    #[serde(default)]
+    recurrence: Option<String>,
+    #[serde(default)]
+    series: Option<String>,
+    #[serde(default)]
+    blocked_by: Vec<String>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    waiting_until: Option<String>,
+    #[serde(default)]
+    scheduled: Option<String>,
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_time(time: &str) -> Result<SystemTime> {
+    Ok(DateTime::parse_from_rfc3339(time)
+        .with_context(|| {
+            format!("Invalid timestamp '{time}'. Use RFC 3339, e.g. 2026-01-31T09:00:00Z")
+        })?
+        .with_timezone(&Utc)
+        .into())
+}
+
+impl From<&Task> for ExportedTask {
+    fn from(task: &Task) -> Self {
+        Self {
+            id: task.id.to_string(),
+            title: task.title.clone(),
+            description: task.desc.clone(),
+            difficulty: task.difficulty.map(u8::from),
+            deadline: task.deadline.as_ref().map(Deadline::rfc3339),
+            tags: task.tags.clone().unwrap_or_default(),
+            parent: task.pid.as_ref().map(|p| p.to_string()),
+            created: format_time(task.created),
+            completed: task.completed.map(format_time),
+            deleted: task.deleted.map(format_time),
+            recurrence: task.recurrence.as_ref().map(|r| r.to_string()),
+            series: task.series.as_ref().map(|s| s.to_string()),
+            blocked_by: task
+                .blocked_by
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            status: Some(task.status.to_string()),
+            waiting_until: task.waiting_until.as_ref().map(Deadline::rfc3339),
+            scheduled: task.scheduled.as_ref().map(Deadline::rfc3339),
+        }
+    }
+}
+
+impl TryFrom<ExportedTask> for Task {
+    type Error = anyhow::Error;
+
+    fn try_from(task: ExportedTask) -> Result<Self> {
+        if !is_full_id(&task.id) {
+            bail!(
+                "Invalid task ID '{}'. IDs are 40 character SHA1 hashes",
+                task.id
+            );
+        }
+
+        Ok(Task {
+            id: task.id.into(),
+            title: task.title,
+            desc: task.description,
+            difficulty: task.difficulty.map(Difficulty::new).transpose()?,
+            deadline: task.deadline.map(|d| Deadline::parse(&d)).transpose()?,
+            tags: Some(task.tags),
+            pid: task.parent.map(Into::into),
+            created: parse_time(&task.created)?,
+            completed: task.completed.as_deref().map(parse_time).transpose()?,
+            deleted: task.deleted.as_deref().map(parse_time).transpose()?,
+            recurrence: task
+                .recurrence
+                .as_deref()
+                .map(Recurrence::parse)
+                .transpose()?,
+            series: task.series.map(Into::into),
+            blocked_by: Some(task.blocked_by.into_iter().map(Into::into).collect()),
+            blocked: false,
+            blocking: false,
+            status: match task.status.as_deref() {
+                None => Status::Todo,
+                Some("done") => {
+                    bail!("Invalid status 'done'. Completed tasks have a completed time")
+                }
+                Some(status) => Status::from_str(status, true)
+                    .map_err(|_| anyhow!("Invalid status '{status}'"))?,
+            },
+            waiting_until: task
+                .waiting_until
+                .map(|d| Deadline::parse(&d))
+                .transpose()?,
+            scheduled: task.scheduled.map(|d| Deadline::parse(&d)).transpose()?,
+            tracked: Duration::ZERO,
+            urgency: 0.0,
+        })
+    }
+}
+
+pub fn export(tasks: &[Task]) -> Result<String> {
+    let document = Document {
+        version: VERSION,
+        exported: format_time(SystemTime::now()),
+        tasks: tasks.iter().map(ExportedTask::from).collect(),
+    };
+
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+pub fn import(input: &str) -> Result<Vec<Task>> {
+    let document: Document = serde_json::from_str(input).context("Invalid JSON task export")?;
+
+    if document.version > VERSION {
+        bail!(
+            "The export is version {} of the JSON format, which is newer than the latest version supported by this version of todo ({VERSION})",
+            document.version
+        );
+    }
+
+    document
+        .tasks
+        .into_iter()
+        .enumerate()
+        .map(|(i, task)| {
+            let title = task.title.clone();
+            Task::try_from(task).map_err(|e| anyhow!("Task {} ('{title}'): {e}", i + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut task = Task::new(
+            "test".to_string(),
+            Some("desc".to_string()),
+            Some(4),
+            Some("2026-01-23".to_string()),
+            Some(vec!["work".to_string()]),
+            None,
+        )
+        .unwrap();
+        task.completed = Some(SystemTime::now());
+
+        let imported = import(&export(&[task]).unwrap()).unwrap();
+
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].title, "test");
+        assert_eq!(imported[0].desc.as_deref(), Some("desc"));
+        assert_eq!(imported[0].difficulty.map(u8::from), Some(4));
+        assert_eq!(
+            imported[0].deadline.as_ref().unwrap().to_string(),
+            "2026-01-23"
+        );
+        assert_eq!(imported[0].tags, Some(vec!["work".to_string()]));
+        assert!(imported[0].completed.is_some());
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let input = r#"{"version": 99, "exported": "2026-01-01T00:00:00Z", "tasks": []}"#;
+        assert!(import(input).is_err());
+    }
+
+    #[test]
+    fn test_invalid_difficulty_is_rejected() {
+        let input = r#"{"version": 1, "exported": "2026-01-01T00:00:00Z", "tasks": [{
+            "id": "1a2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5",
+            "title": "test",
+            "difficulty": 11,
+            "created": "2026-01-01T00:00:00Z"
+        }]}"#;
+        assert!(import(input).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! Markdown checklists, for pasting tasks into documents and turning meeting notes into tasks.
+//!
+//! Subtasks are nested under their parent task, with the deadline and tags inline:
+//!
+//! ```markdown
+//! - [ ] Release (due 2026-12-31) #work
+//!   - [x] Write the changelog
+//! ```
+//!
+//! When importing, checklist items become subtasks of the item or heading they are nested
+//! under, and other lines are ignored.
+
+use anyhow::{Result, anyhow, bail};
+use std::{collections::HashSet, time::SystemTime};
+
+use crate::task::Task;
+
+/// The number of columns a tab indents a list item by.
+const TAB_WIDTH: usize = 4;
+
+fn export_task(task: &Task, tasks: &[Task], depth: usize, lines: &mut Vec<String>) {
+    let mut line = format!(
+        "{}- [{}] {}",
+        "  ".repeat(depth),
+        if task.completed.is_some() { "x" } else { " " },
+        task.title
+    );
+
+    if let Some(deadline) = &task.deadline {
+        line.push_str(&format!(" (due {})", deadline.iso()));
+    }
+
+    for tag in task.tags.as_deref().unwrap_or_default() {
+        line.push_str(&format!(" #{tag}"));
+    }
+
+    lines.push(line);
+
+    let id = task.id.to_string();
+    for child in tasks
+        .iter()
+        .filter(|t| t.pid.as_ref().is_some_and(|p| p.to_string() == id))
+    {
+        export_task(child, tasks, depth + 1, lines);
+    }
+}
+
+/// Write the tasks as a nested checklist.
+pub fn export(tasks: &[Task]) -> String {
+    let ids: HashSet<String> = tasks.iter().map(|t| t.id.to_string()).collect();
+
+    let mut lines = Vec::new();
+    for task in tasks
+        .iter()
+        .filter(|t| t.pid.as_ref().is_none_or(|p| !ids.contains(&p.to_string())))
+    {
+        export_task(task, tasks, 0, &mut lines);
+    }
+
+    lines.join("\n")
+}
+
+/// A checklist item: its indentation, whether it is checked and its text.
+fn parse_item(line: &str) -> Option<(usize, bool, &str)> {
+    let text = line.trim_start();
+    let indent = line[..line.len() - text.len()]
+        .chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum();
+
+    let text = text
+        .strip_prefix(['-', '*', '+'])
+        .or_else(|| {
+            let marker = text.trim_start_matches(|c: char| c.is_ascii_digit());
+            (marker.len() < text.len())
+                .then(|| marker.strip_prefix(['.', ')']))
+                .flatten()
+        })?
+        .strip_prefix(' ')?
+        .trim_start();
+
+    let (checked, text) = if let Some(text) = text.strip_prefix("[ ]") {
+        (false, text)
+    } else {
+        let text = text
+            .strip_prefix("[x]")
+            .or_else(|| text.strip_prefix("[X]"))?;
+        (true, text)
+    };
+
+    Some((indent, checked, text.trim()))
+}
+
+/// A heading: its level and text.
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    let text = text.strip_prefix(' ')?.trim();
+    ((1..=6).contains(&level) && !text.is_empty()).then_some((level, text))
+}
+
+/// Build a task from the text of a checklist item, taking `(due ...)` as the deadline and
+/// `#tag` words as tags.
+fn parse_task(text: &str, checked: bool) -> Result<Task> {
+    let mut text = text.to_string();
+    let mut deadline = None;
+
+    if let Some(start) = text.find("(due ")
+        && let Some(length) = text[start..].find(')')
+    {
+        deadline = Some(text[start + 5..start + length].trim().to_string());
+        text.replace_range(start..=start + length, "");
+    }
+
+    let mut title = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match word.strip_prefix('#').filter(|t| !t.is_empty()) {
+            Some(tag) if !tags.iter().any(|t| t == tag) => tags.push(tag.to_string()),
+            Some(_) => (),
+            None => title.push(word),
+        }
+    }
+
+    if title.is_empty() {
+        bail!("Missing task title");
+    }
+
+    let mut task = Task::new(
+        title.join(" "),
+        None,
+        None,
+        deadline,
+        (!tags.is_empty()).then_some(tags),
+        None,
+    )?;
+
+    if checked {
+        task.completed = Some(SystemTime::now());
+    }
+
+    Ok(task)
+}
+
+/// Read the checklist items of a Markdown document as tasks. Nested items become subtasks of
+/// the item they are nested under, and the items under a heading become subtasks of a task
+/// named after the heading.
+pub fn import(input: &str) -> Result<Vec<Task>> {
+    let mut tasks: Vec<Task> = Vec::new();
+    // The headings that enclose the current line as (level, text, index into `tasks`). A
+    // heading is only added to `tasks` once there is an item under it.
+    let mut headings: Vec<(usize, &str, Option<usize>)> = Vec::new();
+    // The items that enclose the current line as (indent, index into `tasks`).
+    let mut items: Vec<(usize, usize)> = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        if let Some((level, text)) = parse_heading(line) {
+            headings.retain(|(l, _, _)| *l < level);
+            headings.push((level, text, None));
+            items.clear();
+            continue;
+        }
+
+        let Some((indent, checked, text)) = parse_item(line) else {
+            continue;
+        };
+
+        let mut task = parse_task(text, checked).map_err(|e| anyhow!("Line {}: {e}", i + 1))?;
+
+        items.retain(|(item_indent, _)| *item_indent < indent);
+
+        let parent = match items.last() {
+            Some((_, parent)) => Some(*parent),
+            None => {
+                // Add the enclosing headings as tasks now that they have an item under them.
+                let mut parent = None;
+                for (_, text, index) in &mut headings {
+                    if index.is_none() {
+                        let mut heading =
+                            Task::new(text.to_string(), None, None, None, None, None)?;
+                        heading.pid = parent.map(|p: usize| tasks[p].id.clone());
+                        tasks.push(heading);
+                        *index = Some(tasks.len() - 1);
+                    }
+                    parent = *index;
+                }
+                parent
+            }
+        };
+
+        task.pid = parent.map(|p| tasks[p].id.clone());
+        tasks.push(task);
+        items.push((indent, tasks.len() - 1));
+    }
+
+    if tasks.is_empty() {
+        bail!("The Markdown file contains no checklist items, such as '- [ ] Task'");
+    }
+
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parent_title<'a>(tasks: &'a [Task], task: &Task) -> Option<&'a str> {
+        let pid = task.pid.as_ref()?.to_string();
+        tasks
+            .iter()
+            .find(|t| t.id.to_string() == pid)
+            .map(|t| t.title.as_str())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let parent = Task::new(
+            "Release".to_string(),
+            None,
+            None,
+            Some("2026-12-31".to_string()),
+            Some(vec!["work".to_string()]),
+            None,
+        )
+        .unwrap();
+        let mut child = Task::new("Changelog".to_string(), None, None, None, None, None).unwrap();
+        child.pid = Some(parent.id.clone());
+        child.completed = Some(SystemTime::now());
+
+        let output = export(&[parent, child]);
+        assert_eq!(
+            output,
+            "- [ ] Release (due 2026-12-31) #work\n  - [x] Changelog"
+        );
+
+        let tasks = import(&output).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].tags, Some(vec!["work".to_string()]));
+        assert!(tasks[0].deadline.is_some());
+        assert_eq!(parent_title(&tasks, &tasks[1]), Some("Release"));
+        assert!(tasks[1].completed.is_some());
+    }
+
+    #[test]
+    fn test_meeting_notes() {
+        let input = "# Sprint planning\n\
+                     \n\
+                     Attendees: everyone\n\
+                     \n\
+                     - [ ] Write spec #docs\n\
+                     \t- [X] Draft outline\n\
+                     - Not a task\n\
+                     1. [ ] Book room (due 2026-03-01)\n\
+                     ## Follow up\n\
+                     * [ ] Send notes\n";
+
+        let tasks = import(input).unwrap();
+        let titles: Vec<&str> = tasks.iter().map(|t| t.title.as_str()).collect();
+
+        assert_eq!(
+            titles,
+            vec![
+                "Sprint planning",
+                "Write spec",
+                "Draft outline",
+                "Book room",
+                "Follow up",
+                "Send notes"
+            ]
+        );
+        assert_eq!(parent_title(&tasks, &tasks[1]), Some("Sprint planning"));
+        assert_eq!(parent_title(&tasks, &tasks[2]), Some("Write spec"));
+        assert_eq!(parent_title(&tasks, &tasks[3]), Some("Sprint planning"));
+        assert_eq!(parent_title(&tasks, &tasks[4]), Some("Sprint planning"));
+        assert_eq!(parent_title(&tasks, &tasks[5]), Some("Follow up"));
+        assert!(tasks[2].completed.is_some());
+        assert!(tasks[3].deadline.is_some());
+    }
+
+    #[test]
+    fn test_no_checklist() {
+        assert!(import("# Notes\n\n- just a list\n").is_err());
+    }
+}
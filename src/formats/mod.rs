@@ -0,0 +1,80 @@
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+
+pub mod csv;
+pub mod ics;
+pub mod json;
+pub mod markdown;
+pub mod taskwarrior;
+pub mod todotxt;
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    #[value(name = "todotxt", alias = "todo.txt")]
+    TodoTxt,
+    Ics,
+    #[value(alias = "md")]
+    Markdown,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum ImportFormat {
+    Json,
+    Csv,
+    #[value(name = "todotxt", alias = "todo.txt")]
+    TodoTxt,
+    Ics,
+    #[value(alias = "md")]
+    Markdown,
+    /// The JSON written by Taskwarrior's `task export`
+    Taskwarrior,
+}
+
+impl ImportFormat {
+    /// Guess the format of a file to import from its extension.
+    pub fn from_extension(path: &std::path::Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(ImportFormat::Json),
+            "csv" => Some(ImportFormat::Csv),
+            "txt" => Some(ImportFormat::TodoTxt),
+            "ics" => Some(ImportFormat::Ics),
+            "md" | "markdown" => Some(ImportFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+pub enum ImportMode {
+    /// Add new tasks and update existing tasks with the same ID
+    #[default]
+    Merge,
+    /// Remove all existing tasks before importing
+    Replace,
+}
+
+/// The outcome of importing tasks into the database.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+}
+
+/// Find the single ID in `ids` that starts with `prefix`.
+pub(crate) fn resolve_id(prefix: &str, ids: &[String]) -> Result<String> {
+    let mut matches: Vec<&String> = ids.iter().filter(|id| id.starts_with(prefix)).collect();
+    matches.sort();
+    matches.dedup();
+    match matches.as_slice() {
+        [id] => Ok((*id).clone()),
+        [] => bail!("No task found matching ID '{prefix}'"),
+        _ => bail!("ID '{prefix}' matches more than one task"),
+    }
+}
+
+/// Whether a string is a full 40 character task ID.
+pub(crate) fn is_full_id(id: &str) -> bool {
+    id.len() == 40 && id.chars().all(|c| c.is_ascii_hexdigit())
+}
@@ -0,0 +1,347 @@
+//! Import from the JSON written by Taskwarrior's `task export`.
+//!
+//! Each Taskwarrior task is given an ID derived from its UUID, so importing the same export
+//! again updates the tasks rather than duplicating them. The project becomes a tag, priorities
+//! map onto difficulty, annotations are kept in the description and the later of the `wait`
+//! and `scheduled` dates becomes the scheduled date. Taskwarrior models
+//! subtasks as dependencies, so a task that is depended on by exactly one other task becomes
+//! its subtask. Attributes that have no equivalent are reported rather than silently dropped.
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, Utc};
+use serde_json::{Map, Value};
+use std::{collections::BTreeMap, time::SystemTime};
+
+use crate::{
+    deadline::Deadline,
+    task::{ID, Task},
+};
+
+type Attributes = Map<String, Value>;
+
+/// Attributes that Taskwarrior computes or uses internally, which are not worth reporting.
+const IGNORED: &[&str] = &["id", "urgency", "modified", "uuid"];
+
+/// The outcome of reading a Taskwarrior export.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub tasks: Vec<Task>,
+    /// The attributes that could not be imported, with the number of tasks that had them.
+    pub unsupported: BTreeMap<String, usize>,
+    /// The number of recurring task templates that were skipped.
+    pub skipped_recurring: usize,
+}
+
+fn parse_time(value: &str) -> Result<SystemTime> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .map(|t| t.and_utc().into())
+        .or_else(|_| DateTime::parse_from_rfc3339(value).map(SystemTime::from))
+        .map_err(|_| anyhow!("Invalid date '{value}'"))
+}
+
+fn string<'a>(attributes: &'a Attributes, name: &str) -> Option<&'a str> {
+    attributes.get(name).and_then(Value::as_str)
+}
+
+fn time(attributes: &Attributes, name: &str) -> Result<Option<SystemTime>> {
+    string(attributes, name).map(parse_time).transpose()
+}
+
+/// The UUIDs a task depends on, which older versions of Taskwarrior export as a comma
+/// separated string.
+fn depends(attributes: &Attributes) -> Vec<String> {
+    match attributes.get("depends") {
+        Some(Value::Array(uuids)) => uuids
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect(),
+        Some(Value::String(uuids)) => uuids
+            .split(',')
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .map(String::from)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A deadline from a Taskwarrior date. Taskwarrior dates given without a time are at local
+/// midnight, so those are taken to be dates.
+fn to_deadline(time: SystemTime) -> Deadline {
+    let local = DateTime::<Local>::from(time);
+    if local.time() == NaiveTime::MIN {
+        local.date_naive().into()
+    } else {
+        DateTime::<Utc>::from(time).into()
+    }
+}
+
+fn difficulty(priority: &str) -> Option<u8> {
+    match priority {
+        "H" => Some(8),
+        "M" => Some(5),
+        "L" => Some(2),
+        _ => None,
+    }
+}
+
+/// Build a task from a Taskwarrior task, recording the names of the attributes it used.
+fn import_task(attributes: &Attributes, used: &mut Vec<&'static str>) -> Result<Task> {
+    let title = string(attributes, "description")
+        .filter(|d| !d.trim().is_empty())
+        .context("Missing description")?;
+    used.push("description");
+
+    let deadline = time(attributes, "due")?.map(to_deadline);
+    used.push("due");
+
+    // Taskwarrior hides a task until its `wait` date and makes it ready on its `scheduled`
+    // date, so the later of the two is when it shows up again.
+    let scheduled = time(attributes, "scheduled")?.max(time(attributes, "wait")?);
+    used.push("scheduled");
+    used.push("wait");
+
+    let mut tags: Vec<String> = attributes
+        .get("tags")
+        .and_then(Value::as_array)
+        .map(|tags| {
+            tags.iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    used.push("tags");
+
+    if let Some(project) = string(attributes, "project") {
+        tags.push(project.to_string());
+    }
+    used.push("project");
+    tags.sort();
+    tags.dedup();
+
+    let difficulty = match string(attributes, "priority") {
+        Some(priority) => {
+            used.push("priority");
+            difficulty(priority)
+        }
+        None => None,
+    };
+
+    let annotations: Vec<&str> = attributes
+        .get("annotations")
+        .and_then(Value::as_array)
+        .map(|annotations| {
+            annotations
+                .iter()
+                .filter_map(|a| a.get("description").and_then(Value::as_str))
+                .collect()
+        })
+        .unwrap_or_default();
+    used.push("annotations");
+
+    let mut task = Task::new(
+        title.to_string(),
+        (!annotations.is_empty()).then(|| annotations.join("\n")),
+        difficulty,
+        None,
+        (!tags.is_empty()).then_some(tags),
+        None,
+    )?;
+
+    if let Some(uuid) = string(attributes, "uuid") {
+        task.id = ID::from_external(uuid);
+    }
+    task.deadline = deadline;
+    task.scheduled = scheduled.map(to_deadline);
+
+    if let Some(entry) = time(attributes, "entry")? {
+        task.created = entry;
+    }
+    used.push("entry");
+
+    let end = time(attributes, "end")?;
+    used.push("end");
+
+    match string(attributes, "status").unwrap_or("pending") {
+        "pending" | "waiting" => (),
+        "completed" => task.completed = Some(end.unwrap_or_else(SystemTime::now)),
+        "deleted" => task.deleted = Some(end.unwrap_or_else(SystemTime::now)),
+        status => bail!("Unknown status '{status}'"),
+    }
+    used.push("status");
+
+    Ok(task)
+}
+
+/// Parse a Taskwarrior export, which is either a JSON array of tasks or, from older versions,
+/// one JSON task per line.
+fn parse(input: &str) -> Result<Vec<Attributes>> {
+    let invalid = "Invalid Taskwarrior export. Create one with `task export > tasks.json`";
+
+    if input.trim_start().starts_with('[') {
+        return serde_json::from_str(input).context(invalid);
+    }
+
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line.trim().trim_end_matches(',')).context(invalid))
+        .collect()
+}
+
+pub fn import(input: &str) -> Result<Imported> {
+    let mut imported = Imported::default();
+    // The UUID, ID and dependencies of each imported task.
+    let mut links: Vec<(String, ID, Vec<String>)> = Vec::new();
+
+    for (i, attributes) in parse(input)?.iter().enumerate() {
+        // Recurring tasks are templates that Taskwarrior creates pending instances from, and
+        // the instances are imported instead.
+        if string(attributes, "status") == Some("recurring") {
+            imported.skipped_recurring += 1;
+            continue;
+        }
+
+        let mut used = Vec::new();
+        let task = import_task(attributes, &mut used).map_err(|e| {
+            let description = string(attributes, "description").unwrap_or_default();
+            anyhow!("Task {} ('{description}'): {e}", i + 1)
+        })?;
+
+        for name in attributes.keys() {
+            if !used.contains(&name.as_str())
+                && !IGNORED.contains(&name.as_str())
+                && name != "depends"
+            {
+                *imported.unsupported.entry(name.clone()).or_default() += 1;
+            }
+        }
+
+        links.push((
+            string(attributes, "uuid").unwrap_or_default().to_string(),
+            task.id.clone(),
+            depends(attributes),
+        ));
+        imported.tasks.push(task);
+    }
+
+    // A dependency becomes a subtask of the only task that depends on it. Dependencies shared
+    // by several tasks, on tasks outside the export, or that would make a task its own
+    // ancestor cannot be represented.
+    let mut dependents: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, (_, _, depends)) in links.iter().enumerate() {
+        for uuid in depends {
+            dependents.entry(uuid).or_default().push(i);
+        }
+    }
+
+    let mut unsupported_depends = 0;
+    for (i, (_, _, depends)) in links.iter().enumerate() {
+        let mut represented = true;
+        for uuid in depends {
+            let child = links.iter().position(|(u, _, _)| u == uuid);
+            match (child, dependents[uuid.as_str()].as_slice()) {
+                (Some(child), [_]) if !is_ancestor(&imported.tasks, child, i) => {
+                    imported.tasks[child].pid = Some(links[i].1.clone());
+                }
+                _ => represented = false,
+            }
+        }
+        if !represented {
+            unsupported_depends += 1;
+        }
+    }
+    if unsupported_depends > 0 {
+        imported
+            .unsupported
+            .insert("depends".to_string(), unsupported_depends);
+    }
+
+    Ok(imported)
+}
+
+/// Whether the task at index `ancestor` is the task at index `task` or one of its ancestors.
+fn is_ancestor(tasks: &[Task], ancestor: usize, task: usize) -> bool {
+    let id = tasks[ancestor].id.to_string();
+    let mut current = Some(task);
+    while let Some(index) = current {
+        if tasks[index].id.to_string() == id {
+            return true;
+        }
+        current = tasks[index].pid.as_ref().and_then(|pid| {
+            let pid = pid.to_string();
+            tasks.iter().position(|t| t.id.to_string() == pid)
+        });
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let input = r#"[
+            {"id": 1, "uuid": "a", "description": "Plan garden", "status": "pending",
+             "entry": "20260101T090000Z", "due": "20260301T120000Z", "project": "Home",
+             "tags": ["outside"], "priority": "H", "depends": ["b"], "urgency": 8.2,
+             "annotations": [{"entry": "20260102T090000Z", "description": "Ask about soil"}]},
+            {"id": 0, "uuid": "b", "description": "Buy seeds", "status": "completed",
+             "entry": "20260101T090000Z", "end": "20260105T090000Z", "wait": "20260102T000000Z"},
+            {"uuid": "c", "description": "Water plants", "status": "recurring", "recur": "daily"},
+            {"uuid": "d", "description": "Old idea", "status": "deleted", "end": "20260103T000000Z"}
+        ]"#;
+
+        let imported = import(input).unwrap();
+        let tasks = &imported.tasks;
+
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(imported.skipped_recurring, 1);
+        assert_eq!(tasks[0].title, "Plan garden");
+        assert_eq!(
+            tasks[0].tags,
+            Some(vec!["Home".to_string(), "outside".to_string()])
+        );
+        assert_eq!(tasks[0].difficulty.map(u8::from), Some(8));
+        assert_eq!(tasks[0].desc.as_deref(), Some("Ask about soil"));
+        assert!(tasks[0].deadline.is_some());
+        assert_eq!(
+            tasks[1].pid.as_ref().map(ToString::to_string),
+            Some(tasks[0].id.to_string())
+        );
+        assert!(tasks[1].completed.is_some());
+        assert!(tasks[2].deleted.is_some());
+        assert!(tasks[1].scheduled.is_some());
+        assert_eq!(imported.unsupported.get("wait"), None);
+        assert_eq!(imported.unsupported.get("urgency"), None);
+        assert_eq!(tasks[0].id.to_string(), ID::from_external("a").to_string());
+    }
+
+    #[test]
+    fn test_shared_and_cyclic_dependencies() {
+        let input = r#"
+            {"uuid": "a", "description": "A", "depends": "c"}
+            {"uuid": "b", "description": "B", "depends": "c"}
+            {"uuid": "c", "description": "C", "depends": "d"}
+            {"uuid": "d", "description": "D", "depends": "c"}
+        "#;
+
+        let imported = import(input).unwrap();
+
+        assert!(imported.tasks[2].pid.is_none());
+        assert_eq!(
+            imported.tasks[3].pid.as_ref().map(ToString::to_string),
+            Some(imported.tasks[2].id.to_string())
+        );
+        assert_eq!(imported.unsupported.get("depends"), Some(&3));
+    }
+
+    #[test]
+    fn test_invalid_export() {
+        assert!(import("not json").is_err());
+        assert!(import(r#"[{"uuid": "a"}]"#).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+//! The [todo.txt](https://github.com/todotxt/todo.txt) format.
+//!
+//! Each task is a single line. Tags are written as `+project` tags, the deadline as a
+//! `due:YYYY-MM-DD` key, the scheduled date as a `t:YYYY-MM-DD` threshold key and the
+//! difficulty as a priority, with `(A)` for difficulty 10 down to `(K)` for difficulty 0. In
+//! lossless mode the task ID and parent ID are kept as `id:` and `parent:` keys so that
+//! importing the file again updates the tasks instead of duplicating them.
+
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Local, NaiveDate};
+use std::time::SystemTime;
+
+use crate::{
+    deadline::Deadline,
+    formats::{is_full_id, resolve_id},
+    task::Task,
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+fn format_date(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format(DATE_FORMAT)
+        .to_string()
+}
+
+fn parse_date(date: &str) -> Option<SystemTime> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .ok()?
+        .and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+/// The todo.txt priority letter for a difficulty.
+fn priority(difficulty: u8) -> char {
+    char::from(b'A' + 10 - difficulty.min(10))
+}
+
+/// The difficulty for a todo.txt priority letter. Priorities below `(K)` map to 0.
+fn difficulty(priority: char) -> u8 {
+    10u8.saturating_sub(priority as u8 - b'A')
+}
+
+fn parse_priority(token: &str) -> Option<char> {
+    let mut chars = token.strip_prefix('(')?.strip_suffix(')')?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_uppercase() => Some(c),
+        _ => None,
+    }
+}
+
+fn export_task(task: &Task, lossless: bool) -> String {
+    let mut fields = Vec::new();
+
+    if let Some(completed) = task.completed {
+        fields.push("x".to_string());
+        fields.push(format_date(completed));
+    } else if let Some(d) = task.difficulty {
+        fields.push(format!("({})", priority(u8::from(d))));
+    }
+
+    fields.push(format_date(task.created));
+    fields.push(task.title.clone());
+
+    for tag in task.tags.as_deref().unwrap_or_default() {
+        fields.push(format!("+{tag}"));
+    }
+
+    if let Some(deadline) = &task.deadline {
+        fields.push(format!("due:{}", deadline.rfc3339()));
+    }
+
+    if let Some(scheduled) = &task.scheduled {
+        fields.push(format!("t:{}", scheduled.rfc3339()));
+    }
+
+    // The priority of completed tasks is kept as a key, as is conventional in todo.txt.
+    if let (Some(_), Some(d)) = (task.completed, task.difficulty) {
+        fields.push(format!("pri:{}", priority(u8::from(d))));
+    }
+
+    if lossless {
+        fields.push(format!("id:{}", task.id));
+        if let Some(pid) = &task.pid {
+            fields.push(format!("parent:{pid}"));
+        }
+    }
+
+    fields.join(" ")
+}
+
+/// Write the tasks in todo.txt format, one per line.
+pub fn export(tasks: &[Task], lossless: bool) -> String {
+    tasks
+        .iter()
+        .map(|task| export_task(task, lossless))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Build a task from a line of todo.txt, returning it with the parent ID given by its
+/// `parent:` key, if any.
+fn import_task(line: &str, known_ids: &[String]) -> Result<(Task, Option<String>)> {
+    let mut tokens = line.split_whitespace().peekable();
+
+    let mut completed = None;
+    let mut created = None;
+    let mut priority = None;
+
+    if tokens.peek() == Some(&"x") {
+        tokens.next();
+        let date = tokens.peek().and_then(|t| parse_date(t));
+        if date.is_some() {
+            tokens.next();
+        }
+        completed = Some(date.unwrap_or_else(SystemTime::now));
+    }
+
+    if let Some(p) = tokens.peek().and_then(|t| parse_priority(t)) {
+        priority = Some(p);
+        tokens.next();
+    }
+
+    if let Some(date) = tokens.peek().and_then(|t| parse_date(t)) {
+        created = Some(date);
+        tokens.next();
+    }
+
+    let mut title = Vec::new();
+    let mut tags = Vec::new();
+    let mut deadline = None;
+    let mut scheduled = None;
+    let mut id = None;
+    let mut parent = None;
+
+    for token in tokens {
+        if let Some(tag) = token
+            .strip_prefix('+')
+            .or_else(|| token.strip_prefix('@'))
+            .filter(|t| !t.is_empty())
+        {
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+            continue;
+        }
+
+        match token.split_once(':') {
+            Some(("due", value)) => deadline = Some(value.to_string()),
+            Some(("t", value)) => scheduled = Some(Deadline::parse(value)?),
+            Some(("id", value)) => id = Some(value.to_lowercase()),
+            Some(("parent", value)) => parent = Some(value.to_lowercase()),
+            Some(("pri", value)) => {
+                priority = Some(
+                    parse_priority(&format!("({value})"))
+                        .ok_or_else(|| anyhow!("Invalid priority '{value}'"))?,
+                );
+            }
+            _ => title.push(token),
+        }
+    }
+
+    if title.is_empty() {
+        bail!("Missing task title");
+    }
+
+    let mut task = Task::new(
+        title.join(" "),
+        None,
+        priority.map(difficulty),
+        deadline,
+        (!tags.is_empty()).then_some(tags),
+        None,
+    )?;
+
+    if let Some(id) = id {
+        task.id = if is_full_id(&id) {
+            id.into()
+        } else {
+            resolve_id(&id, known_ids)?.into()
+        };
+    }
+
+    if let Some(created) = created {
+        task.created = created;
+    }
+    task.completed = completed;
+    task.scheduled = scheduled;
+
+    Ok((task, parent))
+}
+
+/// Read tasks from todo.txt, skipping blank lines. Parent IDs may be abbreviated and are
+/// resolved against the IDs of the other imported tasks and `known_ids`.
+pub fn import(input: &str, known_ids: &[String]) -> Result<Vec<Task>> {
+    let mut rows = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (task, parent) =
+            import_task(line, known_ids).map_err(|e| anyhow!("Line {}: {e}", i + 1))?;
+        rows.push((i + 1, task, parent));
+    }
+
+    let mut ids: Vec<String> = rows.iter().map(|(_, t, _)| t.id.to_string()).collect();
+    ids.extend(known_ids.iter().cloned());
+
+    rows.into_iter()
+        .map(|(line, mut task, parent)| {
+            if let Some(parent) = parent {
+                task.pid = Some(
+                    resolve_id(&parent, &ids)
+                        .map_err(|e| anyhow!("Line {line}: Invalid parent: {e}"))?
+                        .into(),
+                );
+            }
+            Ok(task)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(priority(10), 'A');
+        assert_eq!(priority(0), 'K');
+        for d in 0..=10 {
+            assert_eq!(difficulty(priority(d)), d);
+        }
+        assert_eq!(difficulty('Z'), 0);
+    }
+
+    #[test]
+    fn test_import_line() {
+        let input =
+            "x 2026-03-02 2026-03-01 Call mom +family @phone due:2026-03-05 note:later pri:B";
+        let tasks = import(input, &[]).unwrap();
+
+        assert_eq!(tasks[0].title, "Call mom note:later");
+        assert_eq!(
+            tasks[0].tags,
+            Some(vec!["family".to_string(), "phone".to_string()])
+        );
+        assert_eq!(tasks[0].difficulty.map(u8::from), Some(9));
+        assert_eq!(
+            tasks[0].deadline.as_ref().map(ToString::to_string),
+            Some("2026-03-05".to_string())
+        );
+        assert_eq!(
+            tasks[0].completed.map(format_date),
+            Some("2026-03-02".to_string())
+        );
+        assert_eq!(format_date(tasks[0].created), "2026-03-01");
+    }
+
+    #[test]
+    fn test_lossless_round_trip() {
+        let parent = Task::new(
+            "Plan trip".to_string(),
+            None,
+            Some(3),
+            Some("2026-05-01".to_string()),
+            Some(vec!["travel".to_string()]),
+            None,
+        )
+        .unwrap();
+        let mut child =
+            Task::new("Book flights".to_string(), None, None, None, None, None).unwrap();
+        child.pid = Some(parent.id.clone());
+        child.completed = Some(SystemTime::now());
+
+        let parent_id = parent.id.to_string();
+        let tasks = import(&export(&[parent, child], true), &[]).unwrap();
+
+        assert_eq!(tasks[0].id.to_string(), parent_id);
+        assert_eq!(tasks[0].difficulty.map(u8::from), Some(3));
+        assert_eq!(
+            tasks[1].pid.as_ref().map(ToString::to_string),
+            Some(parent_id)
+        );
+        assert!(tasks[1].completed.is_some());
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        assert!(import("(A) Task due:someday", &[]).is_err());
+        assert!(import("+tag @context", &[]).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use anyhow::Result;
+use chrono::{DateTime, Days, Local, NaiveDate};
+use colored::Colorize;
+use rusqlite::{params, params_from_iter, types::Value};
+use std::{collections::HashMap, fmt::Display, time::SystemTime};
+
+use crate::{
+    db::{Database, timestamp_now},
+    deadline::{DateFormat, Deadline},
+    journal::State,
+    task::ID,
+};
+
+/// How a task was changed by a single operation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Created,
+    Removed,
+    Restored,
+    Deleted,
+    Completed,
+    Reopened,
+    Updated {
+        field: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+impl Change {
+    fn action(&self) -> &'static str {
+        match self {
+            Change::Created => "created",
+            Change::Removed => "removed",
+            Change::Restored => "restored",
+            Change::Deleted => "deleted",
+            Change::Completed => "completed",
+            Change::Reopened => "reopened",
+            Change::Updated { .. } => "updated",
+        }
+    }
+}
+
+fn field_name(column: &str) -> &str {
+    match column {
+        "title" => "Task",
+        "description" => "Description",
+        "difficulty" => "Difficulty",
+        "deadline" => "Deadline",
+        "parent_id" => "Parent",
+        "recurrence" => "Recurrence",
+        "series" => "Series",
+        "status" => "Status",
+        "waiting_until" => "Waiting until",
+        "scheduled" => "Scheduled",
+        "tags" => "Tags",
+        "blocked_by" => "Blocked by",
+        other => other,
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Created => write!(f, "{}", "created".green()),
+            Change::Removed => write!(f, "{}", "removed".red()),
+            Change::Restored => write!(f, "{}", "restored".green()),
+            Change::Deleted => write!(f, "{}", "permanently deleted".red()),
+            Change::Completed => write!(f, "{}", "completed".green()),
+            Change::Reopened => write!(f, "{}", "reopened".yellow()),
+            Change::Updated { field, old, new } => write!(
+                f,
+                "{}: {} → {}",
+                field_name(field),
+                old.as_deref().unwrap_or("(none)"),
+                new.as_deref().unwrap_or("(none)")
+            ),
+        }
+    }
+}
+
+/// A change made to a task, as recorded in its history.
+#[derive(Debug)]
+pub struct HistoryEvent {
+    pub task_id: ID,
+    pub title: String,
+    pub timestamp: SystemTime,
+    pub change: Change,
+}
+
+impl Display for HistoryEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timestamp: DateTime<Local> = self.timestamp.into();
+        write!(
+            f,
+            "{}  {}  {}  {}",
+            timestamp.format(&format!("%H:%M:%S {}", DateFormat::current().pattern())),
+            self.task_id.short(),
+            self.title,
+            self.change
+        )
+    }
+}
+
+impl TryFrom<&rusqlite::Row<'_>> for HistoryEvent {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &rusqlite::Row<'_>) -> Result<Self, rusqlite::Error> {
+        let timestamp: i64 = row.get(2)?;
+        let action: String = row.get(3)?;
+
+        let change = match action.as_str() {
+            "created" => Change::Created,
+            "removed" => Change::Removed,
+            "restored" => Change::Restored,
+            "deleted" => Change::Deleted,
+            "completed" => Change::Completed,
+            "reopened" => Change::Reopened,
+            _ => Change::Updated {
+                field: row.get(4)?,
+                old: row.get(5)?,
+                new: row.get(6)?,
+            },
+        };
+
+        Ok(Self {
+            task_id: row.get::<_, String>(0)?.into(),
+            title: row.get(1)?,
+            timestamp: DateTime::from_timestamp(timestamp, 0)
+                .expect("invalid timestamp")
+                .into(),
+            change,
+        })
+    }
+}
+
+fn render_value(column: &str, value: Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::Integer(t) if ["created", "completed", "deleted"].contains(&column) => Some(
+            DateTime::from_timestamp(t, 0)
+                .map(|t| {
+                    t.with_timezone(&Local)
+                        .format(&format!("%H:%M:%S {}", DateFormat::current().pattern()))
+                        .to_string()
+                })
+                .unwrap_or_else(|| t.to_string()),
+        ),
+        Value::Integer(i) => Some(i.to_string()),
+        Value::Real(r) => Some(r.to_string()),
+        Value::Text(s) => Some(s),
+        Value::Blob(_) => Some("<blob>".to_string()),
+    }
+}
+
+/// The start of a day as a unix timestamp in the local timezone.
+pub(crate) fn day_start(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|d| d.and_local_timezone(Local).earliest())
+        .map_or(0, |d| d.timestamp())
+}
+
+type Snapshot = HashMap<String, HashMap<String, Option<String>>>;
+
+impl Database {
+    /// The rendered field values of each task in a journal snapshot, keyed by task ID.
+    fn snapshot_fields(&self, entry: i64, state: State) -> Result<Snapshot> {
+        let columns = self.task_columns()?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM journal_tasks WHERE journal_id = ?1 AND state = ?2",
+            columns.join(", ")
+        ))?;
+
+        let mut snapshot: Snapshot = stmt
+            .query_map(params![entry, state.as_str()], |row| {
+                let mut fields = HashMap::new();
+                for (i, column) in columns.iter().enumerate() {
+                    fields.insert(column.clone(), render_value(column, row.get(i)?));
+                }
+                Ok(fields)
+            })?
+            .map(|fields| fields.map(|f| (f["id"].clone().unwrap_or_default(), f)))
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT task_id, group_concat(name, ', ')
+             FROM (SELECT task_id, name FROM journal_tags
+                   WHERE journal_id = ?1 AND state = ?2
+                   ORDER BY name)
+             GROUP BY task_id",
+        )?;
+        let tags = stmt
+            .query_map(params![entry, state.as_str()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        for fields in snapshot.values_mut() {
+            fields.insert("tags".to_string(), None);
+        }
+        for (id, tags) in tags {
+            if let Some(fields) = snapshot.get_mut(&id) {
+                fields.insert("tags".to_string(), Some(tags));
+            }
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT task_id, group_concat(substr(blocker_id, 1, 7), ', ')
+             FROM (SELECT task_id, blocker_id FROM journal_depends
+                   WHERE journal_id = ?1 AND state = ?2
+                   ORDER BY blocker_id)
+             GROUP BY task_id",
+        )?;
+        let blockers = stmt
+            .query_map(params![entry, state.as_str()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        for fields in snapshot.values_mut() {
+            fields.insert("blocked_by".to_string(), None);
+        }
+        for (id, blockers) in blockers {
+            if let Some(fields) = snapshot.get_mut(&id) {
+                fields.insert("blocked_by".to_string(), Some(blockers));
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Record the per-field changes between two snapshots of a journal entry in the task
+    /// history.
+    pub(crate) fn record_history(&self, entry: i64, from: State, to: State) -> Result<()> {
+        let from = self.snapshot_fields(entry, from)?;
+        let to = self.snapshot_fields(entry, to)?;
+
+        let mut columns = self.task_columns()?;
+        columns.push("tags".to_string());
+        columns.push("blocked_by".to_string());
+
+        let mut ids: Vec<&String> = from.keys().chain(to.keys()).collect();
+        ids.sort();
+        ids.dedup();
+
+        for id in ids {
+            let (old, new) = (from.get(id), to.get(id));
+            let title = new
+                .or(old)
+                .and_then(|f| f["title"].clone())
+                .unwrap_or_default();
+
+            let changes = match (old, new) {
+                (None, Some(_)) => vec![Change::Created],
+                (Some(_), None) => vec![Change::Deleted],
+                (Some(old), Some(new)) => columns
+                    .iter()
+                    .filter(|c| *c != "id" && *c != "created")
+                    .filter(|c| old.get(*c) != new.get(*c))
+                    .map(|c| match (c.as_str(), &new[c]) {
+                        ("completed", Some(_)) => Change::Completed,
+                        ("completed", None) => Change::Reopened,
+                        ("deleted", Some(_)) => Change::Removed,
+                        ("deleted", None) => Change::Restored,
+                        _ => Change::Updated {
+                            field: c.clone(),
+                            old: old.get(c).cloned().flatten(),
+                            new: new[c].clone(),
+                        },
+                    })
+                    .collect(),
+                (None, None) => Vec::new(),
+            };
+
+            for change in changes {
+                self.record_change(id, &title, &change)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn record_change(&self, id: &str, title: &str, change: &Change) -> Result<()> {
+        let (field, old, new) = match change {
+            Change::Updated { field, old, new } => (Some(field), old.as_ref(), new.as_ref()),
+            _ => (None, None, None),
+        };
+
+        self.conn.execute(
+            "INSERT INTO history (task_id, title, timestamp, action, field, old_value, new_value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![id, title, timestamp_now(), change.action(), field, old, new],
+        )?;
+
+        Ok(())
+    }
+
+    /// The recorded changes, oldest first, optionally limited to the tasks matching a
+    /// (possibly partial) ID and to changes made between two dates inclusive.
+    pub fn history(
+        &self,
+        id: Option<&str>,
+        after: Option<Deadline>,
+        before: Option<Deadline>,
+    ) -> Result<Vec<HistoryEvent>> {
+        let mut query = String::from(
+            "SELECT task_id, title, timestamp, action, field, old_value, new_value FROM history",
+        );
+        let mut conditions = Vec::new();
+        let mut params: Vec<Value> = Vec::new();
+
+        if let Some(id) = id {
+            conditions.push("task_id LIKE ?");
+            params.push(Value::Text(format!("{id}%")));
+        }
+
+        if let Some(after) = after {
+            conditions.push("timestamp >= ?");
+            params.push(Value::Integer(day_start(after.date())));
+        }
+
+        if let Some(before) = before
+            && let Some(next_day) = before.date().checked_add_days(Days::new(1))
+        {
+            conditions.push("timestamp < ?");
+            params.push(Value::Integer(day_start(next_day)));
+        }
+
+        if !conditions.is_empty() {
+            query.push_str(" WHERE ");
+            query.push_str(&conditions.join(" AND "));
+        }
+
+        query.push_str(" ORDER BY timestamp ASC, id ASC");
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let events = stmt
+            .query_map(params_from_iter(params.iter()), |row| {
+                HistoryEvent::try_from(row)
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(events)
+    }
+}
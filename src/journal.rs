@@ -0,0 +1,372 @@
+use anyhow::{Result, bail};
+use chrono::{DateTime, Local};
+use rusqlite::{OptionalExtension, params, params_from_iter};
+use std::{fmt::Display, time::SystemTime};
+
+use crate::{
+    db::{Database, timestamp_now},
+    deadline::DateFormat,
+};
+
+/// Which side of a journaled operation a snapshot was taken on.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum State {
+    Before,
+    After,
+}
+
+impl State {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            State::Before => "before",
+            State::After => "after",
+        }
+    }
+}
+
+/// A mutating operation recorded in the journal, together with snapshots of the tasks it
+/// touched so that it can be undone and redone. Starting and stopping time tracking and
+/// planning a day are not recorded, as they change time entries and plans but no tasks.
+#[derive(Debug)]
+pub struct JournalEntry {
+    pub id: i64,
+    pub operation: String,
+    pub timestamp: SystemTime,
+    pub undone: bool,
+}
+
+impl Display for JournalEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timestamp: DateTime<Local> = self.timestamp.into();
+        write!(
+            f,
+            "{:>4}  {}  {}{}",
+            self.id,
+            timestamp.format(&format!("%H:%M:%S {}", DateFormat::current().pattern())),
+            self.operation,
+            if self.undone { " (undone)" } else { "" }
+        )
+    }
+}
+
+fn placeholders(n: usize) -> String {
+    vec!["?"; n].join(", ")
+}
+
+impl Database {
+    /// Open a journal entry for an operation that is about to modify the given tasks and
+    /// snapshot their current state. Any previously undone operations can no longer be redone.
+    ///
+    /// Must be called within a transaction, and paired with [`Database::journal_end`] once the
+    /// modification has been made.
+    pub(crate) fn journal_begin(&self, operation: &str, ids: &[String]) -> Result<i64> {
+        self.conn
+            .execute("DELETE FROM journal WHERE undone = 1", [])?;
+
+        self.conn.execute(
+            "INSERT INTO journal (operation, timestamp) VALUES (?1, ?2)",
+            params![operation, timestamp_now()],
+        )?;
+        let entry = self.conn.last_insert_rowid();
+
+        self.snapshot(entry, State::Before, ids)?;
+
+        Ok(entry)
+    }
+
+    /// Snapshot the state of the given tasks after the operation recorded by `entry`, and
+    /// record the changes it made in the task history.
+    pub(crate) fn journal_end(&self, entry: i64, ids: &[String]) -> Result<()> {
+        self.snapshot(entry, State::After, ids)?;
+        self.record_history(entry, State::Before, State::After)
+    }
+
+    pub(crate) fn task_columns(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM pragma_table_info('tasks')")?;
+        let columns = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(columns)
+    }
+
+    fn snapshot(&self, entry: i64, state: State, ids: &[String]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let columns = self.task_columns()?.join(", ");
+        let mut values: Vec<&dyn rusqlite::ToSql> = vec![&entry];
+        let state = state.as_str();
+        values.push(&state);
+        values.extend(ids.iter().map(|id| id as &dyn rusqlite::ToSql));
+
+        self.conn.execute(
+            &format!(
+                "INSERT INTO journal_tasks (journal_id, state, {columns})
+                 SELECT ?, ?, {columns} FROM tasks WHERE id IN ({})",
+                placeholders(ids.len())
+            ),
+            params_from_iter(values.iter()),
+        )?;
+
+        self.conn.execute(
+            &format!(
+                "INSERT INTO journal_tags (journal_id, state, task_id, name)
+                 SELECT ?, ?, tt.task_id, tg.name
+                 FROM task_tags tt
+                 JOIN tags tg ON tt.tag_id = tg.id
+                 WHERE tt.task_id IN ({})",
+                placeholders(ids.len())
+            ),
+            params_from_iter(values.iter()),
+        )?;
+
+        // Time entries and plans are snapshotted too, as deleting a task deletes them.
+        self.conn.execute(
+            &format!(
+                "INSERT INTO journal_time_entries (journal_id, state, task_id, started, stopped)
+                 SELECT ?, ?, task_id, started, stopped FROM time_entries WHERE task_id IN ({})",
+                placeholders(ids.len())
+            ),
+            params_from_iter(values.iter()),
+        )?;
+
+        self.conn.execute(
+            &format!(
+                "INSERT INTO journal_plans (journal_id, state, date, task_id, position)
+                 SELECT ?, ?, date, task_id, position FROM plans WHERE task_id IN ({})",
+                placeholders(ids.len())
+            ),
+            params_from_iter(values.iter()),
+        )?;
+
+        // Dependencies on the tasks are snapshotted too, as deleting a task deletes the
+        // dependencies of other tasks on it.
+        let ids = placeholders(ids.len());
+        values.extend(values[2..].to_vec());
+        self.conn.execute(
+            &format!(
+                "INSERT INTO journal_depends (journal_id, state, task_id, blocker_id)
+                 SELECT ?, ?, task_id, blocker_id
+                 FROM depends_on
+                 WHERE task_id IN ({ids}) OR blocker_id IN ({ids})"
+            ),
+            params_from_iter(values.iter()),
+        )?;
+
+        Ok(())
+    }
+
+    /// Return the tasks and tag links touched by `entry` to their `state` snapshot.
+    fn restore_snapshot(&self, entry: i64, state: State) -> Result<()> {
+        let state = state.as_str();
+        let columns = self.task_columns()?;
+        let updates = columns
+            .iter()
+            .filter(|c| *c != "id")
+            .map(|c| format!("{c} = excluded.{c}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let columns = columns.join(", ");
+
+        // Restored rows may reference parents that are restored later in the same statement.
+        self.conn.execute("PRAGMA defer_foreign_keys = ON", [])?;
+
+        // Tasks that were deleted lost their time entries and plans with them, which are
+        // brought back from the snapshot. Those of tasks that still exist are left alone.
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM journal_tasks
+             WHERE journal_id = ?1 AND state = ?2 AND id NOT IN (SELECT id FROM tasks)",
+        )?;
+        let revived = stmt
+            .query_map(params![entry, state], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        self.conn.execute(
+            "DELETE FROM tasks
+             WHERE id IN (SELECT id FROM journal_tasks WHERE journal_id = ?1)
+             AND id NOT IN (SELECT id FROM journal_tasks WHERE journal_id = ?1 AND state = ?2)",
+            params![entry, state],
+        )?;
+
+        self.conn.execute(
+            &format!(
+                "INSERT INTO tasks ({columns})
+                 SELECT {columns} FROM journal_tasks WHERE journal_id = ?1 AND state = ?2
+                 ON CONFLICT(id) DO UPDATE SET {updates}"
+            ),
+            params![entry, state],
+        )?;
+
+        self.conn.execute(
+            "DELETE FROM task_tags
+             WHERE task_id IN (SELECT id FROM journal_tasks WHERE journal_id = ?1 AND state = ?2)",
+            params![entry, state],
+        )?;
+
+        self.conn.execute(
+            "INSERT OR IGNORE INTO tags (name)
+             SELECT name FROM journal_tags WHERE journal_id = ?1 AND state = ?2",
+            params![entry, state],
+        )?;
+
+        self.conn.execute(
+            "INSERT INTO task_tags (task_id, tag_id)
+             SELECT jt.task_id, tg.id
+             FROM journal_tags jt
+             JOIN tags tg ON jt.name = tg.name
+             WHERE jt.journal_id = ?1 AND jt.state = ?2",
+            params![entry, state],
+        )?;
+
+        self.conn.execute(
+            "DELETE FROM depends_on
+             WHERE task_id IN (SELECT id FROM journal_tasks WHERE journal_id = ?1)
+             OR blocker_id IN (SELECT id FROM journal_tasks WHERE journal_id = ?1)",
+            [entry],
+        )?;
+
+        self.conn.execute(
+            "INSERT OR IGNORE INTO depends_on (task_id, blocker_id)
+             SELECT task_id, blocker_id FROM journal_depends WHERE journal_id = ?1 AND state = ?2",
+            params![entry, state],
+        )?;
+
+        if !revived.is_empty() {
+            let mut values: Vec<&dyn rusqlite::ToSql> = vec![&entry, &state];
+            values.extend(revived.iter().map(|id| id as &dyn rusqlite::ToSql));
+
+            // A restored entry that is still running is dropped if another one has started.
+            self.conn.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO time_entries (task_id, started, stopped)
+                     SELECT task_id, started, stopped FROM journal_time_entries
+                     WHERE journal_id = ? AND state = ? AND task_id IN ({})",
+                    placeholders(revived.len())
+                ),
+                params_from_iter(values.iter()),
+            )?;
+
+            self.conn.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO plans (date, task_id, position)
+                     SELECT date, task_id, position FROM journal_plans
+                     WHERE journal_id = ? AND state = ? AND task_id IN ({})",
+                    placeholders(revived.len())
+                ),
+                params_from_iter(values.iter()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn journal_entry(&self, id: i64) -> Result<JournalEntry> {
+        let entry = self.conn.query_row(
+            "SELECT id, operation, timestamp, undone FROM journal WHERE id = ?1",
+            [id],
+            |row| JournalEntry::try_from(row),
+        )?;
+        Ok(entry)
+    }
+
+    /// Undo the last `n` operations that have not already been undone.
+    pub fn undo(&mut self, n: usize) -> Result<Vec<JournalEntry>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut undone = Vec::new();
+
+        for _ in 0..n {
+            let entry: Option<i64> = self
+                .conn
+                .query_row(
+                    "SELECT id FROM journal WHERE undone = 0 ORDER BY id DESC LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            let Some(entry) = entry else {
+                break;
+            };
+
+            self.restore_snapshot(entry, State::Before)?;
+            self.record_history(entry, State::After, State::Before)?;
+            self.conn
+                .execute("UPDATE journal SET undone = 1 WHERE id = ?1", [entry])?;
+            undone.push(self.journal_entry(entry)?);
+        }
+
+        if undone.is_empty() {
+            bail!("Nothing to undo");
+        }
+
+        tx.commit()?;
+        Ok(undone)
+    }
+
+    /// Redo the last `n` operations that were undone.
+    pub fn redo(&mut self, n: usize) -> Result<Vec<JournalEntry>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut redone = Vec::new();
+
+        for _ in 0..n {
+            let entry: Option<i64> = self
+                .conn
+                .query_row(
+                    "SELECT id FROM journal WHERE undone = 1 ORDER BY id ASC LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            let Some(entry) = entry else {
+                break;
+            };
+
+            self.restore_snapshot(entry, State::After)?;
+            self.record_history(entry, State::Before, State::After)?;
+            self.conn
+                .execute("UPDATE journal SET undone = 0 WHERE id = ?1", [entry])?;
+            redone.push(self.journal_entry(entry)?);
+        }
+
+        if redone.is_empty() {
+            bail!("Nothing to redo");
+        }
+
+        tx.commit()?;
+        Ok(redone)
+    }
+
+    /// The journal of recorded operations, most recent first.
+    pub fn journal(&self) -> Result<Vec<JournalEntry>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, operation, timestamp, undone FROM journal ORDER BY id DESC")?;
+
+        let entries = stmt
+            .query_map([], |row| JournalEntry::try_from(row))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+}
+
+impl TryFrom<&rusqlite::Row<'_>> for JournalEntry {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &rusqlite::Row<'_>) -> Result<Self, rusqlite::Error> {
+        let timestamp: i64 = row.get(2)?;
+
+        Ok(Self {
+            id: row.get(0)?,
+            operation: row.get(1)?,
+            timestamp: DateTime::from_timestamp(timestamp, 0)
+                .expect("invalid timestamp")
+                .into(),
+            undone: row.get(3)?,
+        })
+    }
+}
@@ -1,5 +1,15 @@
+pub mod calendar;
 pub mod cli;
+pub mod config;
 pub mod db;
 pub mod deadline;
 pub mod display;
+pub mod formats;
+pub mod history;
+pub mod journal;
+pub mod migrations;
+pub mod plan;
+pub mod recurrence;
 pub mod task;
+pub mod tracking;
+pub mod urgency;
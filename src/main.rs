@@ -1,13 +1,18 @@
 use anyhow::Result;
 use clap::Parser;
 use todo_cli::{
-    cli::{Cli, Commands},
+    cli::{Cli, Commands, DbCommands, TrackCommands, TrashCommands},
     db::Database,
 };
 
 fn main() -> Result<()> {
     let args = Cli::parse();
-    let db = Database::load(args.path)?;
+    let db = match args.command {
+        Commands::Db { .. } => Database::open(args.path)?,
+        _ => Database::load(args.path)?,
+    };
+    args.date_format.unwrap_or(db.config.date_format).set();
+    db.config.calendar.clone().set();
 
     match args.command {
         Commands::Add {
@@ -17,9 +22,27 @@ fn main() -> Result<()> {
             deadline,
             tags,
             pid,
-        } => Commands::add(db, title, description, difficulty, deadline, tags, pid)?,
+            blocked_by,
+            recur,
+            scheduled,
+        } => Commands::add(
+            db,
+            title,
+            description,
+            difficulty,
+            deadline,
+            tags,
+            pid,
+            blocked_by,
+            recur,
+            scheduled,
+        )?,
         Commands::Complete { id } => Commands::complete(db, id)?,
         Commands::Incomplete { id } => Commands::incomplete(db, id)?,
+        Commands::Start { id } => Commands::start(db, id)?,
+        Commands::Wait { id, until } => Commands::wait(db, id, until)?,
+        Commands::Stop { id } => Commands::stop(db, id)?,
+        Commands::Defer { id, when, clear } => Commands::defer(db, id, when, clear)?,
         Commands::Update {
             id,
             title,
@@ -28,9 +51,56 @@ fn main() -> Result<()> {
             deadline,
             tags,
             pid,
-        } => Commands::update(db, id, title, description, difficulty, deadline, tags, pid)?,
-        Commands::Next => Commands::next(db)?,
+            blocked_by,
+            recur,
+            no_recur,
+            scheduled,
+        } => Commands::update(
+            db,
+            id,
+            title,
+            description,
+            difficulty,
+            deadline,
+            tags,
+            pid,
+            blocked_by,
+            recur,
+            no_recur,
+            scheduled,
+        )?,
+        Commands::Next {
+            count,
+            view,
+            columns,
+            tags,
+            pid,
+            before,
+            after,
+            skip_parents,
+            include_deferred,
+            explain,
+        } => Commands::next(
+            db,
+            count,
+            view,
+            columns,
+            tags,
+            pid,
+            before,
+            after,
+            skip_parents,
+            include_deferred,
+            explain,
+        )?,
+        Commands::Plan {
+            day,
+            budget,
+            yes,
+            show,
+        } => Commands::plan(db, day, budget, yes, show)?,
         Commands::Show { id } => Commands::show(db, id)?,
+        Commands::Date { expression } => Commands::date(expression)?,
         Commands::List {
             view,
             columns,
@@ -40,10 +110,80 @@ fn main() -> Result<()> {
             after,
             all,
             completed,
-        } => Commands::list(db, view, columns, tags, pid, before, after, all, completed)?,
+            series,
+            status,
+            include_deferred,
+        } => Commands::list(
+            db,
+            view,
+            columns,
+            tags,
+            pid,
+            before,
+            after,
+            all,
+            completed,
+            series,
+            status,
+            include_deferred,
+        )?,
+        Commands::Board {
+            by,
+            tags,
+            pid,
+            before,
+            after,
+            all,
+            include_deferred,
+        } => Commands::board(db, by, tags, pid, before, after, all, include_deferred)?,
+        Commands::Calendar {
+            month,
+            tags,
+            all,
+            include_deferred,
+        } => Commands::calendar(db, month, tags, all, include_deferred)?,
+        Commands::Agenda {
+            days,
+            tags,
+            include_deferred,
+        } => Commands::agenda(db, days, tags, include_deferred)?,
         Commands::Remove { ids, tags } => Commands::remove(db, ids, tags)?,
         Commands::Tags => Commands::tags(db)?,
+        Commands::Log { id, after, before } => Commands::log(db, id, after, before)?,
+        Commands::Undo { n, list } => Commands::undo(db, n, list)?,
+        Commands::Redo { n } => Commands::redo(db, n)?,
+        Commands::Restore { id } => Commands::restore(db, id)?,
+        Commands::Trash { command } => match command {
+            TrashCommands::List => TrashCommands::list(db)?,
+            TrashCommands::Empty { older_than, force } => {
+                TrashCommands::empty(db, older_than, force)?
+            }
+        },
+        Commands::Track { command } => match command {
+            TrackCommands::Start { id } => TrackCommands::start(db, id)?,
+            TrackCommands::Stop => TrackCommands::stop(db)?,
+            TrackCommands::Report {
+                after,
+                before,
+                tags,
+            } => TrackCommands::report(db, after, before, tags)?,
+        },
+        Commands::Export {
+            format,
+            columns,
+            lossless,
+            output,
+        } => Commands::export(db, format, columns, lossless, output)?,
+        Commands::Import {
+            file,
+            format,
+            map,
+            mode,
+        } => Commands::import(db, file, format, map, mode)?,
         Commands::Clear { force } => Commands::clear(db, force)?,
+        Commands::Db { command } => match command {
+            DbCommands::Migrate { dry_run } => DbCommands::migrate(db, dry_run)?,
+        },
     };
 
     Ok(())
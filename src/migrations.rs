@@ -0,0 +1,351 @@
+use anyhow::{Context, Result, bail};
+use rusqlite::Connection;
+
+/// A single, ordered step in the evolution of the task database schema.
+///
+/// The schema version of a database is tracked with `PRAGMA user_version`. A database at
+/// version `n` has had every migration up to and including `n` applied.
+#[derive(Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Create the tasks, tags and task_tags tables",
+        sql: "
+        CREATE TABLE IF NOT EXISTS tasks (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            description TEXT,
+            difficulty INTEGER,
+            deadline INTEGER,
+            parent_id TEXT,
+            created INTEGER NOT NULL,
+            completed INTEGER,
+            FOREIGN KEY (parent_id) REFERENCES tasks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY,
+            name TEXT UNIQUE NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS task_tags (
+            task_id TEXT NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (task_id, tag_id),
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_tasks_parent ON tasks(parent_id);
+        CREATE INDEX IF NOT EXISTS idx_task_tags_task ON task_tags(task_id);
+        CREATE INDEX IF NOT EXISTS idx_task_tags_tag ON task_tags(tag_id);
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "Add the operation journal used by undo and redo",
+        sql: "
+        CREATE TABLE journal (
+            id INTEGER PRIMARY KEY,
+            operation TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            undone INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE journal_tasks (
+            journal_id INTEGER NOT NULL,
+            state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+            id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT,
+            difficulty INTEGER,
+            deadline INTEGER,
+            parent_id TEXT,
+            created INTEGER NOT NULL,
+            completed INTEGER,
+            PRIMARY KEY (journal_id, state, id),
+            FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE journal_tags (
+            journal_id INTEGER NOT NULL,
+            state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+            task_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX idx_journal_tasks_journal ON journal_tasks(journal_id);
+        CREATE INDEX idx_journal_tags_journal ON journal_tags(journal_id);
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "Add the per-field task change history",
+        sql: "
+        CREATE TABLE history (
+            id INTEGER PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            field TEXT,
+            old_value TEXT,
+            new_value TEXT
+        );
+
+        CREATE INDEX idx_history_task ON history(task_id);
+        CREATE INDEX idx_history_timestamp ON history(timestamp);
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "Add a deleted timestamp to tasks for the trash",
+        sql: "
+        ALTER TABLE tasks ADD COLUMN deleted INTEGER;
+        ALTER TABLE journal_tasks ADD COLUMN deleted INTEGER;
+
+        CREATE INDEX idx_tasks_deleted ON tasks(deleted);
+        ",
+    },
+    Migration {
+        version: 5,
+        description: "Add recurrence rules and series to tasks",
+        sql: "
+        ALTER TABLE tasks ADD COLUMN recurrence TEXT;
+        ALTER TABLE tasks ADD COLUMN series TEXT;
+        ALTER TABLE journal_tasks ADD COLUMN recurrence TEXT;
+        ALTER TABLE journal_tasks ADD COLUMN series TEXT;
+
+        CREATE INDEX idx_tasks_series ON tasks(series);
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "Add dependencies between tasks",
+        sql: "
+        CREATE TABLE depends_on (
+            task_id TEXT NOT NULL,
+            blocker_id TEXT NOT NULL,
+            PRIMARY KEY (task_id, blocker_id),
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+            FOREIGN KEY (blocker_id) REFERENCES tasks(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE journal_depends (
+            journal_id INTEGER NOT NULL,
+            state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+            task_id TEXT NOT NULL,
+            blocker_id TEXT NOT NULL,
+            FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX idx_depends_on_blocker ON depends_on(blocker_id);
+        CREATE INDEX idx_journal_depends_journal ON journal_depends(journal_id);
+        ",
+    },
+    Migration {
+        version: 7,
+        description: "Add a workflow status to tasks",
+        sql: "
+        ALTER TABLE tasks ADD COLUMN status TEXT NOT NULL DEFAULT 'todo';
+        ALTER TABLE tasks ADD COLUMN waiting_until TEXT;
+        ALTER TABLE journal_tasks ADD COLUMN status TEXT NOT NULL DEFAULT 'todo';
+        ALTER TABLE journal_tasks ADD COLUMN waiting_until TEXT;
+
+        CREATE INDEX idx_tasks_status ON tasks(status);
+        ",
+    },
+    Migration {
+        version: 8,
+        description: "Add time entries for time tracking",
+        sql: "
+        CREATE TABLE time_entries (
+            id INTEGER PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            started INTEGER NOT NULL,
+            stopped INTEGER,
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX idx_time_entries_task ON time_entries(task_id);
+        CREATE INDEX idx_time_entries_started ON time_entries(started);
+        CREATE UNIQUE INDEX idx_time_entries_running ON time_entries((stopped IS NULL))
+            WHERE stopped IS NULL;
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "Add daily plans",
+        sql: "
+        CREATE TABLE plans (
+            date TEXT NOT NULL,
+            task_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (date, task_id),
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX idx_plans_task ON plans(task_id);
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "Add a scheduled date to tasks for deferral",
+        sql: "
+        ALTER TABLE tasks ADD COLUMN scheduled TEXT;
+        ALTER TABLE journal_tasks ADD COLUMN scheduled TEXT;
+
+        CREATE INDEX idx_tasks_scheduled ON tasks(scheduled);
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "Store dates with a time of day as local dates and times",
+        sql: "
+        UPDATE tasks SET deadline = strftime('%Y-%m-%dT%H:%M', deadline, 'localtime')
+            WHERE length(deadline) > 10;
+        UPDATE tasks SET waiting_until = strftime('%Y-%m-%dT%H:%M', waiting_until, 'localtime')
+            WHERE length(waiting_until) > 10;
+        UPDATE tasks SET scheduled = strftime('%Y-%m-%dT%H:%M', scheduled, 'localtime')
+            WHERE length(scheduled) > 10;
+
+        UPDATE journal_tasks SET deadline = strftime('%Y-%m-%dT%H:%M', deadline, 'localtime')
+            WHERE length(deadline) > 10;
+        UPDATE journal_tasks
+            SET waiting_until = strftime('%Y-%m-%dT%H:%M', waiting_until, 'localtime')
+            WHERE length(waiting_until) > 10;
+        UPDATE journal_tasks SET scheduled = strftime('%Y-%m-%dT%H:%M', scheduled, 'localtime')
+            WHERE length(scheduled) > 10;
+        ",
+    },
+    Migration {
+        version: 12,
+        description: "Add the time entries and plans of tasks to the journal",
+        sql: "
+        CREATE TABLE journal_time_entries (
+            journal_id INTEGER NOT NULL,
+            state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+            task_id TEXT NOT NULL,
+            started INTEGER NOT NULL,
+            stopped INTEGER,
+            FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE journal_plans (
+            journal_id INTEGER NOT NULL,
+            state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+            date TEXT NOT NULL,
+            task_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX idx_journal_time_entries_journal ON journal_time_entries(journal_id);
+        CREATE INDEX idx_journal_plans_journal ON journal_plans(journal_id);
+        ",
+    },
+    Migration {
+        version: 13,
+        description: "Store dates with a time of day in UTC again",
+        sql: "
+        UPDATE tasks SET deadline = strftime('%Y-%m-%dT%H:%M:%SZ', deadline, 'utc')
+            WHERE deadline GLOB '????-??-??T??:??';
+        UPDATE tasks SET waiting_until = strftime('%Y-%m-%dT%H:%M:%SZ', waiting_until, 'utc')
+            WHERE waiting_until GLOB '????-??-??T??:??';
+        UPDATE tasks SET scheduled = strftime('%Y-%m-%dT%H:%M:%SZ', scheduled, 'utc')
+            WHERE scheduled GLOB '????-??-??T??:??';
+
+        UPDATE journal_tasks SET deadline = strftime('%Y-%m-%dT%H:%M:%SZ', deadline, 'utc')
+            WHERE deadline GLOB '????-??-??T??:??';
+        UPDATE journal_tasks
+            SET waiting_until = strftime('%Y-%m-%dT%H:%M:%SZ', waiting_until, 'utc')
+            WHERE waiting_until GLOB '????-??-??T??:??';
+        UPDATE journal_tasks SET scheduled = strftime('%Y-%m-%dT%H:%M:%SZ', scheduled, 'utc')
+            WHERE scheduled GLOB '????-??-??T??:??';
+        ",
+    },
+];
+
+/// The schema version produced by applying every known migration.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+pub fn current_version(conn: &Connection) -> Result<u32> {
+    let version = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .context("Unable to read the database schema version")?;
+    Ok(version)
+}
+
+/// The migrations that still need to be applied to bring the database up to date.
+pub fn pending(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    let current = current_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        bail!(
+            "The task database has schema version {current}, which is newer than the latest version supported by this version of todo ({latest})"
+        );
+    }
+
+    Ok(MIGRATIONS.iter().filter(|m| m.version > current).collect())
+}
+
+/// Apply each pending migration in order, each in its own transaction so that a failing step
+/// leaves the database at the last successfully applied version.
+pub fn migrate(conn: &mut Connection) -> Result<Vec<&'static Migration>> {
+    let pending = pending(conn)?;
+
+    for migration in &pending {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.sql).with_context(|| {
+            format!(
+                "Migration to schema version {} failed: {}",
+                migration.version, migration.description
+            )
+        })?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_migration_versions_are_sequential() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, i + 1);
+        }
+    }
+
+    #[test]
+    fn test_migrate_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let applied = migrate(&mut conn).unwrap();
+
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert!(pending(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_newer_database_is_rejected() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+
+        assert!(pending(&conn).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::{db::Database, task::Task};
+
+/// How much work fits in a day, measured in difficulty points.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlanConfig {
+    /// The number of difficulty points that can be planned for a day.
+    pub budget: u32,
+    /// The difficulty assumed for tasks that do not have one.
+    pub unrated: u32,
+}
+
+impl Default for PlanConfig {
+    fn default() -> Self {
+        Self {
+            budget: 15,
+            unrated: 3,
+        }
+    }
+}
+
+impl PlanConfig {
+    /// The number of points a task takes up in a plan.
+    pub fn cost(&self, task: &Task) -> u32 {
+        task.difficulty
+            .map_or(self.unrated, |d| u32::from(u8::from(d)))
+    }
+}
+
+impl Database {
+    /// The tasks planned for a day, in the order they were planned. Removed tasks are left
+    /// out.
+    pub fn plan(&self, date: NaiveDate) -> Result<Vec<Task>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT p.task_id FROM plans p JOIN tasks t ON t.id = p.task_id
+             WHERE p.date = ?1 AND t.deleted IS NULL
+             ORDER BY p.position",
+        )?;
+        let ids = stmt
+            .query_map([date.to_string()], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+
+        ids.iter().map(|id| self.get_task(id)).collect()
+    }
+
+    /// Replace the plan for a day with the given tasks, in order.
+    pub fn save_plan(&mut self, date: NaiveDate, tasks: &[Task]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        self.conn
+            .execute("DELETE FROM plans WHERE date = ?1", [date.to_string()])?;
+        for (position, task) in (0_i64..).zip(tasks) {
+            self.conn.execute(
+                "INSERT INTO plans (date, task_id, position) VALUES (?1, ?2, ?3)",
+                (date.to_string(), &task.id, position),
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cost() {
+        let config = PlanConfig::default();
+        let rated = Task::new("rated".to_string(), None, Some(8), None, None, None).unwrap();
+        let unrated = Task::new("unrated".to_string(), None, None, None, None, None).unwrap();
+
+        assert_eq!(config.cost(&rated), 8);
+        assert_eq!(config.cost(&unrated), 3);
+    }
+}
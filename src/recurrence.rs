@@ -0,0 +1,318 @@
+use anyhow::{Result, anyhow};
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+use rusqlite::{
+    ToSql,
+    types::{FromSql, FromSqlError, FromSqlResult, ValueRef},
+};
+use std::fmt::Display;
+
+use crate::deadline::{Span, days_in_month, parse_weekday};
+
+/// How often a recurring task repeats. Completing an instance of a recurring task creates the
+/// next instance, due on the next date given by the rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    /// Every day.
+    Daily,
+    /// Every week on the given weekdays, or on the weekday of the deadline if there are none.
+    Weekly(Vec<Weekday>),
+    /// Every month on the given day. Days past the end of a month fall on its last day. A rule
+    /// without a day is fixed to the day of the task's deadline when the task is saved.
+    Monthly(Option<u32>),
+    /// Every given span after the previous deadline.
+    Interval(Span),
+    /// The given span after the previous instance is completed.
+    AfterCompletion(Span),
+}
+
+const WEEKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+fn parse_weekdays(input: &str) -> Option<Vec<Weekday>> {
+    let mut days: Vec<Weekday> = input
+        .split(',')
+        .map(|d| parse_weekday(d.trim()))
+        .collect::<Option<_>>()?;
+    days.sort_by_key(Weekday::num_days_from_monday);
+    days.dedup();
+    Some(days)
+}
+
+fn parse_day_of_month(input: &str) -> Option<u32> {
+    let input = input.trim().trim_start_matches("the ").trim();
+    let day = input
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+        .parse()
+        .ok()?;
+    (1..=31).contains(&day).then_some(day)
+}
+
+impl Recurrence {
+    /// Parse a recurrence rule. Rules use the same vocabulary as deadlines:
+    ///
+    /// * `daily`, `weekdays`, `weekly`, `monthly`
+    /// * `weekly on mon,thu`, `every friday`
+    /// * `monthly on 15`, `monthly on the 1st`
+    /// * `every 3d`, `every 2w`
+    /// * `5d after completion`, `after 2w`
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim().to_lowercase();
+        let invalid = || {
+            anyhow!(
+                "Invalid recurrence rule '{input}'. Use: daily, weekdays, weekly, weekly on mon,thu, monthly, monthly on 15, every 3d, 5d after completion, etc. See `todo add --help` for more information."
+            )
+        };
+
+        let recurrence = match input.as_str() {
+            "daily" | "every day" => Recurrence::Daily,
+            "weekdays" | "every weekday" => Recurrence::Weekly(WEEKDAYS.to_vec()),
+            "weekly" | "every week" => Recurrence::Weekly(Vec::new()),
+            "monthly" | "every month" => Recurrence::Monthly(None),
+            _ => {
+                if let Some(days) = input.strip_prefix("weekly on ") {
+                    Recurrence::Weekly(parse_weekdays(days).ok_or_else(invalid)?)
+                } else if let Some(day) = input
+                    .strip_prefix("monthly on ")
+                    .or_else(|| input.strip_prefix("every month on "))
+                {
+                    Recurrence::Monthly(Some(parse_day_of_month(day).ok_or_else(invalid)?))
+                } else if let Some(duration) = input
+                    .strip_suffix(" after completion")
+                    .map(|d| d.trim_start_matches("every "))
+                    .or_else(|| input.strip_prefix("after "))
+                {
+                    Recurrence::AfterCompletion(
+                        Span::parse(duration)
+                            .filter(|s| s.is_positive())
+                            .ok_or_else(invalid)?,
+                    )
+                } else if let Some(every) = input.strip_prefix("every ") {
+                    if let Some(days) = parse_weekdays(every) {
+                        Recurrence::Weekly(days)
+                    } else {
+                        Recurrence::Interval(
+                            Span::parse(every)
+                                .filter(|s| s.is_positive())
+                                .ok_or_else(invalid)?,
+                        )
+                    }
+                } else {
+                    return Err(invalid());
+                }
+            }
+        };
+
+        Ok(recurrence)
+    }
+
+    /// The first occurrence strictly after `date`.
+    fn after(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Recurrence::Daily => date + Duration::days(1),
+            Recurrence::Weekly(days) if days.is_empty() => date + Duration::weeks(1),
+            Recurrence::Weekly(days) => (1..=7)
+                .map(|n| date + Duration::days(n))
+                .find(|d| days.contains(&d.weekday()))
+                .unwrap_or(date + Duration::weeks(1)),
+            Recurrence::Monthly(day) => {
+                let day = day.unwrap_or(date.day());
+                let on = |month: NaiveDate| {
+                    month
+                        .with_day(day.min(days_in_month(month.year(), month.month())))
+                        .unwrap_or(month)
+                };
+                let this_month = date.with_day(1).unwrap_or(date);
+                match on(this_month) {
+                    next if next > date => next,
+                    _ => on(this_month + Months::new(1)),
+                }
+            }
+            Recurrence::Interval(span) | Recurrence::AfterCompletion(span) => {
+                span.after(date).unwrap_or(NaiveDate::MAX)
+            }
+        }
+    }
+
+    /// The rule with a monthly rule fixed to the day of `deadline`, so that it keeps to that
+    /// day after shorter months.
+    pub fn anchored(self, deadline: NaiveDate) -> Self {
+        match self {
+            Recurrence::Monthly(None) => Recurrence::Monthly(Some(deadline.day())),
+            rule => rule,
+        }
+    }
+
+    /// The deadline of the first instance of a recurring task created `today` without a
+    /// deadline.
+    pub fn first(&self, today: NaiveDate) -> NaiveDate {
+        match self {
+            Recurrence::Interval(_) | Recurrence::AfterCompletion(_) => self.after(today),
+            _ => self.after(today - Duration::days(1)),
+        }
+    }
+
+    /// The deadline of the instance that follows one due on `deadline` and completed `today`.
+    /// Occurrences that have already passed are skipped, so completing an overdue task does
+    /// not create another overdue task.
+    pub fn next(&self, deadline: Option<NaiveDate>, today: NaiveDate) -> NaiveDate {
+        if let Recurrence::AfterCompletion(_) = self {
+            return self.after(today);
+        }
+
+        let mut next = self.after(deadline.unwrap_or(today));
+        while next < today {
+            next = self.after(next);
+        }
+        next
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly(days) if days.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly(days) if days == &WEEKDAYS => write!(f, "weekdays"),
+            Recurrence::Weekly(days) => {
+                let days: Vec<String> = days.iter().map(|d| d.to_string().to_lowercase()).collect();
+                write!(f, "weekly on {}", days.join(","))
+            }
+            Recurrence::Monthly(None) => write!(f, "monthly"),
+            Recurrence::Monthly(Some(day)) => write!(f, "monthly on {day}"),
+            Recurrence::Interval(span) => write!(f, "every {span}"),
+            Recurrence::AfterCompletion(span) => write!(f, "{span} after completion"),
+        }
+    }
+}
+
+impl ToSql for Recurrence {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.to_string().into())
+    }
+}
+
+impl FromSql for Recurrence {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        Recurrence::parse(value.as_str()?).map_err(|e| FromSqlError::Other(e.into()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for rule in [
+            "daily",
+            "weekdays",
+            "weekly",
+            "weekly on mon,thu",
+            "monthly",
+            "monthly on 15",
+            "every 3d",
+            "every 1m",
+            "5d after completion",
+            "2bd after completion",
+        ] {
+            assert_eq!(Recurrence::parse(rule).unwrap().to_string(), rule);
+        }
+
+        assert_eq!(
+            Recurrence::parse("every friday").unwrap(),
+            Recurrence::Weekly(vec![Weekday::Fri])
+        );
+        assert_eq!(
+            Recurrence::parse("Monthly on the 1st").unwrap(),
+            Recurrence::Monthly(Some(1))
+        );
+        assert_eq!(
+            Recurrence::parse("every 2 weeks").unwrap(),
+            Recurrence::parse("every 2w").unwrap()
+        );
+        assert_eq!(
+            Recurrence::parse("after 2 weeks").unwrap().to_string(),
+            "2w after completion"
+        );
+
+        for rule in ["sometimes", "monthly on 32", "every 0d", "weekly on funday"] {
+            assert!(Recurrence::parse(rule).is_err());
+        }
+    }
+
+    #[test]
+    fn test_next() {
+        let today = date("2026-01-28");
+
+        assert_eq!(
+            Recurrence::Daily.next(Some(date("2026-01-28")), today),
+            date("2026-01-29")
+        );
+        // Overdue occurrences are skipped.
+        assert_eq!(
+            Recurrence::Daily.next(Some(date("2026-01-20")), today),
+            today
+        );
+        assert_eq!(
+            Recurrence::parse("weekly on mon,thu")
+                .unwrap()
+                .next(Some(date("2026-01-26")), today),
+            date("2026-01-29")
+        );
+        assert_eq!(
+            Recurrence::Monthly(Some(31)).next(Some(date("2026-01-31")), today),
+            date("2026-02-28")
+        );
+        assert_eq!(
+            Recurrence::Monthly(Some(31)).next(Some(date("2026-02-28")), today),
+            date("2026-03-31")
+        );
+        assert_eq!(
+            Recurrence::Monthly(Some(15)).next(Some(date("2026-01-10")), date("2026-01-01")),
+            date("2026-01-15")
+        );
+        assert_eq!(
+            Recurrence::Monthly(None).next(Some(date("2026-02-15")), today),
+            date("2026-03-15")
+        );
+        assert_eq!(
+            Recurrence::Monthly(None).anchored(date("2026-01-31")),
+            Recurrence::Monthly(Some(31))
+        );
+        assert_eq!(
+            Recurrence::parse("3d after completion")
+                .unwrap()
+                .next(Some(date("2026-01-01")), today),
+            date("2026-01-31")
+        );
+        // Months follow the calendar rather than being 30 days long.
+        assert_eq!(
+            Recurrence::parse("every 1m")
+                .unwrap()
+                .next(Some(date("2026-01-31")), today),
+            date("2026-02-28")
+        );
+        assert_eq!(
+            Recurrence::parse("every month")
+                .unwrap()
+                .anchored(date("2026-01-31"))
+                .next(Some(date("2026-02-28")), today),
+            date("2026-03-31")
+        );
+        assert_eq!(
+            Recurrence::parse("weekdays")
+                .unwrap()
+                .first(date("2026-01-31")),
+            date("2026-02-02")
+        );
+    }
+}
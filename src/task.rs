@@ -1,13 +1,19 @@
-use crate::deadline::Deadline;
+use crate::deadline::{DateFormat, Deadline};
+use crate::recurrence::Recurrence;
+use crate::tracking::format_duration;
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, Local};
+use clap::ValueEnum;
 use colored::Colorize;
 use dialoguer::{Input, theme::ColorfulTheme};
 use rusqlite::ToSql;
-use rusqlite::types::FromSql;
+use rusqlite::types::{FromSql, Type};
 use sha1::{Digest, Sha1};
 use std::borrow::Cow;
-use std::{fmt::Display, time::SystemTime};
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime},
+};
 use tabled::Tabled;
 
 #[derive(Debug, Clone)]
@@ -59,6 +65,14 @@ impl ID {
         }
     }
 
+    /// A stable ID for a task that originates outside of todo, such as a calendar entry, so
+    /// that importing it again updates the same task.
+    pub fn from_external(key: &str) -> Self {
+        Self {
+            value: generate_hash(key),
+        }
+    }
+
     pub fn short(&self) -> String {
         self.value[0..7].to_string()
     }
@@ -78,7 +92,7 @@ pub struct Difficulty {
 }
 
 impl Difficulty {
-    fn new(value: u8) -> Result<Self> {
+    pub fn new(value: u8) -> Result<Self> {
         if (0..=10).contains(&value) {
             Ok(Self { value })
         } else {
@@ -88,7 +102,7 @@ impl Difficulty {
         }
     }
 
-    fn colour(&self) -> String {
+    pub(crate) fn colour(&self) -> String {
         let val = self.value;
         let s = val.to_string();
         match val {
@@ -125,6 +139,66 @@ impl From<Difficulty> for u8 {
     }
 }
 
+/// Where a task is in its workflow.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Waiting,
+    Done,
+}
+
+impl Status {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in-progress",
+            Status::Waiting => "waiting",
+            Status::Done => "done",
+        }
+    }
+
+    /// The command that moves a task to this status.
+    pub fn verb(self) -> &'static str {
+        match self {
+            Status::Todo => "stop",
+            Status::InProgress => "start",
+            Status::Waiting => "wait",
+            Status::Done => "complete",
+        }
+    }
+
+    pub(crate) fn colour(self) -> String {
+        let s = self.as_str();
+        match self {
+            Status::Todo => s.to_string(),
+            Status::InProgress => s.yellow().to_string(),
+            Status::Waiting => s.cyan().to_string(),
+            Status::Done => s.green().to_string(),
+        }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl ToSql for Status {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for Status {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        Status::from_str(value.as_str()?, true)
+            .map_err(|e| rusqlite::types::FromSqlError::Other(e.into()))
+    }
+}
+
 #[derive(Debug)]
 pub struct Task {
     pub id: ID,
@@ -136,6 +210,26 @@ pub struct Task {
     pub pid: Option<ID>,
     pub created: SystemTime,
     pub completed: Option<SystemTime>,
+    pub deleted: Option<SystemTime>,
+    pub recurrence: Option<Recurrence>,
+    /// The first task of the recurring series this task belongs to.
+    pub series: Option<ID>,
+    /// The tasks that must be completed before this task can be started.
+    pub blocked_by: Option<Vec<ID>>,
+    /// Whether any of the tasks this task is blocked by are incomplete.
+    pub blocked: bool,
+    /// Whether this task blocks any incomplete tasks.
+    pub blocking: bool,
+    /// The workflow status of the task, which only takes effect while it is incomplete.
+    pub status: Status,
+    /// The date a waiting task becomes workable again.
+    pub waiting_until: Option<Deadline>,
+    /// The date the task is deferred until, before which it is hidden.
+    pub scheduled: Option<Deadline>,
+    /// The total time tracked against the task.
+    pub tracked: Duration,
+    /// How urgent the task is, which decides the order tasks are listed in.
+    pub urgency: f64,
 }
 
 impl Display for Task {
@@ -155,6 +249,13 @@ impl Display for Task {
             writeln!(f, "  Description: {}", desc)?;
         }
 
+        match (self.current_status(), &self.waiting_until) {
+            (Status::Waiting, Some(until)) => {
+                writeln!(f, "  Status: {} until {}", Status::Waiting.colour(), until)?
+            }
+            (status, _) => writeln!(f, "  Status: {}", status.colour())?,
+        }
+
         if let Some(diff) = &self.difficulty {
             writeln!(f, "  Difficulty: {}", diff)?;
         }
@@ -163,24 +264,63 @@ impl Display for Task {
             writeln!(f, "  Deadline: {} ({})", deadline, deadline.days_until())?;
         }
 
+        if let Some(scheduled) = &self.scheduled {
+            writeln!(f, "  Scheduled: {} ({})", scheduled, scheduled.days_until())?;
+        }
+
+        if self.completed.is_none() {
+            writeln!(f, "  Urgency: {:.1}", self.urgency)?;
+        }
+
         if let Some(tags) = &self.tags
             && !tags.is_empty()
         {
             writeln!(f, "  Tags: {}", tags.join(", "))?;
         }
 
+        if let Some(recurrence) = &self.recurrence {
+            writeln!(f, "  Recurs: {}", recurrence)?;
+        }
+
         writeln!(f, "  ID: {}", self.id)?;
 
         if let Some(pid) = &self.pid {
             writeln!(f, "  Parent: {}", pid)?;
         }
 
+        if let Some(series) = &self.series {
+            writeln!(f, "  Series: {}", series)?;
+        }
+
+        if let Some(blocked_by) = &self.blocked_by
+            && !blocked_by.is_empty()
+        {
+            let ids: Vec<String> = blocked_by.iter().map(ID::short).collect();
+            writeln!(
+                f,
+                "  Blocked by: {}{}",
+                ids.join(", "),
+                if self.blocked { "" } else { " (done)" }
+            )?;
+        }
+
+        if !self.tracked.is_zero() {
+            writeln!(f, "  Tracked: {}", format_duration(self.tracked))?;
+        }
+
+        let time_format = format!("%H:%M:%S {}", DateFormat::current().pattern());
+
         let created: DateTime<Local> = self.created.into();
-        writeln!(f, "  Created: {}", created.format("%H:%M:%S %d-%m-%Y"))?;
+        writeln!(f, "  Created: {}", created.format(&time_format))?;
 
         if let Some(time) = self.completed {
             let completed: DateTime<Local> = time.into();
-            writeln!(f, "  Completed: {}", completed.format("%H:%M:%S %d-%m-%Y"))?;
+            writeln!(f, "  Completed: {}", completed.format(&time_format))?;
+        }
+
+        if let Some(time) = self.deleted {
+            let deleted: DateTime<Local> = time.into();
+            writeln!(f, "  Removed: {}", deleted.format(&time_format))?;
         }
 
         Ok(())
@@ -188,6 +328,19 @@ impl Display for Task {
 }
 
 impl Task {
+    /// The current status of the task. Completed tasks are done, and waiting tasks return to
+    /// todo once the date they are waiting until is reached.
+    pub fn current_status(&self) -> Status {
+        if self.completed.is_some() {
+            return Status::Done;
+        }
+
+        match (self.status, &self.waiting_until) {
+            (Status::Waiting, Some(until)) if until.is_reached() => Status::Todo,
+            (status, _) => status,
+        }
+    }
+
     pub fn new(
         title: String,
         desc: Option<String>,
@@ -218,6 +371,17 @@ impl Task {
             pid,
             created: SystemTime::now(),
             completed: None,
+            deleted: None,
+            recurrence: None,
+            series: None,
+            blocked_by: None,
+            blocked: false,
+            blocking: false,
+            status: Status::Todo,
+            waiting_until: None,
+            scheduled: None,
+            tracked: Duration::ZERO,
+            urgency: 0.0,
         };
 
         Ok(task)
@@ -259,15 +423,14 @@ impl Task {
         let deadline: Option<String> = Input::with_theme(&theme)
             .with_prompt("Deadline (today, tomorrow, +5d, YYYY-MM-DD, or empty)")
             .allow_empty(true)
-            .validate_with(|input: &String| -> Result<(), &str> {
+            .validate_with(|input: &String| -> Result<(), String> {
                 if input.is_empty() {
                     return Ok(());
                 }
 
-                match Deadline::parse(input) {
-                    Ok(_) => Ok(()),
-                    Err(_) => Err("Invalid deadline format. Use: today, tomorrow, monday, +5d, 2026-02-10, etc. See `todo add --help` for more information."),
-                }
+                Deadline::parse(input)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
             })
             .interact_text()
             .ok()
@@ -314,14 +477,13 @@ impl TryFrom<&rusqlite::Row<'_>> for Task {
             id: row.get::<_, String>(0)?.into(),
             title: row.get(1)?,
             desc: row.get(2)?,
-            difficulty: diff
-                .map(Difficulty::new)
-                .transpose()
-                .map_err(|e| rusqlite::Error::InvalidParameterName(e.to_string()))?,
+            difficulty: diff.map(Difficulty::new).transpose().map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(3, Type::Integer, e.into())
+            })?,
             deadline: deadline
                 .map(|d| Deadline::parse(&d))
                 .transpose()
-                .map_err(|e| rusqlite::Error::InvalidParameterName(e.to_string()))?,
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, Type::Text, e.into()))?,
             tags: None,
             pid: row.get::<_, Option<String>>(5)?.map(Into::into),
             created: DateTime::from_timestamp(created, 0)
@@ -332,20 +494,44 @@ impl TryFrom<&rusqlite::Row<'_>> for Task {
                     .expect("invalid timestamp")
                     .into()
             }),
+            deleted: row.get::<_, Option<i64>>(8)?.map(|t| {
+                DateTime::from_timestamp(t, 0)
+                    .expect("invalid timestamp")
+                    .into()
+            }),
+            recurrence: row.get(9)?,
+            series: row.get::<_, Option<String>>(10)?.map(Into::into),
+            blocked_by: None,
+            blocked: false,
+            blocking: false,
+            status: row.get(11)?,
+            waiting_until: row
+                .get::<_, Option<String>>(12)?
+                .map(|d| Deadline::parse(&d))
+                .transpose()
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(12, Type::Text, e.into()))?,
+            scheduled: row
+                .get::<_, Option<String>>(13)?
+                .map(|d| Deadline::parse(&d))
+                .transpose()
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(13, Type::Text, e.into()))?,
+            tracked: Duration::ZERO,
+            urgency: 0.0,
         })
     }
 }
 
 impl Tabled for Task {
-    const LENGTH: usize = 9;
+    const LENGTH: usize = 15;
 
     fn fields(&self) -> Vec<std::borrow::Cow<'_, str>> {
         let created: DateTime<Local> = self.created.into();
-        let created_str = created.format("%d-%m-%Y").to_string();
+        let date_format = DateFormat::current().pattern();
+        let created_str = created.format(date_format).to_string();
 
         let completed_str: String = if let Some(time) = self.completed {
             let time: DateTime<Local> = time.into();
-            time.format("%d-%m-%Y").to_string()
+            time.format(date_format).to_string()
         } else {
             "".to_string()
         };
@@ -367,6 +553,7 @@ impl Tabled for Task {
 
         vec![
             Cow::Owned(truncate_string(&self.title, 30)),
+            Cow::Owned(self.current_status().colour()),
             Cow::Owned(
                 self.desc
                     .as_deref()
@@ -375,6 +562,17 @@ impl Tabled for Task {
             ),
             Cow::Owned(difficulty),
             Cow::Owned(deadline),
+            Cow::Owned(
+                self.scheduled
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            ),
+            Cow::Owned(if self.completed.is_some() {
+                "".to_string()
+            } else {
+                format!("{:.1}", self.urgency)
+            }),
             Cow::Owned(truncate_string(
                 &self
                     .tags
@@ -385,6 +583,22 @@ impl Tabled for Task {
             )),
             Cow::Borrowed(&self.id.value[0..7]),
             Cow::Owned(pid),
+            Cow::Owned(if self.blocked {
+                "blocked".red().to_string()
+            } else {
+                "".to_string()
+            }),
+            Cow::Owned(
+                self.recurrence
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            ),
+            Cow::Owned(if self.tracked.is_zero() {
+                "".to_string()
+            } else {
+                format_duration(self.tracked)
+            }),
             Cow::Owned(created_str),
             Cow::Owned(completed_str),
         ]
@@ -393,12 +607,18 @@ impl Tabled for Task {
     fn headers() -> Vec<std::borrow::Cow<'static, str>> {
         vec![
             Cow::Borrowed("Task"),
+            Cow::Borrowed("Status"),
             Cow::Borrowed("Description"),
             Cow::Borrowed("Difficulty"),
             Cow::Borrowed("Deadline"),
+            Cow::Borrowed("Scheduled"),
+            Cow::Borrowed("Urgency"),
             Cow::Borrowed("Tags"),
             Cow::Borrowed("ID"),
             Cow::Borrowed("Parent"),
+            Cow::Borrowed("Blocked"),
+            Cow::Borrowed("Recurrence"),
+            Cow::Borrowed("Tracked"),
             Cow::Borrowed("Created"),
             Cow::Borrowed("Complete"),
         ]
@@ -415,6 +635,24 @@ mod test {
         assert!(Difficulty::new(11).is_err())
     }
 
+    #[test]
+    fn test_current_status() {
+        let mut task = Task::new("test".to_string(), None, None, None, None, None).unwrap();
+        assert_eq!(task.current_status(), Status::Todo);
+
+        task.status = Status::Waiting;
+        assert_eq!(task.current_status(), Status::Waiting);
+
+        task.waiting_until = Some(Deadline::parse("2000-01-01").unwrap());
+        assert_eq!(task.current_status(), Status::Todo);
+
+        task.waiting_until = Some(Deadline::parse("+5d").unwrap());
+        assert_eq!(task.current_status(), Status::Waiting);
+
+        task.completed = Some(SystemTime::now());
+        assert_eq!(task.current_status(), Status::Done);
+    }
+
     #[test]
     fn test_task_creation_with_valid_date() {
         let result = Task::new(
@@ -429,18 +667,52 @@ mod test {
     }
 
     #[test]
-    fn test_task_creation_with_invalid_date() {
-        let result = Task::new(
+    fn test_task_creation_with_day_first_dash_date() {
+        // Only one reading of the date is valid, so the ISO format reads it day first.
+        let task = Task::new(
             "test".to_string(),
             Some("test".to_string()),
             Some(4),
             Some("23-01-2026".to_string()),
             None,
             None,
+        )
+        .unwrap();
+        assert_eq!(
+            task.deadline.unwrap().date(),
+            chrono::NaiveDate::from_ymd_opt(2026, 1, 23).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_task_creation_with_invalid_month() {
+        let result = Task::new(
+            "test".to_string(),
+            Some("test".to_string()),
+            Some(4),
+            Some("23-13-2026".to_string()),
+            None,
+            None,
+        );
+        assert!(
+            result.is_err(),
+            "Task creation should fail with an invalid month"
+        );
+    }
+
+    #[test]
+    fn test_task_creation_with_ambiguous_date() {
+        let result = Task::new(
+            "test".to_string(),
+            None,
+            None,
+            Some("02/10/2026".to_string()),
+            None,
+            None,
         );
         assert!(
             result.is_err(),
-            "Task creation should fail with an invalid date"
+            "Task creation should fail with an ambiguous date"
         );
     }
 
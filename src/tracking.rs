@@ -0,0 +1,234 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Days};
+use rusqlite::{OptionalExtension, params, params_from_iter, types::Value};
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    db::{Database, timestamp_now},
+    deadline::Deadline,
+    history::day_start,
+    task::ID,
+};
+
+/// A period of time spent working on a task. The timer of an entry that has not been stopped
+/// is still running.
+#[derive(Debug)]
+pub struct TimeEntry {
+    pub task_id: ID,
+    pub title: String,
+    pub started: SystemTime,
+    pub stopped: Option<SystemTime>,
+}
+
+impl TimeEntry {
+    /// The time spent on the entry, up to now if its timer is still running.
+    pub fn duration(&self) -> Duration {
+        self.stopped
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(self.started)
+            .unwrap_or_default()
+    }
+}
+
+impl TryFrom<&rusqlite::Row<'_>> for TimeEntry {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &rusqlite::Row<'_>) -> Result<Self, rusqlite::Error> {
+        let started: i64 = row.get(2)?;
+
+        Ok(Self {
+            task_id: row.get::<_, String>(0)?.into(),
+            title: row.get(1)?,
+            started: DateTime::from_timestamp(started, 0)
+                .expect("invalid timestamp")
+                .into(),
+            stopped: row.get::<_, Option<i64>>(3)?.map(|t| {
+                DateTime::from_timestamp(t, 0)
+                    .expect("invalid timestamp")
+                    .into()
+            }),
+        })
+    }
+}
+
+/// The time tracked within a date range, totalled per task and per tag.
+#[derive(Debug, Default)]
+pub struct TimeReport {
+    /// The ID, title and tracked time of each task, most time first.
+    pub tasks: Vec<(ID, String, Duration)>,
+    /// The time tracked against tasks with each tag, most time first.
+    pub tags: Vec<(String, Duration)>,
+    pub total: Duration,
+}
+
+/// Format a duration in hours and minutes, e.g. `2h 05m` or `40m`.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}m"),
+        (h, m) => format!("{h}h {m:02}m"),
+    }
+}
+
+fn seconds(secs: i64) -> Duration {
+    Duration::from_secs(secs.max(0).cast_unsigned())
+}
+
+impl Database {
+    /// The timer that is currently running, if any.
+    pub fn running_timer(&self) -> Result<Option<TimeEntry>> {
+        let entry = self
+            .conn
+            .query_row(
+                "SELECT e.task_id, t.title, e.started, e.stopped
+                 FROM time_entries e JOIN tasks t ON t.id = e.task_id
+                 WHERE e.stopped IS NULL",
+                [],
+                |row| TimeEntry::try_from(row),
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
+    /// Start a timer for the incomplete task matching `id`. Only one timer can run at a time.
+    pub fn start_timer(&mut self, id: &str) -> Result<TimeEntry> {
+        if let Some(running) = self.running_timer()? {
+            bail!(
+                "A timer is already running for {} \"{}\". Stop it with `todo track stop` first",
+                running.task_id.short(),
+                running.title
+            );
+        }
+
+        let task = self.get_task(&self.resolve_id(id)?)?;
+        if task.completed.is_some() {
+            bail!("Task '{id}' is completed. Reopen it with `todo incomplete {id}` first");
+        }
+
+        self.conn.execute(
+            "INSERT INTO time_entries (task_id, started) VALUES (?1, ?2)",
+            params![task.id, timestamp_now()],
+        )?;
+
+        self.running_timer()?.context("Unable to start the timer")
+    }
+
+    /// Stop the running timer, returning its finished time entry.
+    pub fn stop_timer(&mut self) -> Result<TimeEntry> {
+        let Some(running) = self.running_timer()? else {
+            bail!("No timer is running. Start one with `todo track start <ID>`");
+        };
+
+        let stopped = timestamp_now();
+        self.conn.execute(
+            "UPDATE time_entries SET stopped = ?1 WHERE stopped IS NULL",
+            [stopped],
+        )?;
+
+        Ok(TimeEntry {
+            stopped: DateTime::from_timestamp(stopped, 0).map(Into::into),
+            ..running
+        })
+    }
+
+    /// The total time tracked against a task, including its running timer.
+    pub(crate) fn tracked_time(&self, id: &str) -> Result<Duration> {
+        let secs: i64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(COALESCE(stopped, ?2) - started), 0)
+             FROM time_entries WHERE task_id = ?1",
+            params![id, timestamp_now()],
+            |row| row.get(0),
+        )?;
+        Ok(seconds(secs))
+    }
+
+    /// The time tracked between two dates inclusive, optionally limited to tasks with all of
+    /// the given tags. Entries that cross either end of the range only count the time within
+    /// it.
+    pub fn time_report(
+        &self,
+        after: Option<Deadline>,
+        before: Option<Deadline>,
+        tags: Option<Vec<String>>,
+    ) -> Result<TimeReport> {
+        let now = timestamp_now();
+        let start = after.map_or(0, |d| day_start(d.date()));
+        let end = before
+            .and_then(|d| d.date().checked_add_days(Days::new(1)))
+            .map_or(i64::MAX, day_start);
+
+        let mut query = String::from(
+            "SELECT t.id, t.title,
+                SUM(MAX(0, MIN(COALESCE(e.stopped, ?1), ?3) - MAX(e.started, ?2))) AS seconds
+             FROM time_entries e JOIN tasks t ON t.id = e.task_id
+             WHERE t.deleted IS NULL AND COALESCE(e.stopped, ?1) >= ?2 AND e.started < ?3",
+        );
+        let mut params = vec![
+            Value::Integer(now),
+            Value::Integer(start),
+            Value::Integer(end),
+        ];
+
+        if let Some(tags) = &tags
+            && !tags.is_empty()
+        {
+            let placeholders = tags.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+            query.push_str(&format!(
+                " AND t.id IN (
+                    SELECT tt.task_id FROM task_tags tt JOIN tags tg ON tg.id = tt.tag_id
+                    WHERE tg.name IN ({placeholders})
+                    GROUP BY tt.task_id HAVING COUNT(DISTINCT tg.name) = {})",
+                tags.len()
+            ));
+            params.extend(tags.iter().cloned().map(Value::Text));
+        }
+
+        query.push_str(" GROUP BY t.id ORDER BY seconds DESC, t.title ASC");
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let tasks = stmt
+            .query_map(params_from_iter(params.iter()), |row| {
+                Ok((
+                    ID::from(row.get::<_, String>(0)?),
+                    row.get::<_, String>(1)?,
+                    seconds(row.get(2)?),
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut tag_totals: HashMap<String, Duration> = HashMap::new();
+        for (id, _, duration) in &tasks {
+            for tag in self.get_tags(&id.to_string())? {
+                *tag_totals.entry(tag).or_default() += *duration;
+            }
+        }
+        let mut tags: Vec<(String, Duration)> = tag_totals.into_iter().collect();
+        tags.sort_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.cmp(b)));
+
+        Ok(TimeReport {
+            total: tasks.iter().map(|(_, _, d)| *d).sum(),
+            tasks,
+            tags,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "0m");
+        assert_eq!(format_duration(Duration::from_secs(59)), "0m");
+        assert_eq!(format_duration(Duration::from_secs(40 * 60)), "40m");
+        assert_eq!(
+            format_duration(Duration::from_secs(2 * 3600 + 5 * 60)),
+            "2h 05m"
+        );
+        assert_eq!(format_duration(Duration::from_secs(26 * 3600)), "26h 00m");
+    }
+}
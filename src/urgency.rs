@@ -0,0 +1,252 @@
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt::Display};
+use tabled::{
+    builder::Builder,
+    settings::{Style, formatting::AlignmentStrategy},
+};
+
+use crate::task::{Status, Task};
+
+/// The number of days ahead of a deadline at which it starts to become more urgent.
+const DEADLINE_HORIZON: i64 = 14;
+
+/// The number of days overdue at which a task is as overdue as it can be.
+const OVERDUE_HORIZON: i64 = 14;
+
+/// The age in days at which a task is as old as it can be.
+const AGE_HORIZON: i64 = 365;
+
+/// The weights of the factors that make up the urgency of a task. Each factor is scaled
+/// between 0 and 1 before it is weighted, so a coefficient is the most a factor can add.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Coefficients {
+    /// How close the deadline is, from 0.2 two weeks or more ahead to 1 once it is due.
+    pub deadline: f64,
+    /// How long the task has been overdue, up to two weeks.
+    pub overdue: f64,
+    /// The difficulty of the task out of 10.
+    pub difficulty: f64,
+    /// How long ago the task was created, up to a year.
+    pub age: f64,
+    /// Whether incomplete tasks are blocked by the task.
+    pub blocking: f64,
+    /// Whether the task is blocked by incomplete tasks.
+    pub blocked: f64,
+    /// Whether the task is in progress.
+    pub in_progress: f64,
+    /// The weights of tags, added for each tag a task has.
+    pub tags: BTreeMap<String, f64>,
+}
+
+impl Default for Coefficients {
+    fn default() -> Self {
+        Self {
+            deadline: 12.0,
+            overdue: 6.0,
+            difficulty: 5.0,
+            age: 2.0,
+            blocking: 8.0,
+            blocked: -5.0,
+            in_progress: 4.0,
+            tags: BTreeMap::new(),
+        }
+    }
+}
+
+/// A single weighted factor of the urgency of a task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub name: String,
+    pub value: f64,
+    pub coefficient: f64,
+}
+
+impl Term {
+    pub fn score(&self) -> f64 {
+        self.value * self.coefficient
+    }
+}
+
+/// The urgency of a task, broken down into the factors it is made up of.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Urgency {
+    pub terms: Vec<Term>,
+}
+
+impl Urgency {
+    pub fn of(task: &Task, coefficients: &Coefficients, today: NaiveDate) -> Self {
+        let days_until = task
+            .deadline
+            .as_ref()
+            .map(|d| (d.date() - today).num_days());
+
+        let deadline = match days_until {
+            None => 0.0,
+            Some(days) if days <= 0 => 1.0,
+            Some(days) if days >= DEADLINE_HORIZON => 0.2,
+            Some(days) => 1.0 - 0.8 * days as f64 / DEADLINE_HORIZON as f64,
+        };
+
+        let overdue = match days_until {
+            Some(days) if days < 0 => (-days).min(OVERDUE_HORIZON) as f64 / OVERDUE_HORIZON as f64,
+            _ => 0.0,
+        };
+
+        let created: DateTime<Local> = task.created.into();
+        let age = (today - created.date_naive())
+            .num_days()
+            .clamp(0, AGE_HORIZON) as f64
+            / AGE_HORIZON as f64;
+
+        let flag = |set: bool| if set { 1.0 } else { 0.0 };
+
+        let mut terms = vec![
+            Term {
+                name: "deadline".to_string(),
+                value: deadline,
+                coefficient: coefficients.deadline,
+            },
+            Term {
+                name: "overdue".to_string(),
+                value: overdue,
+                coefficient: coefficients.overdue,
+            },
+            Term {
+                name: "difficulty".to_string(),
+                value: task
+                    .difficulty
+                    .map_or(0.0, |d| f64::from(u8::from(d)) / 10.0),
+                coefficient: coefficients.difficulty,
+            },
+            Term {
+                name: "age".to_string(),
+                value: age,
+                coefficient: coefficients.age,
+            },
+            Term {
+                name: "blocking".to_string(),
+                value: flag(task.blocking),
+                coefficient: coefficients.blocking,
+            },
+            Term {
+                name: "blocked".to_string(),
+                value: flag(task.blocked),
+                coefficient: coefficients.blocked,
+            },
+            Term {
+                name: "in progress".to_string(),
+                value: flag(task.current_status() == Status::InProgress),
+                coefficient: coefficients.in_progress,
+            },
+        ];
+
+        for tag in task.tags.as_deref().unwrap_or_default() {
+            if let Some(weight) = coefficients.tags.get(tag) {
+                terms.push(Term {
+                    name: format!("tag {tag}"),
+                    value: 1.0,
+                    coefficient: *weight,
+                });
+            }
+        }
+
+        Self { terms }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.terms.iter().map(Term::score).sum()
+    }
+}
+
+impl Display for Urgency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut builder = Builder::default();
+        builder.push_record(["Factor", "Value", "Coefficient", "Urgency"]);
+
+        for term in self.terms.iter().filter(|t| t.score() != 0.0) {
+            builder.push_record([
+                term.name.clone(),
+                format!("{:.2}", term.value),
+                format!("{:.1}", term.coefficient),
+                format!("{:.2}", term.score()),
+            ]);
+        }
+        builder.push_record([
+            "Total".to_string(),
+            "".to_string(),
+            "".to_string(),
+            format!("{:.2}", self.total()),
+        ]);
+
+        let mut table = builder.build();
+        table.with(Style::modern()).with(AlignmentStrategy::PerLine);
+        write!(f, "{}", table)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deadline::Deadline;
+
+    fn task(deadline: Option<&str>, difficulty: Option<u8>) -> Task {
+        Task::new(
+            "test".to_string(),
+            None,
+            difficulty,
+            deadline.map(String::from),
+            Some(vec!["work".to_string()]),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_deadline_proximity() {
+        let today = Local::now().date_naive();
+        let coefficients = Coefficients::default();
+
+        let none = Urgency::of(&task(None, None), &coefficients, today).total();
+        let far = Urgency::of(&task(Some("+30d"), None), &coefficients, today).total();
+        let near = Urgency::of(&task(Some("+7d"), None), &coefficients, today).total();
+        let due = Urgency::of(&task(Some("today"), None), &coefficients, today).total();
+
+        assert_eq!(none, 0.0);
+        assert!((far - 2.4).abs() < 1e-9);
+        assert!(far < near && near < due);
+        assert!((due - 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_overdue() {
+        let today = Local::now().date_naive();
+        let mut overdue = task(None, None);
+        overdue.deadline = Some(Deadline::from(today - chrono::Duration::days(7)));
+
+        let urgency = Urgency::of(&overdue, &Coefficients::default(), today);
+        assert!((urgency.total() - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_difficulty_without_deadline_outranks_distant_deadline() {
+        let today = Local::now().date_naive();
+        let coefficients = Coefficients::default();
+
+        let hard = Urgency::of(&task(None, Some(10)), &coefficients, today).total();
+        let distant = Urgency::of(&task(Some("+30d"), None), &coefficients, today).total();
+        assert!(hard > distant);
+    }
+
+    #[test]
+    fn test_tag_weights() {
+        let today = Local::now().date_naive();
+        let mut coefficients = Coefficients::default();
+        coefficients.tags.insert("work".to_string(), 3.0);
+
+        let urgency = Urgency::of(&task(None, None), &coefficients, today);
+        assert!(urgency.terms.iter().any(|t| t.name == "tag work"));
+        assert!((urgency.total() - 3.0).abs() < 1e-9);
+    }
+}
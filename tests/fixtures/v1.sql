@@ -0,0 +1,38 @@
+-- Schema version 1: the original schema placed under version control.
+CREATE TABLE tasks (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT,
+    difficulty INTEGER,
+    deadline INTEGER,
+    parent_id TEXT,
+    created INTEGER NOT NULL,
+    completed INTEGER,
+    FOREIGN KEY (parent_id) REFERENCES tasks(id) ON DELETE CASCADE
+);
+
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL
+);
+
+CREATE TABLE task_tags (
+    task_id TEXT NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (task_id, tag_id),
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_tasks_parent ON tasks(parent_id);
+CREATE INDEX idx_task_tags_task ON task_tags(task_id);
+CREATE INDEX idx_task_tags_tag ON task_tags(tag_id);
+
+INSERT INTO tasks VALUES ('1a2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5', 'Fixture parent', 'A legacy task', 4, '2026-12-31', NULL, 1767225600, NULL);
+INSERT INTO tasks VALUES ('2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6', 'Fixture child', NULL, NULL, NULL, '1a2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5', 1767225600, NULL);
+INSERT INTO tasks VALUES ('3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6e7', 'Fixture done', NULL, 2, NULL, NULL, 1767225600, 1767312000);
+
+INSERT INTO tags VALUES (1, 'fixture');
+INSERT INTO task_tags VALUES ('1a2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5', 1);
+
+PRAGMA user_version = 1;
@@ -0,0 +1,176 @@
+-- Schema version 13: stores dates with a time of day in UTC again.
+CREATE TABLE tasks (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT,
+    difficulty INTEGER,
+    deadline INTEGER,
+    parent_id TEXT,
+    created INTEGER NOT NULL,
+    completed INTEGER,
+    deleted INTEGER,
+    recurrence TEXT,
+    series TEXT,
+    status TEXT NOT NULL DEFAULT 'todo',
+    waiting_until TEXT,
+    scheduled TEXT,
+    FOREIGN KEY (parent_id) REFERENCES tasks(id) ON DELETE CASCADE
+);
+
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL
+);
+
+CREATE TABLE task_tags (
+    task_id TEXT NOT NULL,
+    tag_id INTEGER NOT NULL,
+    PRIMARY KEY (task_id, tag_id),
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_tasks_parent ON tasks(parent_id);
+CREATE INDEX idx_tasks_deleted ON tasks(deleted);
+CREATE INDEX idx_tasks_series ON tasks(series);
+CREATE INDEX idx_tasks_status ON tasks(status);
+CREATE INDEX idx_tasks_scheduled ON tasks(scheduled);
+CREATE INDEX idx_task_tags_task ON task_tags(task_id);
+CREATE INDEX idx_task_tags_tag ON task_tags(tag_id);
+
+INSERT INTO tasks VALUES ('1a2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5', 'Fixture parent', 'A legacy task', 4, '2026-12-31', NULL, 1767225600, NULL, NULL, NULL, NULL, 'todo', NULL, NULL);
+INSERT INTO tasks VALUES ('2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6', 'Fixture child', NULL, NULL, NULL, '1a2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5', 1767225600, NULL, NULL, NULL, NULL, 'in-progress', NULL, '2026-01-05T09:00:00Z');
+INSERT INTO tasks VALUES ('3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6e7', 'Fixture done', NULL, 2, NULL, NULL, 1767225600, 1767312000, NULL, NULL, NULL, 'todo', NULL, NULL);
+
+INSERT INTO tags VALUES (1, 'fixture');
+INSERT INTO task_tags VALUES ('1a2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5', 1);
+
+CREATE TABLE depends_on (
+    task_id TEXT NOT NULL,
+    blocker_id TEXT NOT NULL,
+    PRIMARY KEY (task_id, blocker_id),
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+    FOREIGN KEY (blocker_id) REFERENCES tasks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_depends_on_blocker ON depends_on(blocker_id);
+
+INSERT INTO depends_on VALUES ('1a2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5', '3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6e7');
+
+CREATE TABLE time_entries (
+    id INTEGER PRIMARY KEY,
+    task_id TEXT NOT NULL,
+    started INTEGER NOT NULL,
+    stopped INTEGER,
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_time_entries_task ON time_entries(task_id);
+CREATE INDEX idx_time_entries_started ON time_entries(started);
+CREATE UNIQUE INDEX idx_time_entries_running ON time_entries((stopped IS NULL))
+    WHERE stopped IS NULL;
+
+INSERT INTO time_entries VALUES (1, '2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6', 1767229200, 1767234600);
+
+CREATE TABLE plans (
+    date TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (date, task_id),
+    FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_plans_task ON plans(task_id);
+
+INSERT INTO plans VALUES ('2026-01-01', '2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6', 0);
+
+CREATE TABLE journal (
+    id INTEGER PRIMARY KEY,
+    operation TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    undone INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE journal_tasks (
+    journal_id INTEGER NOT NULL,
+    state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+    id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    difficulty INTEGER,
+    deadline INTEGER,
+    parent_id TEXT,
+    created INTEGER NOT NULL,
+    completed INTEGER,
+    deleted INTEGER,
+    recurrence TEXT,
+    series TEXT,
+    status TEXT NOT NULL DEFAULT 'todo',
+    waiting_until TEXT,
+    scheduled TEXT,
+    PRIMARY KEY (journal_id, state, id),
+    FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+);
+
+CREATE TABLE journal_tags (
+    journal_id INTEGER NOT NULL,
+    state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+    task_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_journal_tasks_journal ON journal_tasks(journal_id);
+CREATE TABLE journal_depends (
+    journal_id INTEGER NOT NULL,
+    state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+    task_id TEXT NOT NULL,
+    blocker_id TEXT NOT NULL,
+    FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_journal_tags_journal ON journal_tags(journal_id);
+CREATE INDEX idx_journal_depends_journal ON journal_depends(journal_id);
+
+CREATE TABLE journal_time_entries (
+    journal_id INTEGER NOT NULL,
+    state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+    task_id TEXT NOT NULL,
+    started INTEGER NOT NULL,
+    stopped INTEGER,
+    FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+);
+
+CREATE TABLE journal_plans (
+    journal_id INTEGER NOT NULL,
+    state TEXT NOT NULL CHECK (state IN ('before', 'after')),
+    date TEXT NOT NULL,
+    task_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    FOREIGN KEY (journal_id) REFERENCES journal(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_journal_time_entries_journal ON journal_time_entries(journal_id);
+CREATE INDEX idx_journal_plans_journal ON journal_plans(journal_id);
+
+INSERT INTO journal VALUES (1, 'complete 3c4d5e6', 1767312000, 0);
+INSERT INTO journal_tasks VALUES (1, 'before', '3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6e7', 'Fixture done', NULL, 2, NULL, NULL, 1767225600, NULL, NULL, NULL, NULL, 'todo', NULL, NULL);
+INSERT INTO journal_tasks VALUES (1, 'after', '3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6e7', 'Fixture done', NULL, 2, NULL, NULL, 1767225600, 1767312000, NULL, NULL, NULL, 'todo', NULL, NULL);
+
+CREATE TABLE history (
+    id INTEGER PRIMARY KEY,
+    task_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    field TEXT,
+    old_value TEXT,
+    new_value TEXT
+);
+
+CREATE INDEX idx_history_task ON history(task_id);
+CREATE INDEX idx_history_timestamp ON history(timestamp);
+
+INSERT INTO history VALUES (1, '3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6e7', 'Fixture done', 1767312000, 'completed', NULL, NULL, NULL);
+
+PRAGMA user_version = 13;
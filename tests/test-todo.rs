@@ -1,6 +1,12 @@
 use assert_cmd::{cargo, Command};
 use predicates::prelude::*;
+use rusqlite::Connection;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 use tempfile::TempDir;
+use todo_cli::migrations;
 
 /// Helper to create a new command with a temporary database
 fn todo_cmd(temp_dir: &TempDir) -> Command {
@@ -289,697 +295,3733 @@ fn test_list_deadline_range() {
 }
 
 #[test]
-fn test_list_all() {
+fn test_deadline_with_time_of_day() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task"]);
+    let id = add_task(&temp_dir, &["Standup", "--deadline", "tomorrow 9am"]);
+    add_task(&temp_dir, &["Later", "--deadline", "+30d"]);
+
     todo_cmd(&temp_dir)
-        .args(["complete", &id])
+        .args(["show", &id])
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::is_match(r"Deadline: \d{4}-\d{2}-\d{2} 09:00").unwrap());
 
-    // Without flag, shouldn't show completed
-    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--before", "tomorrow"])
+        .output()
+        .unwrap();
     let stdout = String::from_utf8(output.stdout).unwrap();
-    assert!(!stdout.contains("Task") || stdout.contains("No tasks"));
+    assert!(stdout.contains("Standup"));
+    assert!(!stdout.contains("Later"));
 
-    // With flag, should show completed
     todo_cmd(&temp_dir)
-        .args(["list", "--all"])
+        .args(["export", "--format", "json"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Task"));
+        .stdout(
+            predicate::str::is_match(r#""deadline": "\d{4}-\d{2}-\d{2}T\d{2}:00:00Z""#).unwrap(),
+        );
 }
 
 #[test]
-fn test_list_only_completed() {
+fn test_deadline_in_timezone() {
     let temp_dir = TempDir::new().unwrap();
 
-    add_task(&temp_dir, &["Incomplete"]);
-    let id = add_task(&temp_dir, &["Complete"]);
+    add_task(
+        &temp_dir,
+        &["Call", "--deadline", "2099-01-01 09:00 +02:00"],
+    );
+
     todo_cmd(&temp_dir)
-        .args(["complete", &id])
+        .args(["export", "--format", "todotxt"])
         .assert()
-        .success();
-
-    let output = todo_cmd(&temp_dir)
-        .args(["list", "--completed"])
-        .output()
-        .unwrap();
+        .success()
+        .stdout(predicate::str::contains("due:2099-01-01T07:00:00Z"));
 
-    let stdout = String::from_utf8(output.stdout).unwrap();
-    assert!(stdout.contains("Complete"));
-    assert!(!stdout.contains("Incomplete"));
+    todo_cmd(&temp_dir)
+        .args(["add", "Bad", "--deadline", "friday 25:00"])
+        .assert()
+        .failure();
 }
 
 #[test]
-fn test_list_alias() {
+fn test_deadline_time_uses_local_date() {
     let temp_dir = TempDir::new().unwrap();
 
-    todo_cmd(&temp_dir).arg("ls").assert().success();
-}
-
-// ============================================================================
-// TAGS COMMAND TESTS
-// ============================================================================
-
-#[test]
-fn test_tags() {
-    let temp_dir = TempDir::new().unwrap();
+    for (title, deadline) in [("Evening", "2099-10-20 10pm"), ("Next day", "2099-10-21")] {
+        todo_cmd(&temp_dir)
+            .env("TZ", "America/New_York")
+            .args(["add", title, "--deadline", deadline])
+            .assert()
+            .success();
+    }
 
-    add_task(&temp_dir, &["Test task", "--tags", "work"]);
+    let output = todo_cmd(&temp_dir)
+        .env("TZ", "America/New_York")
+        .args(["list", "--before", "2099-10-20"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Evening"));
+    assert!(!stdout.contains("Next day"));
 
     todo_cmd(&temp_dir)
-        .args(["tags"])
+        .args(["export", "--format", "todotxt"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("work"));
+        .stdout(predicate::str::contains("due:2099-10-21T02:00:00Z"));
 }
 
-// ============================================================================
-// SHOW COMMAND TESTS
-// ============================================================================
-
 #[test]
-fn test_show_task() {
+fn test_deadline_hours_remaining() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(
-        &temp_dir,
-        &[
-            "Test task",
-            "--desc",
-            "Description",
-            "--diff",
-            "5",
-            "--tags",
-            "work",
-        ],
-    );
+    let soon = chrono::Utc::now() + chrono::Duration::minutes(150);
+    let id = add_task(&temp_dir, &["Soon", "--deadline", &soon.to_rfc3339()]);
 
     todo_cmd(&temp_dir)
         .args(["show", &id])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Test task"))
-        .stdout(predicate::str::contains("Description"))
-        .stdout(predicate::str::contains("5"))
-        .stdout(predicate::str::contains("work"));
+        .stdout(predicate::str::contains("in 2 hours"));
 }
 
 #[test]
-fn test_show_nonexistent_task() {
+fn test_wait_until_time_has_passed() {
     let temp_dir = TempDir::new().unwrap();
 
+    let id = add_task(&temp_dir, &["Reply"]);
+    let past = chrono::Utc::now() - chrono::Duration::minutes(5);
+
     todo_cmd(&temp_dir)
-        .args(["show", "nonexistent"])
+        .args(["wait", &id, "--until", &past.to_rfc3339()])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("No task found"));
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--status", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reply"));
 }
 
 #[test]
-fn test_show_partial_id() {
+fn test_date_command() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task"]);
-    let partial_id = &id[..3];
-
     todo_cmd(&temp_dir)
-        .args(["show", partial_id])
+        .args(["date", "2026-01-31"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Task"));
-}
-
-// ============================================================================
-// COMPLETE COMMAND TESTS
-// ============================================================================
+        .stdout(predicate::str::contains("2026-01-31 (Saturday"));
 
-#[test]
-fn test_complete_task() {
-    let temp_dir = TempDir::new().unwrap();
+    todo_cmd(&temp_dir)
+        .args(["date", "end", "of", "next", "month"])
+        .assert()
+        .success();
 
-    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir)
+        .args(["date", "-3d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("3 days ago"));
 
     todo_cmd(&temp_dir)
-        .args(["complete", &id])
+        .args(["date", "2099-01-01", "09:00", "utc"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("marked as complete"));
+        .stdout(predicate::str::contains("UTC: 2099-01-01T09:00:00Z"));
+
+    todo_cmd(&temp_dir)
+        .args(["date", "someday"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid deadline format 'someday'",
+        ));
 }
 
 #[test]
-fn test_complete_alias() {
+fn test_list_before_relative_expression() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task"]);
+    add_task(&temp_dir, &["This quarter", "--deadline", "eoq"]);
+    add_task(&temp_dir, &["Next year", "--deadline", "end of next year"]);
 
-    todo_cmd(&temp_dir).args(["done", &id]).assert().success();
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--before", "last day of the quarter"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("This quarter"));
+    assert!(!stdout.contains("Next year"));
 }
 
 #[test]
-fn test_complete_nonexistent_task() {
+fn test_ambiguous_numeric_date() {
     let temp_dir = TempDir::new().unwrap();
 
     todo_cmd(&temp_dir)
-        .args(["complete", "nonexistent"])
+        .args(["add", "Task", "--deadline", "02/10/2099"])
         .assert()
-        .failure();
+        .failure()
+        .stderr(predicate::str::contains(
+            "Ambiguous date '02/10/2099', which could be 2 October 2099 or 10 February 2099",
+        ));
+
+    // Only one reading is a valid date
+    todo_cmd(&temp_dir)
+        .args(["date", "25/12/2099"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2099-12-25 (Friday"));
 }
 
 #[test]
-fn test_complete_partial_id() {
+fn test_date_format_flag() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task"]);
-    let partial_id = &id[..3];
+    let id = add_task(
+        &temp_dir,
+        &["Task", "--deadline", "02/10/2099", "--date-format", "uk"],
+    );
 
     todo_cmd(&temp_dir)
-        .args(["complete", partial_id])
+        .args(["show", &id, "--date-format", "uk"])
         .assert()
-        .success();
-}
+        .success()
+        .stdout(predicate::str::contains("Deadline: 02/10/2099"));
 
-// ============================================================================
-// INCOMPLETE COMMAND TESTS
-// ============================================================================
-#[test]
-fn test_incomplete_task() {
-    let temp_dir = TempDir::new().unwrap();
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deadline: 2099-10-02"));
 
-    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir)
+        .args(["--date-format", "us", "date", "02/10/2099"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("02/10/2099 (Tuesday"));
 
     todo_cmd(&temp_dir)
-        .args(["complete", &id])
+        .args(["--date-format", "us", "date", "13/01/2099"])
         .assert()
-        .success();
+        .failure()
+        .stderr(predicate::str::contains("Dates are read as MM/DD/YYYY"));
 
+    let today = chrono::Local::now().date_naive();
     todo_cmd(&temp_dir)
-        .args(["incomplete", &id])
+        .args(["log", &id, "--date-format", "uk"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("marked as incomplete"));
+        .stdout(predicate::str::contains(
+            today.format("%d/%m/%Y").to_string(),
+        ));
 
-    // Verify it appears in default list again
+    todo_cmd(&temp_dir).args(["remove", &id]).assert().success();
     todo_cmd(&temp_dir)
-        .arg("list")
+        .args(["trash", "list", "--date-format", "us"])
         .assert()
-        .stdout(predicate::str::contains("Task"));
+        .success()
+        .stdout(predicate::str::contains(
+            today.format("%m/%d/%Y").to_string(),
+        ));
 }
 
 #[test]
-fn test_incomplete_alias() {
+fn test_date_format_config() {
     let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("config.json"),
+        r#"{"date_format": "uk"}"#,
+    )
+    .unwrap();
 
-    let id = add_task(&temp_dir, &["Task"]);
+    let id = add_task(&temp_dir, &["Task", "--deadline", "02/10/2099"]);
 
     todo_cmd(&temp_dir)
-        .args(["complete", &id])
+        .args(["show", &id])
         .assert()
-        .success();
-    todo_cmd(&temp_dir).args(["undo", &id]).assert().success();
-}
-
-#[test]
-fn test_incomplete_nonexistent_task() {
-    let temp_dir = TempDir::new().unwrap();
+        .success()
+        .stdout(predicate::str::contains("Deadline: 02/10/2099"));
 
+    // The flag overrides the config
     todo_cmd(&temp_dir)
-        .args(["incomplete", "nonexistent"])
+        .args(["show", &id, "--date-format", "iso"])
         .assert()
-        .failure();
+        .success()
+        .stdout(predicate::str::contains("Deadline: 2099-10-02"));
 }
 
-// ============================================================================
-// UPDATE COMMAND TESTS
-// ============================================================================
-
 #[test]
-fn test_update_task_title() {
-    let temp_dir = TempDir::new().unwrap();
+fn test_working_calendar() {
+    use chrono::{Datelike, Duration, Local, Weekday};
 
-    let id = add_task(&temp_dir, &["Old title"]);
+    let temp_dir = TempDir::new().unwrap();
+    let today = Local::now().date_naive();
+    let next_monday = today
+        .iter_days()
+        .skip(1)
+        .find(|d| d.weekday() == Weekday::Mon)
+        .unwrap();
+    let this_monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+
+    fs::write(
+        temp_dir.path().join("holidays.txt"),
+        format!("# Days off\n{next_monday} Bank holiday\n"),
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("config.json"),
+        r#"{"calendar": {"workdays": ["mon"], "holiday_file": "holidays.txt", "end_of_week": "last_workday", "count_workdays": true}}"#,
+    )
+    .unwrap();
+
+    // The next Monday is a holiday, so the first working day is the one after
+    todo_cmd(&temp_dir)
+        .args(["date", "+1bd"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{} (Monday",
+            next_monday + Duration::weeks(1)
+        )))
+        .stdout(predicate::str::contains("1 working days"));
 
     todo_cmd(&temp_dir)
-        .args(["update", &id, "--task", "New title"])
+        .args(["date", "eow"])
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::contains(this_monday.to_string()));
 
+    fs::write(
+        temp_dir.path().join("config.json"),
+        r#"{"calendar": {"holiday_file": "missing.txt"}}"#,
+    )
+    .unwrap();
     todo_cmd(&temp_dir)
-        .args(["show", &id])
+        .arg("list")
         .assert()
-        .stdout(predicate::str::contains("New title"));
+        .failure()
+        .stderr(predicate::str::contains("Unable to read the holiday file"));
 }
 
 #[test]
-fn test_update_task_difficulty() {
+fn test_board() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task", "--diff", "3"]);
-
+    let report = add_task(
+        &temp_dir,
+        &[
+            "Write the quarterly report",
+            "--diff",
+            "7",
+            "--tags",
+            "work",
+        ],
+    );
+    let review = add_task(&temp_dir, &["Review PR", "--tags", "work,code"]);
+    let milk = add_task(&temp_dir, &["Buy milk"]);
     todo_cmd(&temp_dir)
-        .args(["update", &id, "--diff", "8"])
+        .args(["start", &review])
         .assert()
         .success();
-
     todo_cmd(&temp_dir)
-        .args(["show", &id])
+        .args(["complete", &milk])
         .assert()
-        .stdout(predicate::str::contains("8"));
+        .success();
+
+    let output = todo_cmd(&temp_dir)
+        .arg("board")
+        .env("NO_COLOR", "1")
+        .env("COLUMNS", "60")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("todo (1)"));
+    assert!(stdout.contains("in-progress (1)"));
+    assert!(stdout.contains("waiting (0)"));
+    assert!(stdout.contains(&format!("{} · 7", &report[..7])));
+    assert!(!stdout.contains("Buy milk"));
+    assert!(stdout.lines().all(|line| line.chars().count() <= 60));
+
+    let output = todo_cmd(&temp_dir)
+        .args(["board", "--by", "tags", "--all"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("code (1)"));
+    assert!(stdout.contains("work (2)"));
+    assert!(stdout.contains("untagged (1)"));
+    assert!(stdout.contains("Buy milk"));
 }
 
 #[test]
-fn test_update_task_deadline() {
+fn test_calendar() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task"]);
+    add_task(&temp_dir, &["Submit taxes", "--deadline", "2099-11-20"]);
+    add_task(&temp_dir, &["Book flights", "--deadline", "2099-11-20"]);
+    add_task(&temp_dir, &["Next month", "--deadline", "2099-12-01"]);
+
+    let output = todo_cmd(&temp_dir)
+        .args(["calendar", "--month", "2099-11"])
+        .env("NO_COLOR", "1")
+        .env("COLUMNS", "120")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("November 2099"));
+    assert!(stdout.contains("20 (2)"));
+    assert!(stdout.contains("Submit taxes"));
+    assert!(stdout.contains("Book flights"));
+    assert!(stdout.contains("30"));
+    assert!(!stdout.contains("Next month"));
 
     todo_cmd(&temp_dir)
-        .args(["update", &id, "--deadline", "tomorrow"])
+        .args(["calendar", "--month", "2099-13"])
         .assert()
-        .success();
+        .failure()
+        .stderr(predicate::str::contains("Invalid month '2099-13'"));
 }
 
 #[test]
-fn test_update_task_tags() {
+fn test_agenda() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task", "--tags", "old"]);
+    add_task(&temp_dir, &["Overdue report", "--deadline", "3 days ago"]);
+    add_task(&temp_dir, &["Dentist", "--deadline", "tomorrow 15:00"]);
+    add_task(&temp_dir, &["Pay rent", "--deadline", "tomorrow"]);
+    add_task(&temp_dir, &["Later", "--deadline", "+10d"]);
+    add_task(&temp_dir, &["Late call", "--deadline", "tomorrow 10pm"]);
+    add_task(&temp_dir, &["Standup", "--deadline", "tomorrow 9am"]);
 
-    todo_cmd(&temp_dir)
-        .args(["update", &id, "--tags", "new,updated"])
-        .assert()
-        .success();
+    let output = todo_cmd(&temp_dir)
+        .arg("agenda")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let position = |text: &str| stdout.find(text).unwrap();
+    assert!(position("Overdue") < position("(tomorrow)"));
+    assert!(stdout.contains("Overdue report (3 days ago)"));
+    assert!(stdout.contains("15:00 Dentist"));
+    assert_eq!(stdout.matches("(tomorrow)").count(), 1);
+    assert!(position("(tomorrow)") < position("Pay rent"));
+    assert!(position("Pay rent") < position("Standup"));
+    assert!(position("Standup") < position("Dentist"));
+    assert!(position("Dentist") < position("22:00 Late call"));
+    assert!(!stdout.contains("Later"));
 
     todo_cmd(&temp_dir)
-        .args(["show", &id])
+        .args(["agenda", "--days", "14"])
         .assert()
-        .stdout(predicate::str::contains("new"))
-        .stdout(predicate::str::contains("updated"));
+        .success()
+        .stdout(predicate::str::contains("Later"));
 }
 
 #[test]
-fn test_update_multiple_fields() {
+fn test_list_all() {
     let temp_dir = TempDir::new().unwrap();
 
     let id = add_task(&temp_dir, &["Task"]);
-
     todo_cmd(&temp_dir)
-        .args([
-            "update",
-            &id,
-            "--task",
-            "Updated",
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    // Without flag, shouldn't show completed
+    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Task") || stdout.contains("No tasks"));
+
+    // With flag, should show completed
+    todo_cmd(&temp_dir)
+        .args(["list", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task"));
+}
+
+#[test]
+fn test_list_tree_view() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let project = add_task(&temp_dir, &["Launch website"]);
+    let design = add_task(&temp_dir, &["Design", "--pid", &project]);
+    let build = add_task(&temp_dir, &["Build pages", "--pid", &project]);
+    let home = add_task(&temp_dir, &["Home page", "--pid", &build]);
+    add_task(&temp_dir, &["About page", "--pid", &build]);
+    add_task(&temp_dir, &["Deploy", "--pid", &project]);
+    let old = add_task(&temp_dir, &["Old project"]);
+    for id in [&design, &home, &old] {
+        todo_cmd(&temp_dir)
+            .args(["complete", id])
+            .assert()
+            .success();
+    }
+
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--view", "tree"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("○ Launch website [2/5]"));
+    assert!(stdout.contains("├── ✓ Design"));
+    assert!(stdout.contains("├── ○ Build pages [1/2]"));
+    assert!(stdout.contains("│   ├── ✓ Home page"));
+    assert!(stdout.contains("│   └── ○ About page"));
+    assert!(stdout.contains("└── ○ Deploy"));
+    assert!(!stdout.contains("Old project"));
+
+    // Completed branches are collapsed
+    todo_cmd(&temp_dir)
+        .args(["complete", &build])
+        .assert()
+        .success();
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--view", "tree"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("├── ✓ Build pages [1/2]"));
+    assert!(!stdout.contains("Home page"));
+
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--view", "tree", "--all"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("│   ├── ✓ Home page"));
+    assert!(stdout.contains("✓ Old project"));
+
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--view", "tree", "--pid", &build])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("✓ Home page"));
+    assert!(stdout.contains("○ About page"));
+    assert!(!stdout.contains("Deploy"));
+}
+
+#[test]
+fn test_list_only_completed() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["Incomplete"]);
+    let id = add_task(&temp_dir, &["Complete"]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--completed"])
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Complete"));
+    assert!(!stdout.contains("Incomplete"));
+}
+
+#[test]
+fn test_list_alias() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir).arg("ls").assert().success();
+}
+
+// ============================================================================
+// TAGS COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_tags() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["Test task", "--tags", "work"]);
+
+    todo_cmd(&temp_dir)
+        .args(["tags"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("work"));
+}
+
+// ============================================================================
+// SHOW COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_show_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(
+        &temp_dir,
+        &[
+            "Test task",
+            "--desc",
+            "Description",
+            "--diff",
+            "5",
+            "--tags",
+            "work",
+        ],
+    );
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Test task"))
+        .stdout(predicate::str::contains("Description"))
+        .stdout(predicate::str::contains("5"))
+        .stdout(predicate::str::contains("work"));
+}
+
+#[test]
+fn test_show_nonexistent_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["show", "nonexistent"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No task found"));
+}
+
+#[test]
+fn test_show_partial_id() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    let partial_id = &id[..3];
+
+    todo_cmd(&temp_dir)
+        .args(["show", partial_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task"));
+}
+
+// ============================================================================
+// COMPLETE COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_complete_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("marked as complete"));
+}
+
+#[test]
+fn test_complete_alias() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir).args(["done", &id]).assert().success();
+}
+
+#[test]
+fn test_complete_nonexistent_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["complete", "nonexistent"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_complete_partial_id() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    let partial_id = &id[..3];
+
+    todo_cmd(&temp_dir)
+        .args(["complete", partial_id])
+        .assert()
+        .success();
+}
+
+// ============================================================================
+// INCOMPLETE COMMAND TESTS
+// ============================================================================
+#[test]
+fn test_incomplete_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["incomplete", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("marked as incomplete"));
+
+    // Verify it appears in default list again
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .stdout(predicate::str::contains("Task"));
+}
+
+#[test]
+fn test_incomplete_alias() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir).args(["reopen", &id]).assert().success();
+}
+
+#[test]
+fn test_incomplete_nonexistent_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["incomplete", "nonexistent"])
+        .assert()
+        .failure();
+}
+
+// ============================================================================
+// UPDATE COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_update_task_title() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Old title"]);
+
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--task", "New title"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .stdout(predicate::str::contains("New title"));
+}
+
+#[test]
+fn test_update_task_difficulty() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task", "--diff", "3"]);
+
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--diff", "8"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .stdout(predicate::str::contains("8"));
+}
+
+#[test]
+fn test_update_task_deadline() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--deadline", "tomorrow"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_update_task_tags() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task", "--tags", "old"]);
+
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--tags", "new,updated"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .stdout(predicate::str::contains("new"))
+        .stdout(predicate::str::contains("updated"));
+}
+
+#[test]
+fn test_update_multiple_fields() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir)
+        .args([
+            "update",
+            &id,
+            "--task",
+            "Updated",
+            "--diff",
+            "9",
+            "--deadline",
+            "friday",
+            "--tags",
+            "urgent",
+        ])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_update_nonexistent_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["update", "nonexistent", "--task", "New"])
+        .assert()
+        .failure();
+}
+
+// ============================================================================
+// NEXT COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_next_with_no_tasks() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir).arg("next").assert().failure();
+}
+
+#[test]
+fn test_next_shows_highest_priority() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(
+        &temp_dir,
+        &["Low priority", "--diff", "1", "--deadline", "+7d"],
+    );
+    add_task(
+        &temp_dir,
+        &["High priority", "--diff", "10", "--deadline", "tomorrow"],
+    );
+
+    todo_cmd(&temp_dir)
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("High priority"));
+}
+
+#[test]
+fn test_next_ignores_completed() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id1 = add_task(
+        &temp_dir,
+        &["Urgent", "--diff", "10", "--deadline", "today"],
+    );
+    add_task(
+        &temp_dir,
+        &["Less urgent", "--diff", "5", "--deadline", "+7d"],
+    );
+
+    todo_cmd(&temp_dir)
+        .args(["complete", &id1])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Less urgent"));
+}
+
+// ============================================================================
+// REMOVE COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_remove_single_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir)
+        .args(["remove", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 task"));
+}
+
+#[test]
+fn test_remove_multiple_tasks() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id1 = add_task(&temp_dir, &["Task 1"]);
+    let id2 = add_task(&temp_dir, &["Task 2"]);
+
+    todo_cmd(&temp_dir)
+        .args(["remove", &id1, &id2])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 2 task"));
+}
+
+#[test]
+fn test_remove_by_tags() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["Task 1", "--tags", "remove"]);
+    add_task(&temp_dir, &["Task 2", "--tags", "remove"]);
+    add_task(&temp_dir, &["Task 3", "--tags", "keep"]);
+
+    todo_cmd(&temp_dir)
+        .args(["remove", "--tags", "remove"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 2 task"));
+
+    // Verify the "keep" task is still there
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .stdout(predicate::str::contains("Task 3"));
+}
+
+#[test]
+fn test_remove_alias() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir).args(["rm", &id]).assert().success();
+}
+
+#[test]
+fn test_remove_nonexistent_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["remove", "nonexistent"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 0 task"));
+}
+
+#[test]
+fn test_remove_partial_id() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    let partial_id = &id[..3];
+
+    todo_cmd(&temp_dir)
+        .args(["remove", partial_id])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_remove_requires_id_or_tags() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("remove")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("required"));
+}
+
+// ============================================================================
+// CLEAR COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_clear_with_force() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["Task 1"]);
+    add_task(&temp_dir, &["Task 2"]);
+
+    todo_cmd(&temp_dir)
+        .args(["clear", "--force"])
+        .assert()
+        .success();
+
+    // Verify all tasks are gone
+    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Task 1"));
+    assert!(!stdout.contains("Task 2"));
+}
+
+// ============================================================================
+// INTEGRATION TESTS
+// ============================================================================
+
+#[test]
+fn test_full_task_lifecycle() {
+    let temp_dir = TempDir::new().unwrap();
+
+    // Add a task
+    let id = add_task(
+        &temp_dir,
+        &[
+            "Complete project",
+            "--desc",
+            "Finish the todo app",
+            "--diff",
+            "8",
+            "--deadline",
+            "friday",
+            "--tags",
+            "work,coding",
+        ],
+    );
+
+    // Verify it appears in list
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .stdout(predicate::str::contains("Complete project"));
+
+    // Update it
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--diff", "6"])
+        .assert()
+        .success();
+
+    // Show details
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .stdout(predicate::str::contains("6"));
+
+    // Complete it
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    // Verify it's not in default list
+    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Complete project") || stdout.contains("No tasks"));
+
+    // Verify it appears with --completed flag
+    todo_cmd(&temp_dir)
+        .args(["list", "--completed"])
+        .assert()
+        .stdout(predicate::str::contains("Complete project"));
+}
+
+#[test]
+fn test_hierarchical_tasks() {
+    let temp_dir = TempDir::new().unwrap();
+
+    // Create parent task
+    let parent_id = add_task(&temp_dir, &["Main project"]);
+
+    // Create subtasks
+    let subtask1_id = add_task(&temp_dir, &["Subtask 1", "--pid", &parent_id]);
+    let subtask2_id = add_task(&temp_dir, &["Subtask 2", "--pid", &parent_id]);
+
+    // List tasks by parent
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--pid", &parent_id])
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Subtask 1"));
+    assert!(stdout.contains("Subtask 2"));
+
+    // Complete subtasks
+    todo_cmd(&temp_dir)
+        .args(["complete", &subtask1_id])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir)
+        .args(["complete", &subtask2_id])
+        .assert()
+        .success();
+
+    // Complete parent
+    todo_cmd(&temp_dir)
+        .args(["complete", &parent_id])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_tag_filtering() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["Work task 1", "--tags", "work"]);
+    add_task(&temp_dir, &["Work task 2", "--tags", "work,urgent"]);
+    add_task(&temp_dir, &["Personal task", "--tags", "personal"]);
+    add_task(&temp_dir, &["Urgent personal", "--tags", "personal,urgent"]);
+
+    // Filter by single tag
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--tags", "work"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Work task"));
+
+    // Filter by multiple tags
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--tags", "urgent"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Work task 2"));
+    assert!(stdout.contains("Urgent personal"));
+}
+
+#[test]
+fn test_priority_ordering() {
+    let temp_dir = TempDir::new().unwrap();
+
+    // Add tasks with different priorities
+    add_task(&temp_dir, &["Low", "--diff", "2", "--deadline", "+30d"]);
+    add_task(&temp_dir, &["Medium", "--diff", "5", "--deadline", "+7d"]);
+    add_task(
+        &temp_dir,
+        &["High", "--diff", "9", "--deadline", "tomorrow"],
+    );
+
+    // Next should show the highest priority
+    todo_cmd(&temp_dir)
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("High"));
+}
+
+#[test]
+fn test_custom_database_path() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task in custom location"]);
+
+    // Verify task is accessible with custom path
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task in custom location"));
+}
+
+#[test]
+fn test_partial_id_ambiguity() {
+    let temp_dir = TempDir::new().unwrap();
+
+    // This test assumes IDs might conflict - if your ID generation
+    // makes this impossible, you can skip this test
+    let id1 = add_task(&temp_dir, &["Task 1"]);
+    let _id2 = add_task(&temp_dir, &["Task 2"]);
+
+    // Using full ID should always work
+    todo_cmd(&temp_dir).args(["show", &id1]).assert().success();
+}
+
+#[test]
+fn test_deadline_sorting() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["Due later", "--deadline", "+14d"]);
+    add_task(&temp_dir, &["Due soon", "--deadline", "tomorrow"]);
+    add_task(&temp_dir, &["Due today", "--deadline", "today"]);
+
+    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    // Earlier deadlines should appear first
+    let today_pos = stdout.find("Due today");
+    let soon_pos = stdout.find("Due soon");
+    let later_pos = stdout.find("Due later");
+
+    assert!(today_pos.is_some());
+    assert!(soon_pos.is_some());
+    assert!(later_pos.is_some());
+    assert!(today_pos < soon_pos);
+    assert!(soon_pos < later_pos);
+}
+
+// ============================================================================
+// EXPORT/IMPORT COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_export_json() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(
+        &temp_dir,
+        &["Task", "--desc", "Details", "--tags", "work,urgent"],
+    );
+
+    let output = todo_cmd(&temp_dir)
+        .args(["export", "--format", "json"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(r#""version": 6"#));
+    assert!(stdout.contains(&id));
+    assert!(stdout.contains("Details"));
+    assert!(stdout.contains("urgent"));
+}
+
+#[test]
+fn test_export_import_round_trip() {
+    let source = TempDir::new().unwrap();
+
+    let parent_id = add_task(
+        &source,
+        &[
+            "Parent",
+            "--diff",
+            "6",
+            "--deadline",
+            "2026-12-31",
+            "--tags",
+            "project",
+        ],
+    );
+    let child_id = add_task(&source, &["Child", "--pid", &parent_id]);
+    todo_cmd(&source)
+        .args(["complete", &child_id])
+        .assert()
+        .success();
+
+    let export = source.path().join("tasks.json");
+    todo_cmd(&source)
+        .args(["export", "--output"])
+        .arg(&export)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 2 task(s)"));
+
+    let target = TempDir::new().unwrap();
+    todo_cmd(&target)
+        .arg("import")
+        .arg(&export)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 added, 0 updated"));
+
+    let source_show = todo_cmd(&source)
+        .args(["show", &child_id])
+        .output()
+        .unwrap();
+    let target_show = todo_cmd(&target)
+        .args(["show", &child_id])
+        .output()
+        .unwrap();
+    assert_eq!(source_show.stdout, target_show.stdout);
+
+    let source_show = todo_cmd(&source)
+        .args(["show", &parent_id])
+        .output()
+        .unwrap();
+    let target_show = todo_cmd(&target)
+        .args(["show", &parent_id])
+        .output()
+        .unwrap();
+    assert_eq!(source_show.stdout, target_show.stdout);
+}
+
+#[test]
+fn test_import_merge_updates_existing() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Original"]);
+    add_task(&temp_dir, &["Other"]);
+
+    let export = temp_dir.path().join("tasks.json");
+    todo_cmd(&temp_dir)
+        .args(["export", "--output"])
+        .arg(&export)
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--task", "Renamed"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&export)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 added, 2 updated"));
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Original"));
+}
+
+#[test]
+fn test_import_replace() {
+    let source = TempDir::new().unwrap();
+    add_task(&source, &["Imported"]);
+    let export = source.path().join("tasks.json");
+    todo_cmd(&source)
+        .args(["export", "--output"])
+        .arg(&export)
+        .assert()
+        .success();
+
+    let target = TempDir::new().unwrap();
+    add_task(&target, &["Existing"]);
+
+    todo_cmd(&target)
+        .arg("import")
+        .arg(&export)
+        .args(["--mode", "replace"])
+        .assert()
+        .success();
+
+    let output = todo_cmd(&target).arg("list").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Imported"));
+    assert!(!stdout.contains("Existing"));
+
+    todo_cmd(&target).arg("undo").assert().success();
+
+    let output = todo_cmd(&target).arg("list").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Imported"));
+    assert!(stdout.contains("Existing"));
+}
+
+#[test]
+fn test_import_merge_journals_imported_tasks() {
+    let source = TempDir::new().unwrap();
+    add_task(&source, &["Imported"]);
+    let export = source.path().join("tasks.json");
+    todo_cmd(&source)
+        .args(["export", "--output"])
+        .arg(&export)
+        .assert()
+        .success();
+
+    let target = TempDir::new().unwrap();
+    add_task(&target, &["Existing"]);
+
+    todo_cmd(&target)
+        .arg("import")
+        .arg(&export)
+        .assert()
+        .success();
+
+    let titles: Vec<String> = Connection::open(target.path().join("tasks.db"))
+        .unwrap()
+        .prepare(
+            "SELECT title FROM journal_tasks
+             WHERE journal_id = (SELECT MAX(id) FROM journal)",
+        )
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(titles, vec!["Imported".to_string()]);
+}
+
+#[test]
+fn test_import_missing_parent() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("tasks.json");
+    fs::write(
+        &file,
+        r#"{"version": 1, "exported": "2026-01-01T00:00:00Z", "tasks": [{
+            "id": "1a2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5",
+            "title": "Orphan",
+            "parent": "2b3c4d5e6f708192a3b4c5d6e7f80912a3b4c5d6",
+            "created": "2026-01-01T00:00:00Z"
+        }]}"#,
+    )
+    .unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "not in the import or the database",
+        ));
+}
+
+#[test]
+fn test_export_csv_columns() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(
+        &temp_dir,
+        &["Task", "--deadline", "2026-12-31", "--tags", "work,urgent"],
+    );
+
+    todo_cmd(&temp_dir)
+        .args([
+            "export",
+            "--format",
+            "csv",
+            "--columns",
+            "task,deadline,tags",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Task,Deadline,Tags\nTask,2026-12-31,\"urgent,work\"",
+        ));
+}
+
+#[test]
+fn test_export_json_columns() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["export", "--format", "json", "--columns", "task"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only supported by CSV"));
+}
+
+#[test]
+fn test_import_csv() {
+    let temp_dir = TempDir::new().unwrap();
+    let parent_id = add_task(&temp_dir, &["Project"]);
+
+    let file = temp_dir.path().join("tasks.csv");
+    fs::write(
+        &file,
+        format!(
+            "Summary,Effort,Due Date,Labels,Parent,Owner\n\
+             Write report,3,2026-11-01,work;q4,{},me\n\
+             Too hard,12,,,,me\n\
+             Bad date,,someday,,,me\n\
+             Orphan,,,,abcdef0,me\n",
+            &parent_id[..7]
+        ),
+    )
+    .unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .args(["--map", "Effort=difficulty,Labels=tags"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 task(s)"))
+        .stderr(predicate::str::contains("Ignoring column 'Owner'"))
+        .stderr(predicate::str::contains(
+            "Row 3: Difficulty value out of range",
+        ))
+        .stderr(predicate::str::contains("Row 4: Invalid deadline format"))
+        .stderr(predicate::str::contains("Row 5: Invalid parent"))
+        .stderr(predicate::str::contains("Skipped 3 invalid row(s)"));
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--pid", &parent_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Write report"))
+        .stdout(predicate::str::contains("q4, work"));
+}
+
+#[test]
+fn test_import_csv_without_title() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("tasks.csv");
+    fs::write(&file, "Thing,When\nreport,today\n").unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no task title column"));
+}
+
+#[test]
+fn test_export_todotxt() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(
+        &temp_dir,
+        &[
+            "Call mom",
+            "--diff",
+            "9",
+            "--deadline",
+            "2026-12-31",
+            "--tags",
+            "family",
+        ],
+    );
+
+    let output = todo_cmd(&temp_dir)
+        .args(["export", "--format", "todotxt"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.starts_with("(B) "));
+    assert!(stdout.contains("Call mom +family due:2026-12-31"));
+    assert!(!stdout.contains(&id));
+}
+
+#[test]
+fn test_import_todotxt_lossless_merges() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let parent_id = add_task(&temp_dir, &["Plan trip"]);
+    add_task(&temp_dir, &["Book flights", "--pid", &parent_id]);
+
+    let file = temp_dir.path().join("todo.txt");
+    todo_cmd(&temp_dir)
+        .args(["export", "--format", "todotxt", "--lossless", "--output"])
+        .arg(&file)
+        .assert()
+        .success();
+
+    let contents = fs::read_to_string(&file).unwrap();
+    assert!(contents.contains(&format!("id:{parent_id}")));
+    assert!(contents.contains(&format!("parent:{parent_id}")));
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 added, 2 updated"));
+}
+
+#[test]
+fn test_import_todotxt() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("todo.txt");
+    fs::write(
+        &file,
+        "(A) 2026-01-10 Write report +work @office due:2026-12-01\n\
+         \n\
+         x 2026-01-12 2026-01-11 Buy milk +shopping\n",
+    )
+    .unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 added, 0 updated"));
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Write report"))
+        .stdout(predicate::str::contains("office, work"))
+        .stdout(predicate::str::contains("10"))
+        .stdout(predicate::str::contains("Buy milk").not());
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--completed"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Buy milk"));
+}
+
+#[test]
+fn test_import_todotxt_invalid_line() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("todo.txt");
+    fs::write(&file, "Fine task\nBad task due:someday\n").unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Line 2: Invalid deadline format"));
+}
+
+#[test]
+fn test_export_ics() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let parent_id = add_task(
+        &temp_dir,
+        &["Release", "--deadline", "2026-12-31", "--tags", "work,q4"],
+    );
+    let child_id = add_task(&temp_dir, &["Changelog", "--pid", &parent_id]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &child_id])
+        .assert()
+        .success();
+
+    let output = todo_cmd(&temp_dir)
+        .args(["export", "--format", "ics"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(stdout.contains(&format!("UID:{parent_id}")));
+    assert!(stdout.contains("DUE;VALUE=DATE:20261231\r\n"));
+    assert!(stdout.contains("CATEGORIES:q4,work\r\n"));
+    assert!(stdout.contains(&format!("RELATED-TO;RELTYPE=PARENT:{parent_id}")));
+    assert!(stdout.contains("STATUS:COMPLETED\r\n"));
+    assert!(stdout.contains("STATUS:NEEDS-ACTION\r\n"));
+}
+
+#[test]
+fn test_import_ics() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("calendar.ics");
+    fs::write(
+        &file,
+        "BEGIN:VCALENDAR\r\n\
+         VERSION:2.0\r\n\
+         PRODID:-//Example//Calendar//EN\r\n\
+         BEGIN:VEVENT\r\n\
+         UID:meeting@example.com\r\n\
+         SUMMARY:Standup\r\n\
+         END:VEVENT\r\n\
+         BEGIN:VTODO\r\n\
+         UID:project@example.com\r\n\
+         SUMMARY:Quarterly report\r\n\
+         DUE;VALUE=DATE:20261201\r\n\
+         CATEGORIES:work,reports\r\n\
+         END:VTODO\r\n\
+         BEGIN:VTODO\r\n\
+         UID:draft@example.com\r\n\
+         SUMMARY:Draft\\, then review\r\n\
+         RELATED-TO:project@example.com\r\n\
+         STATUS:COMPLETED\r\n\
+         END:VTODO\r\n\
+         END:VCALENDAR\r\n",
+    )
+    .unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 added, 0 updated"));
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 added, 2 updated"));
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Quarterly report"))
+        .stdout(predicate::str::contains("reports, work"))
+        .stdout(predicate::str::contains("Draft, then review"))
+        .stdout(predicate::str::contains("Standup").not());
+}
+
+#[test]
+fn test_export_markdown() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let parent_id = add_task(
+        &temp_dir,
+        &["Release", "--deadline", "2026-12-31", "--tags", "work"],
+    );
+    let child_id = add_task(&temp_dir, &["Changelog", "--pid", &parent_id]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &child_id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["export", "--format", "markdown"])
+        .assert()
+        .success()
+        .stdout("- [ ] Release (due 2026-12-31) #work\n  - [x] Changelog\n");
+}
+
+#[test]
+fn test_import_markdown_meeting_notes() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("notes.md");
+    fs::write(
+        &file,
+        "# Sprint planning\n\
+         \n\
+         Discussed the release.\n\
+         \n\
+         - [ ] Write spec #docs\n\
+         \x20 - [x] Draft outline\n\
+         - [ ] Book room (due 2026-12-01)\n",
+    )
+    .unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("4 added, 0 updated"));
+
+    let output = todo_cmd(&temp_dir)
+        .args(["export", "--format", "md"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains(
+        "- [ ] Sprint planning\n  \
+         - [ ] Write spec #docs\n    \
+         - [x] Draft outline\n  \
+         - [ ] Book room (due 2026-12-01)"
+    ));
+}
+
+#[test]
+fn test_import_taskwarrior() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("tasks.json");
+    fs::write(
+        &file,
+        r#"[
+            {"id": 1, "uuid": "5f1c0a42-3b5e-4c7a-9a53-5d2f3e6c1b10", "description": "Plan garden",
+             "status": "pending", "entry": "20260101T090000Z", "due": "20261201T120000Z",
+             "project": "home", "tags": ["outside"], "urgency": 8.2,
+             "depends": ["8e0f8a3c-0d1b-4f7e-8c5e-2b6c1d9e4a21"]},
+            {"id": 2, "uuid": "8e0f8a3c-0d1b-4f7e-8c5e-2b6c1d9e4a21", "description": "Buy seeds",
+             "status": "pending", "entry": "20260101T090000Z", "wait": "20260102T000000Z",
+             "estimate": "2h"},
+            {"uuid": "0b7d3c1e-9f2a-4e6b-8d4c-7a1e5f3b2c90", "description": "Water plants",
+             "status": "recurring", "recur": "daily"}
+        ]"#,
+    )
+    .unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["import", "--from", "taskwarrior"])
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 added, 0 updated"))
+        .stderr(predicate::str::contains(
+            "attribute 'estimate' of 1 task(s)",
+        ))
+        .stderr(predicate::str::contains(
+            "Skipped 1 recurring task template(s)",
+        ));
+
+    todo_cmd(&temp_dir)
+        .args(["import", "--from", "taskwarrior"])
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 added, 2 updated"));
+
+    // The due time is kept, and shown in the local timezone.
+    todo_cmd(&temp_dir)
+        .args(["export", "--format", "markdown"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"- \[ \] Plan garden \(due 2026-12-0[12] \d{2}:00\) #home #outside\n  - \[ \] Buy seeds",
+            )
+            .unwrap(),
+        );
+}
+
+#[test]
+fn test_import_unknown_format() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("tasks.dat");
+    fs::write(&file, "").unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--format"));
+}
+
+// ============================================================================
+// TRASH COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_removed_tasks_go_to_trash() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let parent_id = add_task(&temp_dir, &["Project task", "--tags", "project"]);
+    add_task(
+        &temp_dir,
+        &["Subtask", "--pid", &parent_id, "--tags", "sub"],
+    );
+    add_task(&temp_dir, &["Unrelated"]);
+
+    todo_cmd(&temp_dir)
+        .args(["rm", "--tags", "project"])
+        .assert()
+        .success();
+
+    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Unrelated"));
+    assert!(!stdout.contains("Project task"));
+    assert!(!stdout.contains("Subtask"));
+
+    let output = todo_cmd(&temp_dir).arg("tags").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("project"));
+    assert!(!stdout.contains("sub"));
+
+    todo_cmd(&temp_dir)
+        .args(["trash", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Project task"))
+        .stdout(predicate::str::contains("Subtask"));
+}
+
+#[test]
+fn test_next_ignores_trashed() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Urgent", "--deadline", "today"]);
+    add_task(&temp_dir, &["Later", "--deadline", "+7d"]);
+    todo_cmd(&temp_dir).args(["rm", &id]).assert().success();
+
+    todo_cmd(&temp_dir)
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Later"));
+}
+
+#[test]
+fn test_restore_task_with_subtasks() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let parent_id = add_task(&temp_dir, &["Parent"]);
+    let child_id = add_task(&temp_dir, &["Child", "--pid", &parent_id, "--tags", "sub"]);
+
+    todo_cmd(&temp_dir)
+        .args(["rm", &parent_id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["show", &child_id])
+        .assert()
+        .failure();
+
+    todo_cmd(&temp_dir)
+        .args(["restore", &parent_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 2 task(s)"));
+
+    todo_cmd(&temp_dir)
+        .args(["show", &child_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("sub"));
+
+    todo_cmd(&temp_dir)
+        .args(["trash", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Trash is empty"));
+}
+
+#[test]
+fn test_restore_subtask_restores_parent() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let parent_id = add_task(&temp_dir, &["Parent"]);
+    let child_id = add_task(&temp_dir, &["Child", "--pid", &parent_id]);
+
+    todo_cmd(&temp_dir)
+        .args(["rm", &parent_id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["restore", &child_id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--pid", &parent_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Child"));
+
+    todo_cmd(&temp_dir)
+        .args(["show", &parent_id])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_restore_parent_keeps_subtask_removed_earlier() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let parent_id = add_task(&temp_dir, &["Project"]);
+    let child_id = add_task(&temp_dir, &["Child", "--pid", &parent_id]);
+
+    todo_cmd(&temp_dir)
+        .args(["rm", &child_id])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir)
+        .args(["rm", &parent_id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["restore", &parent_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 1 task(s)"));
+
+    todo_cmd(&temp_dir)
+        .args(["trash", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Child"))
+        .stdout(predicate::str::contains("Project").not());
+}
+
+#[test]
+fn test_restore_nonexistent_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Not removed"]);
+
+    todo_cmd(&temp_dir)
+        .args(["restore", &id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No removed task found"));
+}
+
+#[test]
+fn test_trash_empty() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir).args(["rm", &id]).assert().success();
+
+    todo_cmd(&temp_dir)
+        .args(["trash", "empty", "--older-than", "30d", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Permanently deleted 0 task(s)"));
+
+    todo_cmd(&temp_dir)
+        .args(["trash", "empty", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Permanently deleted 1 task(s)"));
+
+    todo_cmd(&temp_dir)
+        .args(["restore", &id])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_trash_empty_invalid_age() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["trash", "empty", "--older-than", "soon", "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid age"));
+}
+
+// ============================================================================
+// LOG COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_log_task_history() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(
+        &temp_dir,
+        &["Original", "--deadline", "2026-12-01", "--tags", "work"],
+    );
+    todo_cmd(&temp_dir)
+        .args([
+            "update",
+            &id,
+            "--task",
+            "Renamed",
+            "--deadline",
+            "2026-12-24",
+            "--tags",
+            "home",
+        ])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    let output = todo_cmd(&temp_dir).args(["log", &id]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("created"));
+    assert!(stdout.contains("Task: Original → Renamed"));
+    assert!(stdout.contains("Deadline: 2026-12-01 → 2026-12-24"));
+    assert!(stdout.contains("Tags: work → home"));
+    assert!(stdout.contains("completed"));
+
+    let created_pos = stdout.find("created").unwrap();
+    let completed_pos = stdout.find("completed").unwrap();
+    assert!(created_pos < completed_pos);
+}
+
+#[test]
+fn test_log_removed_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir).args(["rm", &id]).assert().success();
+
+    todo_cmd(&temp_dir)
+        .args(["log", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("removed"));
+}
+
+#[test]
+fn test_log_activity_feed() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["First"]);
+    add_task(&temp_dir, &["Second"]);
+
+    todo_cmd(&temp_dir)
+        .args(["log", "--after", "today"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First"))
+        .stdout(predicate::str::contains("Second"));
+
+    // A weekday looks back to its most recent occurrence, as in the documented examples.
+    todo_cmd(&temp_dir)
+        .args(["log", "--after", "mon", "--before", "today"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First"));
+
+    todo_cmd(&temp_dir)
+        .args(["log", "--before", "2020-01-01"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No history found"));
+}
+
+#[test]
+fn test_log_nonexistent_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["log", "nonexistent"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No history found"));
+}
+
+// ============================================================================
+// UNDO/REDO COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_undo_add() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["Added by mistake"]);
+
+    todo_cmd(&temp_dir)
+        .arg("undo")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Undid: add"));
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks found"));
+}
+
+#[test]
+fn test_undo_complete() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir).arg("undo").assert().success();
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--columns", "id,task"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&id));
+}
+
+#[test]
+fn test_undo_update_restores_tags() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Original", "--tags", "work"]);
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--task", "Renamed", "--tags", "home"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir).arg("undo").assert().success();
+
+    let output = todo_cmd(&temp_dir).args(["show", &id]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Original"));
+    assert!(stdout.contains("work"));
+    assert!(!stdout.contains("Renamed"));
+    assert!(!stdout.contains("home"));
+}
+
+#[test]
+fn test_undo_remove_restores_subtasks() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let parent_id = add_task(&temp_dir, &["Parent", "--tags", "project"]);
+    let child_id = add_task(&temp_dir, &["Child", "--pid", &parent_id, "--tags", "sub"]);
+
+    todo_cmd(&temp_dir)
+        .args(["remove", "--tags", "project"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .arg("undo")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Undid: remove --tags project"));
+
+    todo_cmd(&temp_dir)
+        .args(["show", &child_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Child"))
+        .stdout(predicate::str::contains("sub"));
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--pid", &parent_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Child"));
+}
+
+#[test]
+fn test_undo_clear() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let first = add_task(&temp_dir, &["First", "--tags", "work"]);
+    add_task(&temp_dir, &["Second"]);
+
+    todo_cmd(&temp_dir)
+        .args(["track", "start", &first])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir)
+        .args(["track", "stop"])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir)
+        .args(["plan", "--yes"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["clear", "--force"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir).arg("undo").assert().success();
+
+    todo_cmd(&temp_dir)
+        .args(["track", "report"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First"));
+
+    todo_cmd(&temp_dir)
+        .args(["plan", "--show"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First"))
+        .stdout(predicate::str::contains("Second"));
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First"))
+        .stdout(predicate::str::contains("Second"));
+
+    todo_cmd(&temp_dir)
+        .arg("tags")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("work"));
+}
+
+#[test]
+fn test_undo_multiple_and_redo() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["First"]);
+    add_task(&temp_dir, &["Second"]);
+    add_task(&temp_dir, &["Third"]);
+
+    todo_cmd(&temp_dir)
+        .args(["undo", "-n", "2"])
+        .assert()
+        .success();
+
+    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("First"));
+    assert!(!stdout.contains("Second"));
+    assert!(!stdout.contains("Third"));
+
+    todo_cmd(&temp_dir)
+        .arg("redo")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Second"));
+
+    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Second"));
+    assert!(!stdout.contains("Third"));
+}
+
+#[test]
+fn test_new_operation_discards_redo() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["First"]);
+    todo_cmd(&temp_dir).arg("undo").assert().success();
+    add_task(&temp_dir, &["Second"]);
+
+    todo_cmd(&temp_dir)
+        .arg("redo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to redo"));
+}
+
+#[test]
+fn test_undo_refuses_positional_count() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["First"]);
+    add_task(&temp_dir, &["Second"]);
+
+    todo_cmd(&temp_dir)
+        .args(["undo", &id[..7]])
+        .assert()
+        .failure();
+
+    todo_cmd(&temp_dir)
+        .args(["undo", "-n", "0"])
+        .assert()
+        .failure();
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("First"))
+        .stdout(predicate::str::contains("Second"));
+}
+
+#[test]
+fn test_undo_skips_time_tracking() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir)
+        .args(["track", "start", &id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .arg("undo")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Undid: add"));
+
+    todo_cmd(&temp_dir)
+        .args(["undo", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("not undone"));
+}
+
+#[test]
+fn test_undo_with_empty_journal() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .arg("undo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to undo"));
+}
+
+#[test]
+fn test_undo_list() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir).arg("undo").assert().success();
+
+    todo_cmd(&temp_dir)
+        .args(["undo", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("add"))
+        .stdout(predicate::str::contains(format!("complete {id} (undone)")));
+}
+
+// ============================================================================
+// DATABASE MIGRATION TESTS
+// ============================================================================
+
+/// Helper to create a temporary database from one of the SQL fixtures in `tests/fixtures`
+fn fixture_db(fixture: &Path) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let sql = fs::read_to_string(fixture).unwrap();
+
+    Connection::open(temp_dir.path().join("tasks.db"))
+        .unwrap()
+        .execute_batch(&sql)
+        .unwrap();
+
+    temp_dir
+}
+
+/// Every historical schema version, as a fixture database
+fn schema_fixtures() -> Vec<PathBuf> {
+    let mut fixtures: Vec<PathBuf> =
+        fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+            .collect();
+    fixtures.sort();
+    fixtures
+}
+
+fn schema_version(temp_dir: &TempDir) -> u32 {
+    Connection::open(temp_dir.path().join("tasks.db"))
+        .unwrap()
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn test_migrate_fixtures_to_latest() {
+    let fixtures = schema_fixtures();
+    assert!(!fixtures.is_empty());
+
+    for fixture in fixtures {
+        let temp_dir = fixture_db(&fixture);
+
+        todo_cmd(&temp_dir)
+            .args(["list", "--all", "--view", "full"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Fixture parent"))
+            .stdout(predicate::str::contains("Fixture child"))
+            .stdout(predicate::str::contains("Fixture done"))
+            .stdout(predicate::str::contains("fixture"));
+
+        assert_eq!(
+            schema_version(&temp_dir),
+            migrations::latest_version(),
+            "{} was not migrated to the latest schema",
+            fixture.display()
+        );
+
+        todo_cmd(&temp_dir)
+            .args(["db", "migrate", "--dry-run"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("up to date"));
+    }
+}
+
+#[test]
+fn test_migrate_times_stay_in_utc() {
+    let deadline = |temp_dir: &TempDir| -> String {
+        Connection::open(temp_dir.path().join("tasks.db"))
+            .unwrap()
+            .query_row(
+                "SELECT deadline FROM tasks WHERE title = 'Fixture done'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+    };
+
+    // Databases from before times were stored locally, and while they were.
+    for (fixture, stored) in [
+        ("v10.sql", "2026-10-21T02:00:00Z"),
+        ("v12.sql", "2026-10-20T22:00"),
+    ] {
+        let temp_dir = fixture_db(
+            &Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(fixture),
+        );
+        Connection::open(temp_dir.path().join("tasks.db"))
+            .unwrap()
+            .execute(
+                "UPDATE tasks SET deadline = ?1 WHERE title = 'Fixture done'",
+                [stored],
+            )
+            .unwrap();
+
+        todo_cmd(&temp_dir)
+            .env("TZ", "America/New_York")
+            .args(["show", "3c4d5e6"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Deadline: 2026-10-20 22:00"));
+        assert_eq!(deadline(&temp_dir), "2026-10-21T02:00:00Z");
+    }
+
+    // A local time that was skipped when the clocks went forward still migrates.
+    let temp_dir =
+        fixture_db(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/v12.sql"));
+    Connection::open(temp_dir.path().join("tasks.db"))
+        .unwrap()
+        .execute(
+            "UPDATE tasks SET deadline = '2026-03-08T02:30' WHERE title = 'Fixture done'",
+            [],
+        )
+        .unwrap();
+    todo_cmd(&temp_dir)
+        .env("TZ", "America/New_York")
+        .args(["list", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fixture done"));
+    assert!(deadline(&temp_dir).ends_with('Z'));
+}
+
+#[test]
+fn test_migrate_dry_run_does_not_migrate() {
+    let temp_dir = fixture_db(&schema_fixtures()[0]);
+
+    todo_cmd(&temp_dir)
+        .args(["db", "migrate", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pending migrations"))
+        .stdout(predicate::str::contains("v1"));
+
+    assert_eq!(schema_version(&temp_dir), 0);
+}
+
+#[test]
+fn test_migrate_backs_up_database() {
+    let temp_dir = fixture_db(&schema_fixtures()[0]);
+
+    todo_cmd(&temp_dir)
+        .args(["db", "migrate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Applied migration v1"))
+        .stderr(predicate::str::contains("Backed up task database"));
+
+    let backups: Vec<PathBuf> = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with(".db.bak"))
+        .collect();
+    assert_eq!(backups.len(), 1);
+
+    let backup_version: u32 = Connection::open(&backups[0])
+        .unwrap()
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(backup_version, 0);
+}
+
+#[test]
+fn test_new_database_is_not_backed_up() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["Task"]);
+
+    let entries = fs::read_dir(temp_dir.path()).unwrap().count();
+    assert_eq!(entries, 1);
+}
+
+// ============================================================================
+// RECURRING TASK TESTS
+// ============================================================================
+
+#[test]
+fn test_complete_recurring_task_creates_next() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(
+        &temp_dir,
+        &[
+            "Weekly review",
+            "--recur",
+            "every 7d",
+            "--deadline",
+            "2026-01-02",
+            "--tags",
+            "work",
+        ],
+    );
+
+    let output = todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Next occurrence"));
+
+    let next = stdout
+        .lines()
+        .last()
+        .unwrap()
+        .split_whitespace()
+        .nth(2)
+        .unwrap();
+
+    let output = todo_cmd(&temp_dir).args(["show", next]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Weekly review"));
+    assert!(stdout.contains("Tags: work"));
+    assert!(stdout.contains("Recurs: every 7d"));
+    assert!(stdout.contains("Series: "));
+    assert!(!stdout.contains("Deadline: 2026-01-02"));
+}
+
+#[test]
+fn test_monthly_recurrence_keeps_day_of_month() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let mut id = add_task(
+        &temp_dir,
+        &["Pay rent", "--recur", "monthly", "--deadline", "2027-01-31"],
+    );
+
+    for deadline in ["2027-02-28", "2027-03-31"] {
+        let output = todo_cmd(&temp_dir)
+            .args(["complete", &id])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        id = stdout
+            .lines()
+            .last()
+            .unwrap()
+            .split_whitespace()
+            .nth(2)
+            .unwrap()
+            .to_string();
+
+        todo_cmd(&temp_dir)
+            .args(["show", &id])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!("Deadline: {deadline}")))
+            .stdout(predicate::str::contains("Recurs: monthly on 31"));
+    }
+}
+
+#[test]
+fn test_add_recurring_task_defaults_deadline() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Water plants", "--recur", "daily"]);
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deadline:"))
+        .stdout(predicate::str::contains("Recurs: daily"));
+}
+
+#[test]
+fn test_add_invalid_recurrence() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["add", "Task", "--recur", "sometimes"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid recurrence rule"));
+}
+
+#[test]
+fn test_complete_non_recurring_task_creates_nothing() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task", "--recur", "daily"]);
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--no-recur"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Next occurrence").not());
+}
+
+#[test]
+fn test_list_series() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Monthly report", "--recur", "monthly on 1"]);
+    add_task(&temp_dir, &["Unrelated"]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    let output = todo_cmd(&temp_dir)
+        .args([
+            "list",
+            "--series",
+            &id,
+            "--columns",
+            "id,task,recurrence,complete",
+        ])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(stdout.matches("Monthly report").count(), 2);
+    assert!(stdout.contains("monthly on 1"));
+    assert!(!stdout.contains("Unrelated"));
+}
+
+#[test]
+fn test_undo_complete_removes_next_occurrence() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Standup", "--recur", "weekdays"]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir).arg("undo").assert().success();
+
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--all", "--columns", "id,task"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("Standup").count(), 1);
+}
+
+#[test]
+fn test_reopened_recurring_task_does_not_duplicate() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Standup", "--recur", "daily"]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir)
+        .args(["incomplete", &id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Next occurrence").not());
+}
+
+// ============================================================================
+// DEPENDENCY TESTS
+// ============================================================================
+
+#[test]
+fn test_next_skips_blocked_tasks() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let design = add_task(&temp_dir, &["Design", "--deadline", "+7d"]);
+    add_task(
+        &temp_dir,
+        &[
+            "Build",
             "--diff",
-            "9",
+            "10",
             "--deadline",
-            "friday",
-            "--tags",
-            "urgent",
+            "today",
+            "--blocked-by",
+            &design,
+        ],
+    );
+
+    todo_cmd(&temp_dir)
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Design"));
+
+    todo_cmd(&temp_dir)
+        .args(["complete", &design])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Build"));
+}
+
+#[test]
+fn test_next_ignores_removed_blockers() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let first = add_task(&temp_dir, &["First", "--deadline", "+7d"]);
+    add_task(
+        &temp_dir,
+        &["Second", "--deadline", "today", "--blocked-by", &first],
+    );
+
+    todo_cmd(&temp_dir)
+        .args(["remove", &first])
+        .assert()
+        .success();
+
+    // A removed blocker no longer blocks.
+    todo_cmd(&temp_dir)
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Second"));
+}
+
+#[test]
+fn test_blocked_by_rejects_cycles() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let a = add_task(&temp_dir, &["A"]);
+    let b = add_task(&temp_dir, &["B", "--blocked-by", &a]);
+    let c = add_task(&temp_dir, &["C", "--blocked-by", &b]);
+
+    todo_cmd(&temp_dir)
+        .args(["update", &a, "--blocked-by", &c])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("dependency cycle"));
+
+    todo_cmd(&temp_dir)
+        .args(["update", &a, "--blocked-by", &a])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("dependency cycle"));
+}
+
+#[test]
+fn test_blocked_by_unknown_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    todo_cmd(&temp_dir)
+        .args(["add", "Task", "--blocked-by", "nonexistent"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No task found"));
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks found"));
+}
+
+#[test]
+fn test_list_and_show_blocked_tasks() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let blocker = add_task(&temp_dir, &["Blocker"]);
+    let id = add_task(&temp_dir, &["Waiting", "--blocked-by", &blocker]);
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--columns", "task,blocked"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Blocked"))
+        .stdout(predicate::str::contains("blocked"));
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Blocked by: {blocker}")));
+
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--blocked-by", ""])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Blocked by").not());
+}
+
+#[test]
+fn test_undo_restores_dependencies() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let blocker = add_task(&temp_dir, &["Blocker"]);
+    let id = add_task(&temp_dir, &["Waiting", "--blocked-by", &blocker]);
+
+    todo_cmd(&temp_dir)
+        .args(["remove", &blocker])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir)
+        .args(["trash", "empty", "--force"])
+        .assert()
+        .success();
+    todo_cmd(&temp_dir)
+        .args(["undo", "-n", "2"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Blocked by: {blocker}")));
+
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--blocked-by", ""])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["log", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Blocked by: {blocker} → (none)"
+        )));
+}
+
+#[test]
+fn test_export_import_keeps_dependencies() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let blocker = add_task(&temp_dir, &["Blocker"]);
+    let id = add_task(&temp_dir, &["Waiting", "--blocked-by", &blocker]);
+
+    let output = todo_cmd(&temp_dir).arg("export").output().unwrap();
+    let export_file = temp_dir.path().join("tasks.json");
+    fs::write(&export_file, output.stdout).unwrap();
+
+    let other_dir = TempDir::new().unwrap();
+    todo_cmd(&other_dir)
+        .args(["import", export_file.to_str().unwrap()])
+        .assert()
+        .success();
+
+    todo_cmd(&other_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Blocked by: {blocker}")));
+}
+
+// ============================================================================
+// STATUS COMMAND TESTS
+// ============================================================================
+
+#[test]
+fn test_start_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Write report"]);
+    add_task(&temp_dir, &["Other task"]);
+
+    todo_cmd(&temp_dir)
+        .args(["start", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("marked as in progress"));
+
+    let output = todo_cmd(&temp_dir)
+        .args([
+            "list",
+            "--status",
+            "in-progress",
+            "--columns",
+            "task,status",
         ])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Write report"));
+    assert!(stdout.contains("in-progress"));
+    assert!(!stdout.contains("Other task"));
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Status: "));
+}
+
+#[test]
+fn test_next_prefers_in_progress_tasks() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(
+        &temp_dir,
+        &["Urgent", "--diff", "10", "--deadline", "today"],
+    );
+    let id = add_task(&temp_dir, &["Started", "--deadline", "+7d"]);
+
+    todo_cmd(&temp_dir).args(["start", &id]).assert().success();
+
+    todo_cmd(&temp_dir)
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Started"));
+}
+
+#[test]
+fn test_next_skips_waiting_tasks() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Waiting on review", "--deadline", "today"]);
+    add_task(&temp_dir, &["Something else", "--deadline", "+7d"]);
+
+    todo_cmd(&temp_dir)
+        .args(["wait", &id, "--until", "+3d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("waiting until"));
+
+    todo_cmd(&temp_dir)
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Something else"));
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--status", "waiting", "--columns", "task"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Waiting on review"));
+}
+
+#[test]
+fn test_waiting_task_returns_to_todo() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Waiting on review"]);
+
+    todo_cmd(&temp_dir)
+        .args(["wait", &id, "--until", "2000-01-01"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--status", "todo", "--columns", "task"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Waiting on review"));
+}
+
+#[test]
+fn test_stop_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir).args(["wait", &id]).assert().success();
+
+    todo_cmd(&temp_dir)
+        .args(["stop", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("back to todo"));
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--status", "waiting"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks found"));
+
+    todo_cmd(&temp_dir)
+        .args(["log", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Status: waiting → todo"));
+}
+
+#[test]
+fn test_start_completed_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
         .assert()
         .success();
+
+    todo_cmd(&temp_dir)
+        .args(["start", &id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is completed"));
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--status", "done", "--columns", "task,status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("done"));
 }
 
 #[test]
-fn test_update_nonexistent_task() {
+fn test_undo_start() {
     let temp_dir = TempDir::new().unwrap();
 
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir).args(["start", &id]).assert().success();
+
     todo_cmd(&temp_dir)
-        .args(["update", "nonexistent", "--task", "New"])
+        .arg("undo")
         .assert()
-        .failure();
+        .success()
+        .stdout(predicate::str::contains("start"));
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--status", "in-progress"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks found"));
 }
 
 // ============================================================================
-// NEXT COMMAND TESTS
+// DEFER TESTS
 // ============================================================================
 
 #[test]
-fn test_next_with_no_tasks() {
+fn test_defer_hides_task() {
     let temp_dir = TempDir::new().unwrap();
 
-    todo_cmd(&temp_dir).arg("next").assert().failure();
+    let id = add_task(&temp_dir, &["Renew passport"]);
+    add_task(&temp_dir, &["Visible"]);
+
+    todo_cmd(&temp_dir)
+        .args(["defer", &id, "+7d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deferred until"));
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Visible"))
+        .stdout(predicate::str::contains("Renew passport").not());
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--include-deferred", "--columns", "task,scheduled"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Renew passport"))
+        .stdout(predicate::str::contains("Scheduled"));
+
+    todo_cmd(&temp_dir)
+        .args(["show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Scheduled:"));
 }
 
 #[test]
-fn test_next_shows_highest_priority() {
+fn test_next_skips_deferred_task() {
     let temp_dir = TempDir::new().unwrap();
 
-    add_task(
-        &temp_dir,
-        &["Low priority", "--diff", "1", "--deadline", "+7d"],
-    );
-    add_task(
-        &temp_dir,
-        &["High priority", "--diff", "10", "--deadline", "tomorrow"],
-    );
+    add_task(&temp_dir, &["Hard", "--diff", "10", "--scheduled", "+7d"]);
+    add_task(&temp_dir, &["Easy", "--diff", "1"]);
 
     todo_cmd(&temp_dir)
         .arg("next")
         .assert()
         .success()
-        .stdout(predicate::str::contains("High priority"));
+        .stdout(predicate::str::contains("Task: Easy"));
+
+    todo_cmd(&temp_dir)
+        .args(["next", "--include-deferred"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task: Hard"));
 }
 
 #[test]
-fn test_next_ignores_completed() {
+fn test_plan_includes_tasks_deferred_to_the_day() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id1 = add_task(
-        &temp_dir,
-        &["Urgent", "--diff", "10", "--deadline", "today"],
-    );
     add_task(
         &temp_dir,
-        &["Less urgent", "--diff", "5", "--deadline", "+7d"],
+        &["Tomorrow", "--diff", "2", "--scheduled", "tomorrow"],
+    );
+    add_task(&temp_dir, &["Later", "--diff", "2", "--scheduled", "+7d"]);
+
+    let output = todo_cmd(&temp_dir)
+        .args(["plan", "tomorrow", "--yes"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Tomorrow"));
+    assert!(!stdout.contains("Later"));
+
+    todo_cmd(&temp_dir)
+        .args(["plan", "today", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing planned"));
+
+    todo_cmd(&temp_dir)
+        .args(["plan", "--budget", "0"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_scheduled_today_is_visible() {
+    let temp_dir = TempDir::new().unwrap();
+
+    add_task(&temp_dir, &["Today", "--scheduled", "today"]);
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Today"));
+}
+
+#[test]
+fn test_defer_clear_and_undo() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task", "--scheduled", "+2w"]);
+
+    todo_cmd(&temp_dir)
+        .args(["defer", &id, "--clear"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("no longer deferred"));
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task"));
+
+    todo_cmd(&temp_dir).arg("undo").assert().success();
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks found"));
+}
+
+#[test]
+fn test_defer_requires_date() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir).args(["defer", &id]).assert().failure();
+
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["defer", &id, "monday"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is completed"));
+}
+
+#[test]
+fn test_update_scheduled() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--scheduled", "+3d"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks found"));
+
+    // Updating another field keeps the scheduled date.
+    todo_cmd(&temp_dir)
+        .args(["update", &id, "--diff", "4"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No tasks found"));
+}
+
+#[test]
+fn test_scheduled_export_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let export = temp_dir.path().join("tasks.json");
+
+    add_task(&temp_dir, &["Deferred", "--scheduled", "2099-01-01"]);
+
+    todo_cmd(&temp_dir)
+        .args(["export", "--output", export.to_str().unwrap()])
+        .assert()
+        .success();
+    assert!(
+        fs::read_to_string(&export)
+            .unwrap()
+            .contains(r#""scheduled": "2099-01-01""#)
     );
 
     todo_cmd(&temp_dir)
-        .args(["complete", &id1])
+        .args(["import", export.to_str().unwrap(), "--mode", "replace"])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["list", "--include-deferred", "--columns", "task,scheduled"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2099-01-01"));
+}
+
+// ============================================================================
+// TIME TRACKING TESTS
+// ============================================================================
+
+#[test]
+fn test_track_start_and_stop() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Write report", "--tags", "work"]);
+
+    todo_cmd(&temp_dir)
+        .args(["track", "start", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Started timer"))
+        .stdout(predicate::str::contains("Write report"));
+
+    todo_cmd(&temp_dir)
+        .args(["track", "stop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Stopped timer"))
+        .stdout(predicate::str::contains("after 0m"));
+
+    todo_cmd(&temp_dir)
+        .args(["track", "stop"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No timer is running"));
+}
+
+#[test]
+fn test_track_refuses_two_timers() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let first = add_task(&temp_dir, &["First"]);
+    let second = add_task(&temp_dir, &["Second"]);
+
+    todo_cmd(&temp_dir)
+        .args(["track", "start", &first])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["track", "start", &second])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already running"))
+        .stderr(predicate::str::contains(&first));
+}
+
+#[test]
+fn test_track_completed_task() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir)
+        .args(["complete", &id])
+        .assert()
+        .success();
+
+    todo_cmd(&temp_dir)
+        .args(["track", "start", &id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is completed"));
+}
+
+#[test]
+fn test_track_report() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let work = add_task(&temp_dir, &["Write report", "--tags", "work"]);
+    let home = add_task(&temp_dir, &["Fix sink", "--tags", "home"]);
+
+    for id in [&work, &home] {
+        todo_cmd(&temp_dir)
+            .args(["track", "start", id])
+            .assert()
+            .success();
+        todo_cmd(&temp_dir)
+            .args(["track", "stop"])
+            .assert()
+            .success();
+    }
+
+    todo_cmd(&temp_dir)
+        .args(["track", "report", "--after", "mon", "--before", "eow"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Write report"))
+        .stdout(predicate::str::contains("Fix sink"))
+        .stdout(predicate::str::contains("Total"))
+        .stdout(predicate::str::contains("work"))
+        .stdout(predicate::str::contains("home"));
+
+    let output = todo_cmd(&temp_dir)
+        .args(["track", "report", "--tags", "work"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Write report"));
+    assert!(!stdout.contains("Fix sink"));
+    assert!(!stdout.contains("home"));
+
+    todo_cmd(&temp_dir)
+        .args(["track", "report", "--before", "2020-01-01"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No time tracked"));
+}
+
+#[test]
+fn test_tracked_column() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let id = add_task(&temp_dir, &["Task"]);
+    todo_cmd(&temp_dir)
+        .args(["track", "start", &id])
         .assert()
         .success();
 
     todo_cmd(&temp_dir)
-        .arg("next")
+        .args(["list", "--columns", "task,tracked"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Less urgent"));
+        .stdout(predicate::str::contains("Tracked"));
 }
 
 // ============================================================================
-// REMOVE COMMAND TESTS
+// URGENCY TESTS
 // ============================================================================
 
 #[test]
-fn test_remove_single_task() {
+fn test_next_surfaces_difficult_task_without_deadline() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task"]);
+    add_task(&temp_dir, &["Distant", "--deadline", "+30d"]);
+    add_task(&temp_dir, &["Hard", "--diff", "10"]);
 
     todo_cmd(&temp_dir)
-        .args(["remove", &id])
+        .arg("next")
         .assert()
         .success()
-        .stdout(predicate::str::contains("Removed 1 task"));
+        .stdout(predicate::str::contains("Hard"));
 }
 
 #[test]
-fn test_remove_multiple_tasks() {
+fn test_list_orders_by_urgency() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id1 = add_task(&temp_dir, &["Task 1"]);
-    let id2 = add_task(&temp_dir, &["Task 2"]);
+    add_task(&temp_dir, &["Distant", "--deadline", "+30d"]);
+    add_task(&temp_dir, &["Due", "--deadline", "today"]);
+    add_task(&temp_dir, &["Hard", "--diff", "10"]);
 
-    todo_cmd(&temp_dir)
-        .args(["remove", &id1, &id2])
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("Removed 2 task"));
+    let output = todo_cmd(&temp_dir)
+        .args(["list", "--columns", "task,urgency"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("Urgency"));
+    let due = stdout.find("Due").unwrap();
+    let hard = stdout.find("Hard").unwrap();
+    let distant = stdout.find("Distant").unwrap();
+    assert!(due < hard && hard < distant);
 }
 
 #[test]
-fn test_remove_by_tags() {
+fn test_next_explain() {
     let temp_dir = TempDir::new().unwrap();
 
-    add_task(&temp_dir, &["Task 1", "--tags", "remove"]);
-    add_task(&temp_dir, &["Task 2", "--tags", "remove"]);
-    add_task(&temp_dir, &["Task 3", "--tags", "keep"]);
+    add_task(&temp_dir, &["Task", "--diff", "5", "--deadline", "today"]);
 
     todo_cmd(&temp_dir)
-        .args(["remove", "--tags", "remove"])
+        .args(["next", "--explain"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Removed 2 task"));
-
-    // Verify the "keep" task is still there
-    todo_cmd(&temp_dir)
-        .arg("list")
-        .assert()
-        .stdout(predicate::str::contains("Task 3"));
+        .stdout(predicate::str::contains("Factor"))
+        .stdout(predicate::str::contains("deadline"))
+        .stdout(predicate::str::contains("difficulty"))
+        .stdout(predicate::str::contains("14.50"));
 }
 
 #[test]
-fn test_remove_alias() {
+fn test_next_explain_several_tasks() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task"]);
+    add_task(&temp_dir, &["Due", "--diff", "5", "--deadline", "today"]);
+    add_task(&temp_dir, &["Hard", "--diff", "9"]);
 
-    todo_cmd(&temp_dir).args(["rm", &id]).assert().success();
-}
+    let output = todo_cmd(&temp_dir)
+        .args(["next", "-n", "2", "--explain"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("Factor").count(), 2);
+    assert!(stdout.contains("14.50"));
 
-#[test]
-fn test_remove_nonexistent_task() {
-    let temp_dir = TempDir::new().unwrap();
+    todo_cmd(&temp_dir)
+        .args(["next", "-n", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No incomplete tasks").not());
 
     todo_cmd(&temp_dir)
-        .args(["remove", "nonexistent"])
+        .args(["next", "--columns", "task,urgency"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Removed 0 task"));
+        .stdout(predicate::str::contains("Urgency"))
+        .stdout(predicate::str::contains("Due"))
+        .stdout(predicate::str::contains("Hard").not());
 }
 
 #[test]
-fn test_remove_partial_id() {
+fn test_urgency_config() {
     let temp_dir = TempDir::new().unwrap();
 
-    let id = add_task(&temp_dir, &["Task"]);
-    let partial_id = &id[..3];
-
-    todo_cmd(&temp_dir)
-        .args(["remove", partial_id])
-        .assert()
-        .success();
-}
+    add_task(&temp_dir, &["Due", "--deadline", "today"]);
+    add_task(&temp_dir, &["Flagged", "--tags", "urgent"]);
 
-#[test]
-fn test_remove_requires_id_or_tags() {
-    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("config.json"),
+        r#"{"urgency": {"deadline": 1.0, "tags": {"urgent": 10.0}}}"#,
+    )
+    .unwrap();
 
     todo_cmd(&temp_dir)
-        .arg("remove")
+        .args(["next", "--explain"])
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("required"));
+        .success()
+        .stdout(predicate::str::contains("Flagged"))
+        .stdout(predicate::str::contains("tag urgent"));
 }
 
-// ============================================================================
-// CLEAR COMMAND TESTS
-// ============================================================================
-
 #[test]
-fn test_clear_with_force() {
+fn test_invalid_config() {
     let temp_dir = TempDir::new().unwrap();
 
-    add_task(&temp_dir, &["Task 1"]);
-    add_task(&temp_dir, &["Task 2"]);
+    fs::write(
+        temp_dir.path().join("config.json"),
+        r#"{"urgency": {"deadlines": 1.0}}"#,
+    )
+    .unwrap();
 
     todo_cmd(&temp_dir)
-        .args(["clear", "--force"])
+        .arg("next")
         .assert()
-        .success();
-
-    // Verify all tasks are gone
-    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
-
-    let stdout = String::from_utf8(output.stdout).unwrap();
-    assert!(!stdout.contains("Task 1"));
-    assert!(!stdout.contains("Task 2"));
+        .failure()
+        .stderr(predicate::str::contains("Invalid config file"));
 }
 
-// ============================================================================
-// INTEGRATION TESTS
-// ============================================================================
-
 #[test]
-fn test_full_task_lifecycle() {
+fn test_next_with_filters() {
     let temp_dir = TempDir::new().unwrap();
 
-    // Add a task
-    let id = add_task(
+    add_task(
         &temp_dir,
-        &[
-            "Complete project",
-            "--desc",
-            "Finish the todo app",
-            "--diff",
-            "8",
-            "--deadline",
-            "friday",
-            "--tags",
-            "work,coding",
-        ],
+        &["Home chore", "--tags", "home", "--deadline", "today"],
+    );
+    add_task(
+        &temp_dir,
+        &["Work item", "--tags", "work", "--deadline", "+3d"],
+    );
+    add_task(
+        &temp_dir,
+        &["Later work", "--tags", "work", "--deadline", "+30d"],
     );
 
-    // Verify it appears in list
-    todo_cmd(&temp_dir)
-        .arg("list")
-        .assert()
-        .stdout(predicate::str::contains("Complete project"));
-
-    // Update it
-    todo_cmd(&temp_dir)
-        .args(["update", &id, "--diff", "6"])
-        .assert()
-        .success();
-
-    // Show details
     todo_cmd(&temp_dir)
-        .args(["show", &id])
+        .args(["next", "--tags", "work"])
         .assert()
-        .stdout(predicate::str::contains("6"));
+        .success()
+        .stdout(predicate::str::contains("Work item"));
 
-    // Complete it
     todo_cmd(&temp_dir)
-        .args(["complete", &id])
+        .args(["next", "--after", "+10d"])
         .assert()
-        .success();
-
-    // Verify it's not in default list
-    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
-    let stdout = String::from_utf8(output.stdout).unwrap();
-    assert!(!stdout.contains("Complete project") || stdout.contains("No tasks"));
+        .success()
+        .stdout(predicate::str::contains("Later work"));
 
-    // Verify it appears with --completed flag
     todo_cmd(&temp_dir)
-        .args(["list", "--completed"])
+        .args(["next", "--tags", "missing"])
         .assert()
-        .stdout(predicate::str::contains("Complete project"));
+        .failure()
+        .stderr(predicate::str::contains("No incomplete tasks found"));
 }
 
 #[test]
-fn test_hierarchical_tasks() {
+fn test_next_shortlist() {
     let temp_dir = TempDir::new().unwrap();
 
-    // Create parent task
-    let parent_id = add_task(&temp_dir, &["Main project"]);
-
-    // Create subtasks
-    let subtask1_id = add_task(&temp_dir, &["Subtask 1", "--pid", &parent_id]);
-    let subtask2_id = add_task(&temp_dir, &["Subtask 2", "--pid", &parent_id]);
+    add_task(&temp_dir, &["First", "--deadline", "today"]);
+    add_task(&temp_dir, &["Second", "--deadline", "+3d"]);
+    add_task(&temp_dir, &["Third", "--deadline", "+30d"]);
 
-    // List tasks by parent
     let output = todo_cmd(&temp_dir)
-        .args(["list", "--pid", &parent_id])
+        .args(["next", "-n", "2", "--columns", "task,urgency"])
         .output()
         .unwrap();
-
     let stdout = String::from_utf8(output.stdout).unwrap();
-    assert!(stdout.contains("Subtask 1"));
-    assert!(stdout.contains("Subtask 2"));
 
-    // Complete subtasks
+    assert!(stdout.contains("Urgency"));
+    assert!(stdout.find("First").unwrap() < stdout.find("Second").unwrap());
+    assert!(!stdout.contains("Third"));
+
     todo_cmd(&temp_dir)
-        .args(["complete", &subtask1_id])
+        .args(["next", "-n", "5", "--view", "minimal"])
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::contains("Third"))
+        .stdout(predicate::str::contains("Urgency").not());
+}
+
+#[test]
+fn test_next_skip_parents() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let parent = add_task(&temp_dir, &["Parent", "--deadline", "today"]);
+    add_task(&temp_dir, &["Child", "--pid", &parent, "--deadline", "+7d"]);
+
     todo_cmd(&temp_dir)
-        .args(["complete", &subtask2_id])
+        .arg("next")
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::contains("Task: Parent"));
 
-    // Complete parent
     todo_cmd(&temp_dir)
-        .args(["complete", &parent_id])
+        .args(["next", "--skip-parents"])
         .assert()
-        .success();
+        .success()
+        .stdout(predicate::str::contains("Task: Child"));
 }
 
+// ============================================================================
+// PLAN TESTS
+// ============================================================================
+
 #[test]
-fn test_tag_filtering() {
+fn test_plan_fills_budget_in_priority_order() {
     let temp_dir = TempDir::new().unwrap();
 
-    add_task(&temp_dir, &["Work task 1", "--tags", "work"]);
-    add_task(&temp_dir, &["Work task 2", "--tags", "work,urgent"]);
-    add_task(&temp_dir, &["Personal task", "--tags", "personal"]);
-    add_task(&temp_dir, &["Urgent personal", "--tags", "personal,urgent"]);
+    add_task(&temp_dir, &["Urgent", "--diff", "9", "--deadline", "today"]);
+    add_task(&temp_dir, &["Big", "--diff", "8", "--deadline", "+3d"]);
+    add_task(&temp_dir, &["Small", "--diff", "5"]);
 
-    // Filter by single tag
     let output = todo_cmd(&temp_dir)
-        .args(["list", "--tags", "work"])
+        .args(["plan", "today", "--yes"])
         .output()
         .unwrap();
     let stdout = String::from_utf8(output.stdout).unwrap();
-    assert!(stdout.contains("Work task"));
 
-    // Filter by multiple tags
-    let output = todo_cmd(&temp_dir)
-        .args(["list", "--tags", "urgent"])
-        .output()
-        .unwrap();
-    let stdout = String::from_utf8(output.stdout).unwrap();
-    assert!(stdout.contains("Work task 2"));
-    assert!(stdout.contains("Urgent personal"));
+    assert!(stdout.contains("Urgent"));
+    assert!(!stdout.contains("Big"));
+    assert!(stdout.contains("Small"));
+    assert!(stdout.contains("Planned 14 of 15 points"));
+
+    todo_cmd(&temp_dir)
+        .args(["plan", "--show"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Urgent"))
+        .stdout(predicate::str::contains("Small"))
+        .stdout(predicate::str::contains("Big").not());
 }
 
 #[test]
-fn test_priority_ordering() {
+fn test_plan_budget() {
     let temp_dir = TempDir::new().unwrap();
 
-    // Add tasks with different priorities
-    add_task(&temp_dir, &["Low", "--diff", "2", "--deadline", "+30d"]);
-    add_task(&temp_dir, &["Medium", "--diff", "5", "--deadline", "+7d"]);
-    add_task(
-        &temp_dir,
-        &["High", "--diff", "9", "--deadline", "tomorrow"],
-    );
+    add_task(&temp_dir, &["Unrated"]);
+    add_task(&temp_dir, &["Rated", "--diff", "4"]);
 
-    // Next should show the highest priority
     todo_cmd(&temp_dir)
-        .arg("next")
+        .args(["plan", "--yes", "--budget", "3"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("High"));
-}
-
-#[test]
-fn test_custom_database_path() {
-    let temp_dir = TempDir::new().unwrap();
+        .stdout(predicate::str::contains("Unrated"))
+        .stdout(predicate::str::contains("Rated").not());
 
-    let id = add_task(&temp_dir, &["Task in custom location"]);
+    fs::write(
+        temp_dir.path().join("config.json"),
+        r#"{"plan": {"budget": 4, "unrated": 1}}"#,
+    )
+    .unwrap();
 
-    // Verify task is accessible with custom path
     todo_cmd(&temp_dir)
-        .args(["show", &id])
+        .args(["plan", "--yes"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Task in custom location"));
+        .stdout(predicate::str::contains("Planned 4 of 4 points"));
 }
 
 #[test]
-fn test_partial_id_ambiguity() {
+fn test_plan_warns_when_due_tasks_exceed_budget() {
     let temp_dir = TempDir::new().unwrap();
 
-    // This test assumes IDs might conflict - if your ID generation
-    // makes this impossible, you can skip this test
-    let id1 = add_task(&temp_dir, &["Task 1"]);
-    let _id2 = add_task(&temp_dir, &["Task 2"]);
+    add_task(&temp_dir, &["First", "--diff", "10", "--deadline", "today"]);
+    add_task(
+        &temp_dir,
+        &["Second", "--diff", "10", "--deadline", "today"],
+    );
 
-    // Using full ID should always work
-    todo_cmd(&temp_dir).args(["show", &id1]).assert().success();
+    todo_cmd(&temp_dir)
+        .args(["plan", "--yes"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("add up to 20 points"));
 }
 
 #[test]
-fn test_deadline_sorting() {
+fn test_plan_show_without_plan() {
     let temp_dir = TempDir::new().unwrap();
 
-    add_task(&temp_dir, &["Due later", "--deadline", "+14d"]);
-    add_task(&temp_dir, &["Due soon", "--deadline", "tomorrow"]);
-    add_task(&temp_dir, &["Due today", "--deadline", "today"]);
-
-    let output = todo_cmd(&temp_dir).arg("list").output().unwrap();
-
-    let stdout = String::from_utf8(output.stdout).unwrap();
-
-    // Earlier deadlines should appear first
-    let today_pos = stdout.find("Due today");
-    let soon_pos = stdout.find("Due soon");
-    let later_pos = stdout.find("Due later");
+    add_task(&temp_dir, &["Task"]);
 
-    assert!(today_pos.is_some());
-    assert!(soon_pos.is_some());
-    assert!(later_pos.is_some());
-    assert!(today_pos < soon_pos);
-    assert!(soon_pos < later_pos);
+    todo_cmd(&temp_dir)
+        .args(["plan", "tomorrow", "--show"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing planned"));
 }